@@ -10,14 +10,23 @@
 //! - Character-count fallback (1 token ~ 4 chars) when token data unavailable
 //! - Observation masking replaces tool output content with summary placeholders
 //!   while preserving message structure (never removes messages)
+//! - Masking picks superseded and large observations before old small ones,
+//!   subject to per-tool `[context.mask_policies]`
+//! - The `[context] strategy` decides what the soft threshold does: mask,
+//!   summarize old turns (see [`crate::agent::summarize`]), or both
 
-use genai::chat::{ChatMessage, ChatRole, MessageContent, ToolResponse};
+use std::collections::HashMap;
+
+use genai::chat::{ChatMessage, ChatRole, MessageContent, ToolCall, ToolResponse};
+
+use crate::agent::observations::{ObservationStore, StoredObservation};
+use crate::config::{ContextStrategy, MaskPolicy};
 
 /// Substring used to detect already-masked observations in tool response content.
 const MASKED_MARKER: &str = "masked";
 
 /// Default number of observations to mask per evaluation round.
-const DEFAULT_MASK_BATCH_SIZE: usize = 3;
+pub const DEFAULT_MASK_BATCH_SIZE: usize = 3;
 
 // ---------------------------------------------------------------------------
 // ContextAction
@@ -28,8 +37,11 @@ const DEFAULT_MASK_BATCH_SIZE: usize = 3;
 pub enum ContextAction {
     /// Context is healthy -- proceed normally.
     Continue,
-    /// Soft threshold hit -- mask `count` oldest unmasked observations.
+    /// Soft threshold hit -- mask `count` unmasked observations.
     Mask { count: usize },
+    /// Soft threshold hit with the summarize strategy -- replace the oldest
+    /// `turns` turns with a model-written summary.
+    Summarize { turns: usize },
     /// Hard threshold hit for the first time -- tell the agent to wrap up.
     WindDown,
     /// Hard threshold hit again after wind-down -- restart session.
@@ -40,7 +52,7 @@ pub enum ContextAction {
 // MaskResult
 // ---------------------------------------------------------------------------
 
-/// Outcome of a mask_oldest_observations call.
+/// Outcome of a mask_observations call.
 #[derive(Debug, PartialEq)]
 pub struct MaskResult {
     /// Number of observations masked in this round.
@@ -78,6 +90,14 @@ pub struct ContextManager {
     turn_count: u64,
     /// Whether a WindDown action has already been sent.
     wind_down_sent: bool,
+    /// What the soft threshold does (masking unless configured otherwise).
+    strategy: ContextStrategy,
+    /// Turns folded into one summary by the summarize strategy.
+    summarize_turns: usize,
+    /// Per-tool masking rules, keyed by tool name.
+    mask_policies: HashMap<String, MaskPolicy>,
+    /// Where originals are saved before masking, for `recall_observation`.
+    observation_store: Option<ObservationStore>,
 }
 
 impl ContextManager {
@@ -102,9 +122,32 @@ impl ContextManager {
             session_number: 1,
             turn_count: 0,
             wind_down_sent: false,
+            strategy: ContextStrategy::Mask,
+            summarize_turns: 10,
+            mask_policies: HashMap::new(),
+            observation_store: None,
         }
     }
 
+    /// Set the soft-threshold compression strategy.
+    pub fn with_strategy(mut self, strategy: ContextStrategy, summarize_turns: usize) -> Self {
+        self.strategy = strategy;
+        self.summarize_turns = summarize_turns;
+        self
+    }
+
+    /// Set the per-tool masking rules.
+    pub fn with_mask_policies(mut self, mask_policies: HashMap<String, MaskPolicy>) -> Self {
+        self.mask_policies = mask_policies;
+        self
+    }
+
+    /// Save masked observations' originals to `store` so they can be recalled.
+    pub fn with_observation_store(mut self, store: ObservationStore) -> Self {
+        self.observation_store = Some(store);
+        self
+    }
+
     // -- Token tracking -----------------------------------------------------
 
     /// Update token usage from the latest Ollama response.
@@ -117,6 +160,15 @@ impl ContextManager {
         self.completion_tokens_total += completion_tokens;
     }
 
+    /// Replace the context size with an estimate of the full conversation.
+    ///
+    /// Used when the provider reported no usage and after masking or
+    /// summarization, when the last reported count is stale. The next
+    /// reported `prompt_tokens` overrides it.
+    pub fn update_estimated_usage(&mut self, estimated_tokens: usize) {
+        self.prompt_tokens = estimated_tokens;
+    }
+
     /// Add characters to the fallback counter.
     ///
     /// Used when token data is unavailable; the heuristic 1 token ~ 4 chars
@@ -146,6 +198,8 @@ impl ContextManager {
     /// 2. >= hard_threshold AND wind_down NOT sent => WindDown (sets flag)
     /// 3. >= soft_threshold => Mask { count: DEFAULT_MASK_BATCH_SIZE }
     /// 4. Otherwise => Continue
+    ///
+    /// Under the summarize strategy, step 3 returns Summarize { turns } instead.
     pub fn evaluate(&mut self) -> ContextAction {
         let pct = self.usage_percentage();
 
@@ -158,8 +212,13 @@ impl ContextManager {
         }
 
         if pct >= self.soft_threshold_pct {
-            return ContextAction::Mask {
-                count: DEFAULT_MASK_BATCH_SIZE,
+            return match self.strategy {
+                ContextStrategy::Summarize => ContextAction::Summarize {
+                    turns: self.summarize_turns,
+                },
+                ContextStrategy::Mask | ContextStrategy::Hybrid => ContextAction::Mask {
+                    count: DEFAULT_MASK_BATCH_SIZE,
+                },
             };
         }
 
@@ -211,6 +270,34 @@ impl ContextManager {
     pub fn carryover_turns(&self) -> usize {
         self.carryover_turns
     }
+
+    // -- Masking policy -----------------------------------------------------
+
+    /// True if any tool response in `messages` still has its original content
+    /// and may be masked under its tool's policy now.
+    pub fn has_maskable_observations(&self, messages: &[ChatMessage]) -> bool {
+        mask_candidates(messages)
+            .iter()
+            .any(|candidate| self.may_mask(candidate))
+    }
+
+    /// The masking policy for `fn_name` (the default if none is configured).
+    fn mask_policy(&self, fn_name: &str) -> &MaskPolicy {
+        static DEFAULT_POLICY: MaskPolicy = MaskPolicy {
+            never: false,
+            after_turns: None,
+            keep_head_lines: 0,
+            keep_tail_lines: 0,
+            max_retained_bytes: None,
+        };
+        self.mask_policies.get(fn_name).unwrap_or(&DEFAULT_POLICY)
+    }
+
+    /// Whether the candidate's tool policy allows masking it yet.
+    fn may_mask(&self, candidate: &MaskCandidate) -> bool {
+        let policy = self.mask_policy(&candidate.fn_name);
+        !policy.never && policy.after_turns.is_none_or(|turns| candidate.age_turns >= turns)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -220,9 +307,16 @@ impl ContextManager {
 /// Generate a descriptive summary placeholder for a masked tool observation.
 ///
 /// The placeholder preserves enough information for the agent to understand
-/// what the original output contained without the full content.
-pub fn generate_placeholder(fn_name: &str, original_content: &str) -> String {
-    match fn_name {
+/// what the original output contained without the full content. When the
+/// original was saved in session `saved_in`, it also names the `call_id` and
+/// session under which `recall_observation` can fetch it back.
+pub fn generate_placeholder(
+    fn_name: &str,
+    call_id: &str,
+    saved_in: Option<&str>,
+    original_content: &str,
+) -> String {
+    let summary = match fn_name {
         "file_read" => {
             let line_count = original_content.lines().count();
             let first_line = original_content.lines().next().unwrap_or("");
@@ -232,7 +326,7 @@ pub fn generate_placeholder(fn_name: &str, original_content: &str) -> String {
                 first_line.to_string()
             };
             format!(
-                "[file_read result masked -- {} lines, starts with: {}]",
+                "file_read result masked -- {} lines, starts with: {}",
                 line_count, first_line_display
             )
         }
@@ -249,29 +343,35 @@ pub fn generate_placeholder(fn_name: &str, original_content: &str) -> String {
                     .map(|s| s.len())
                     .unwrap_or(0);
                 format!(
-                    "[shell_exec result masked -- exit_code={}, stdout={} bytes]",
+                    "shell_exec result masked -- exit_code={}, stdout={} bytes",
                     exit_code, stdout_len
                 )
             } else {
                 format!(
-                    "[shell_exec result masked -- {} bytes of output]",
+                    "shell_exec result masked -- {} bytes of output",
                     original_content.len()
                 )
             }
         }
         "file_write" => {
             format!(
-                "[file_write result masked -- {} bytes]",
+                "file_write result masked -- {} bytes",
                 original_content.len()
             )
         }
         _ => {
             format!(
-                "[{} result masked -- {} bytes of output]",
+                "{} result masked -- {} bytes of output",
                 fn_name,
                 original_content.len()
             )
         }
+    };
+    match saved_in {
+        Some(session) if !call_id.is_empty() => {
+            format!("[{summary}; call_id={call_id}, session={session}, recall_observation to view]")
+        }
+        _ => format!("[{summary}]"),
     }
 }
 
@@ -282,83 +382,115 @@ pub fn is_already_masked(content: &str) -> bool {
     content.starts_with('[') && content.contains(MASKED_MARKER)
 }
 
-/// Walk `messages` from oldest to newest, masking up to `count` unmasked
-/// tool response observations by replacing their content with summary
-/// placeholders.
+
+/// Tool output at least this large is masked before smaller output.
+const LARGE_OBSERVATION_BYTES: usize = 4096;
+
+/// An unmasked tool observation that may be masked this round.
+struct MaskCandidate {
+    /// Index of the Tool-role message.
+    index: usize,
+    call_id: String,
+    fn_name: String,
+    bytes: usize,
+    /// Assistant turns since the observation was produced.
+    age_turns: u64,
+    /// A later call read, rewrote, or re-ran the same target.
+    superseded: bool,
+}
+
+impl MaskCandidate {
+    /// Sort key; candidates with lower keys are masked first.
+    ///
+    /// Superseded observations go first (oldest first), then large ones
+    /// (largest first), then everything else (oldest first).
+    fn priority(&self) -> (u8, usize, usize) {
+        if self.superseded {
+            (0, 0, self.index)
+        } else if self.bytes >= LARGE_OBSERVATION_BYTES {
+            (1, usize::MAX - self.bytes, self.index)
+        } else {
+            (2, 0, self.index)
+        }
+    }
+}
+
+/// Mask up to `count` unmasked tool observations, least valuable first,
+/// replacing their content with summary placeholders.
+///
+/// Originals are saved to the observation store first (if one is set), so
+/// the agent can recall them by the call_id and session the placeholder names.
+///
+/// Observations whose target was later read, rewritten, or re-run are masked
+/// first, then large ones, then the rest oldest-first. The context manager's
+/// per-tool [`MaskPolicy`] can exempt a tool entirely, delay masking until
+/// its output is old enough, or keep some of the output beside the
+/// placeholder.
 ///
 /// Returns a `MaskResult` describing how many were masked this round and
 /// the running total. The `context_manager` masked_count is updated.
 ///
 /// IMPORTANT: Messages are never removed -- only their content is replaced.
 /// This preserves the tool call/response chain that providers expect.
-pub fn mask_oldest_observations(
-    messages: &mut Vec<ChatMessage>,
+pub fn mask_observations(
+    messages: &mut [ChatMessage],
     count: usize,
     context_manager: &mut ContextManager,
 ) -> MaskResult {
-    let mut masked_this_round = 0;
-
-    // First pass: collect (index, call_id) pairs for Tool-role messages
-    // that are not yet masked.
-    let tool_msg_indices: Vec<(usize, String)> = messages
-        .iter()
-        .enumerate()
-        .filter_map(|(i, msg)| {
-            if msg.role != ChatRole::Tool {
-                return None;
-            }
-            // Extract the ToolResponse content from the message.
-            let tool_responses = msg.content.tool_responses();
-            if let Some(tr) = tool_responses.first() {
-                if is_already_masked(&tr.content) {
-                    return None;
-                }
-                Some((i, tr.call_id.clone()))
-            } else {
-                // Fallback: check if it's a text-content tool message (unlikely
-                // but defensively handle it).
-                if let Some(text) = msg.content.first_text() {
-                    if is_already_masked(text) {
-                        return None;
-                    }
-                }
-                Some((i, String::new()))
-            }
+    let mut candidates: Vec<MaskCandidate> = mask_candidates(messages)
+        .into_iter()
+        .filter(|candidate| context_manager.may_mask(candidate))
+        .filter(|candidate| {
+            // A policy that would keep the whole output reclaims nothing.
+            let content = extract_tool_content(&messages[candidate.index]);
+            retained_excerpt(&content, context_manager.mask_policy(&candidate.fn_name)) != content
         })
         .collect();
+    candidates.sort_by_key(MaskCandidate::priority);
 
-    for (msg_idx, call_id) in tool_msg_indices {
-        if masked_this_round >= count {
-            break;
-        }
-
-        // Try to find the fn_name from the preceding assistant message's
-        // tool calls by matching call_id.
-        let fn_name = if !call_id.is_empty() {
-            find_fn_name_for_call_id(messages, msg_idx, &call_id)
-                .unwrap_or_else(|| "unknown".to_string())
+    let mut masked_this_round = 0;
+    for candidate in candidates.into_iter().take(count) {
+        let policy = context_manager.mask_policy(&candidate.fn_name);
+        let original_content = extract_tool_content(&messages[candidate.index]);
+        // Only a saved original can be recalled, so only then does the
+        // placeholder say how.
+        let saved_in = context_manager.observation_store.as_ref().and_then(|store| {
+            let observation = StoredObservation {
+                call_id: candidate.call_id.clone(),
+                fn_name: candidate.fn_name.clone(),
+                content: original_content.clone(),
+            };
+            match store.save(&observation) {
+                Ok(()) => Some(store.session()),
+                Err(e) => {
+                    tracing::warn!("Failed to store observation {}: {}", candidate.call_id, e);
+                    None
+                }
+            }
+        });
+        let placeholder = generate_placeholder(
+            &candidate.fn_name,
+            &candidate.call_id,
+            saved_in,
+            &original_content,
+        );
+        let excerpt = retained_excerpt(&original_content, policy);
+        let masked = if excerpt.is_empty() {
+            placeholder
         } else {
-            "unknown".to_string()
+            format!("{placeholder}\n{excerpt}")
         };
 
-        // Extract current content, generate placeholder, and replace.
-        let msg = &messages[msg_idx];
-        let original_content = extract_tool_content(msg);
-        let placeholder = generate_placeholder(&fn_name, &original_content);
-
         // Replace the message content with a ToolResponse containing the
         // placeholder text, preserving the call_id.
-        let effective_call_id = if call_id.is_empty() {
+        let effective_call_id = if candidate.call_id.is_empty() {
             "masked".to_string()
         } else {
-            call_id.clone()
+            candidate.call_id
         };
-        messages[msg_idx] = ChatMessage {
+        messages[candidate.index] = ChatMessage {
             role: ChatRole::Tool,
-            content: MessageContent::from(ToolResponse::new(
-                effective_call_id,
-                placeholder,
-            )),
+            content: MessageContent::from(ToolResponse::new(effective_call_id, masked)),
             options: None,
         };
 
@@ -372,6 +504,125 @@ pub fn mask_oldest_observations(
     }
 }
 
+/// Collect every unmasked tool observation with its age, size, and whether
+/// a later tool call superseded it.
+fn mask_candidates(messages: &[ChatMessage]) -> Vec<MaskCandidate> {
+    // (message index, target) of every tool call, to spot later calls on
+    // the same target.
+    let mut call_targets: Vec<(usize, String)> = Vec::new();
+    let mut turn_of_message = Vec::with_capacity(messages.len());
+    let mut turns: u64 = 0;
+    for (i, msg) in messages.iter().enumerate() {
+        if msg.role == ChatRole::Assistant {
+            turns += 1;
+            for call in msg.content.tool_calls() {
+                if let Some(target) = call_target(call) {
+                    call_targets.push((i, target));
+                }
+            }
+        }
+        turn_of_message.push(turns);
+    }
+
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| msg.role == ChatRole::Tool)
+        .filter_map(|(i, msg)| {
+            let content = extract_tool_content(msg);
+            if is_already_masked(&content) {
+                return None;
+            }
+            let call_id = msg
+                .content
+                .tool_responses()
+                .first()
+                .map(|tr| tr.call_id.clone())
+                .unwrap_or_default();
+            let call = if call_id.is_empty() {
+                None
+            } else {
+                find_tool_call(messages, i, &call_id)
+            };
+            let target = call.and_then(call_target);
+            let superseded = target.is_some_and(|target| {
+                call_targets
+                    .iter()
+                    .any(|(j, later)| *j > i && *later == target)
+            });
+            Some(MaskCandidate {
+                index: i,
+                call_id,
+                fn_name: call.map_or_else(|| "unknown".to_string(), |c| c.fn_name.clone()),
+                bytes: content.len(),
+                age_turns: turns - turn_of_message[i],
+                superseded,
+            })
+        })
+        .collect()
+}
+
+/// What a tool call looks at, such that a later call with the same target
+/// makes its output stale.
+///
+/// File reads and writes share a path target, so rewriting a file supersedes
+/// earlier reads of it. Any other call is only superseded by an identical
+/// call (the same command run again, the same URL fetched again).
+fn call_target(call: &ToolCall) -> Option<String> {
+    match call.fn_name.as_str() {
+        "file_read" | "file_write" => {
+            let path = call.fn_arguments.get("path")?.as_str()?;
+            Some(format!("path:{}", path.trim_start_matches("./")))
+        }
+        _ => Some(format!("{}:{}", call.fn_name, call.fn_arguments)),
+    }
+}
+
+/// The part of a masked observation kept under its policy: the first and
+/// last lines, capped at `max_retained_bytes`. Empty if nothing is kept.
+fn retained_excerpt(content: &str, policy: &MaskPolicy) -> String {
+    let (head, tail) = (policy.keep_head_lines, policy.keep_tail_lines);
+    let lines: Vec<&str> = content.lines().collect();
+    let excerpt = if head + tail == 0 {
+        if policy.max_retained_bytes.is_none() {
+            return String::new();
+        }
+        content.to_string()
+    } else if head + tail >= lines.len() {
+        content.to_string()
+    } else {
+        let kept: Vec<String> = [
+            lines[..head].join("\n"),
+            format!("... [{} lines omitted]", lines.len() - head - tail),
+            lines[lines.len() - tail..].join("\n"),
+        ]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+        kept.join("\n")
+    };
+
+    match policy.max_retained_bytes {
+        Some(max) if excerpt.len() > max => {
+            // Tail-only policies keep the end; everything else the start.
+            if head == 0 && tail > 0 {
+                let mut start = excerpt.len() - max;
+                while !excerpt.is_char_boundary(start) {
+                    start += 1;
+                }
+                excerpt[start..].to_string()
+            } else {
+                let mut end = max;
+                while !excerpt.is_char_boundary(end) {
+                    end -= 1;
+                }
+                excerpt[..end].to_string()
+            }
+        }
+        _ => excerpt,
+    }
+}
+
 /// Generate the system notification text for a masking round.
 pub fn generate_mask_notification(
     masked_count: usize,
@@ -389,13 +640,12 @@ pub fn generate_mask_notification(
 // ---------------------------------------------------------------------------
 
 /// Search backwards from `tool_msg_idx` to find the assistant message whose
-/// tool calls contain a ToolCall with the given `call_id`, and return its
-/// `fn_name`.
-fn find_fn_name_for_call_id(
-    messages: &[ChatMessage],
+/// tool calls contain a ToolCall with the given `call_id`, and return it.
+fn find_tool_call<'a>(
+    messages: &'a [ChatMessage],
     tool_msg_idx: usize,
     call_id: &str,
-) -> Option<String> {
+) -> Option<&'a ToolCall> {
     for i in (0..tool_msg_idx).rev() {
         if messages[i].role != ChatRole::Assistant {
             continue;
@@ -403,7 +653,7 @@ fn find_fn_name_for_call_id(
         let tool_calls = messages[i].content.tool_calls();
         for tc in tool_calls {
             if tc.call_id == call_id {
-                return Some(tc.fn_name.clone());
+                return Some(tc);
             }
         }
         // Only check the immediately preceding assistant message.
@@ -453,6 +703,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_soft_threshold_follows_strategy() {
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5)
+            .with_strategy(ContextStrategy::Summarize, 6);
+        cm.update_token_usage(750, 10);
+        assert_eq!(cm.evaluate(), ContextAction::Summarize { turns: 6 });
+
+        // Hybrid starts by masking; the loop escalates when nothing is left.
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5)
+            .with_strategy(ContextStrategy::Hybrid, 6);
+        cm.update_token_usage(750, 10);
+        assert!(matches!(cm.evaluate(), ContextAction::Mask { .. }));
+
+        // The hard threshold behaves the same under every strategy.
+        cm.update_token_usage(920, 10);
+        assert_eq!(cm.evaluate(), ContextAction::WindDown);
+    }
+
     #[test]
     fn test_winddown_when_at_hard_threshold() {
         // 92% usage, hard at 90% => WindDown, flag set
@@ -488,6 +756,18 @@ mod tests {
         assert_eq!(cm.evaluate(), ContextAction::Continue);
     }
 
+    #[test]
+    fn test_estimated_usage_replaces_stale_count() {
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5);
+        cm.update_token_usage(800, 10);
+        cm.update_estimated_usage(500);
+        assert!((cm.usage_percentage() - 0.5).abs() < f64::EPSILON);
+
+        // The next reported count wins again.
+        cm.update_token_usage(600, 10);
+        assert!((cm.usage_percentage() - 0.6).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_prompt_tokens_not_additive() {
         // Call update_token_usage twice -- prompt_tokens should be latest, not summed
@@ -504,17 +784,61 @@ mod tests {
     #[test]
     fn test_generate_placeholder_file_read() {
         let content = "line one of the file\nline two\nline three\n";
-        let placeholder = generate_placeholder("file_read", content);
+        let placeholder = generate_placeholder("file_read", "call_1", Some("session-1"), content);
         assert!(placeholder.starts_with('['));
         assert!(placeholder.contains("masked"));
         assert!(placeholder.contains("3 lines"));
         assert!(placeholder.contains("starts with: line one of the file"));
+        assert!(placeholder.ends_with("call_id=call_1, session=session-1, recall_observation to view]"));
+
+        let unsaved = generate_placeholder("file_read", "call_1", None, content);
+        assert!(unsaved.ends_with("starts with: line one of the file]"), "{unsaved}");
+    }
+
+    #[test]
+    fn test_mask_saves_original_for_recall() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = ObservationStore::new(tmp.path(), "session-1");
+        let mut cm =
+            ContextManager::new(1000, 0.70, 0.90, 5).with_observation_store(store.clone());
+        let mut messages = vec![
+            call("c1", "shell_exec", serde_json::json!({"command": "ls"})),
+            result("c1", "a\nb\nc"),
+        ];
+        mask_observations(&mut messages, 1, &mut cm);
+
+        let content = extract_tool_content(&messages[1]);
+        assert!(
+            content.contains("call_id=c1, session=session-1"),
+            "placeholder names the call: {content}"
+        );
+        let stored = store.load("session-1", "c1").unwrap().unwrap();
+        assert_eq!(stored.fn_name, "shell_exec");
+        assert_eq!(stored.content, "a\nb\nc");
+    }
+
+    #[test]
+    fn test_mask_without_saved_original_offers_no_recall() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        // A file where the store's directory should be makes every save fail.
+        std::fs::write(tmp.path().join("observations"), "").unwrap();
+        let store = ObservationStore::new(tmp.path(), "session-1");
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5).with_observation_store(store);
+        let mut messages = vec![
+            call("c1", "shell_exec", serde_json::json!({"command": "ls"})),
+            result("c1", "a\nb\nc"),
+        ];
+        mask_observations(&mut messages, 1, &mut cm);
+
+        let content = extract_tool_content(&messages[1]);
+        assert!(content.contains("masked"), "{content}");
+        assert!(!content.contains("recall_observation"), "{content}");
     }
 
     #[test]
     fn test_generate_placeholder_shell_exec_json() {
         let content = r#"{"exit_code":0,"stdout":"hello world","stderr":""}"#;
-        let placeholder = generate_placeholder("shell_exec", content);
+        let placeholder = generate_placeholder("shell_exec", "call_1", None, content);
         assert!(placeholder.starts_with('['));
         assert!(placeholder.contains("masked"));
         assert!(placeholder.contains("exit_code=0"));
@@ -524,7 +848,7 @@ mod tests {
     #[test]
     fn test_generate_placeholder_shell_exec_plain() {
         let content = "some plain text output that is not json";
-        let placeholder = generate_placeholder("shell_exec", content);
+        let placeholder = generate_placeholder("shell_exec", "call_1", None, content);
         assert!(placeholder.starts_with('['));
         assert!(placeholder.contains("masked"));
         assert!(placeholder.contains("bytes of output"));
@@ -570,7 +894,7 @@ mod tests {
         let mut messages = vec![assistant_msg, tool_resp_1, tool_resp_2];
 
         // Mask 1 observation
-        let result = mask_oldest_observations(&mut messages, 1, &mut cm);
+        let result = mask_observations(&mut messages, 1, &mut cm);
         assert_eq!(result.masked_count, 1);
         assert_eq!(result.total_masked, 1);
         assert_eq!(cm.masked_count(), 1);
@@ -588,7 +912,7 @@ mod tests {
         assert!(!is_already_masked(&content2));
 
         // Mask 1 more -- should get the second one
-        let result2 = mask_oldest_observations(&mut messages, 1, &mut cm);
+        let result2 = mask_observations(&mut messages, 1, &mut cm);
         assert_eq!(result2.masked_count, 1);
         assert_eq!(result2.total_masked, 2);
 
@@ -611,11 +935,168 @@ mod tests {
         let mut messages = vec![already_masked];
 
         // Try to mask -- should skip it
-        let result = mask_oldest_observations(&mut messages, 1, &mut cm);
+        let result = mask_observations(&mut messages, 1, &mut cm);
         assert_eq!(result.masked_count, 0);
         assert_eq!(result.total_masked, 0);
     }
 
+    #[test]
+    fn test_has_maskable_observations() {
+        let cm = ContextManager::new(1000, 0.70, 0.90, 5);
+        let mut messages = vec![
+            ChatMessage::assistant("text only"),
+            ToolResponse::new("c1", "[file_read result masked -- 3 lines]").into(),
+        ];
+        assert!(!cm.has_maskable_observations(&messages));
+
+        messages.push(ToolResponse::new("c2", "fresh output").into());
+        assert!(cm.has_maskable_observations(&messages));
+
+        // Output a policy never masks does not count.
+        let mut messages = vec![call("c1", "file_read", serde_json::json!({"path": "a"}))];
+        messages.push(ToolResponse::new("c1", "contents").into());
+        let cm = cm.with_mask_policies(policies("file_read", MaskPolicy {
+            never: true,
+            ..Default::default()
+        }));
+        assert!(!cm.has_maskable_observations(&messages));
+    }
+
+    // -- Masking order and per-tool policies --------------------------------
+
+    fn call(id: &str, fn_name: &str, args: serde_json::Value) -> ChatMessage {
+        use genai::chat::ToolCall;
+        ChatMessage::from(vec![ToolCall {
+            call_id: id.to_string(),
+            fn_name: fn_name.to_string(),
+            fn_arguments: args,
+            thought_signatures: None,
+        }])
+    }
+
+    fn result(id: &str, content: &str) -> ChatMessage {
+        ToolResponse::new(id, content).into()
+    }
+
+    fn policies(fn_name: &str, policy: MaskPolicy) -> HashMap<String, MaskPolicy> {
+        HashMap::from([(fn_name.to_string(), policy)])
+    }
+
+    fn masked(messages: &[ChatMessage]) -> Vec<bool> {
+        messages
+            .iter()
+            .filter(|msg| msg.role == ChatRole::Tool)
+            .map(|msg| is_already_masked(&extract_tool_content(msg)))
+            .collect()
+    }
+
+    #[test]
+    fn test_mask_prefers_superseded_then_large() {
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5);
+        let mut messages = vec![
+            call("c1", "file_read", serde_json::json!({"path": "SYSTEM_PROMPT.md"})),
+            result("c1", "# Goals"),
+            call("c2", "file_read", serde_json::json!({"path": "./notes.md"})),
+            result("c2", "old notes"),
+            call("c3", "shell_exec", serde_json::json!({"command": "ls -R"})),
+            result("c3", &"src/main.rs\n".repeat(1000)),
+            call("c4", "file_write", serde_json::json!({"path": "notes.md", "content": "x"})),
+            result("c4", "{\"bytes_written\":1}"),
+        ];
+
+        // The read of a file later rewritten goes first, then the big listing.
+        mask_observations(&mut messages, 1, &mut cm);
+        assert_eq!(masked(&messages), vec![false, true, false, false]);
+        mask_observations(&mut messages, 1, &mut cm);
+        assert_eq!(masked(&messages), vec![false, true, true, false]);
+        // Then oldest first.
+        mask_observations(&mut messages, 1, &mut cm);
+        assert_eq!(masked(&messages), vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn test_mask_policy_never_and_after_turns() {
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5).with_mask_policies(HashMap::from([
+            ("file_read".to_string(), MaskPolicy { never: true, ..Default::default() }),
+            ("shell_exec".to_string(), MaskPolicy { after_turns: Some(1), ..Default::default() }),
+        ]));
+        let mut messages = vec![
+            call("c1", "file_read", serde_json::json!({"path": "SYSTEM_PROMPT.md"})),
+            result("c1", "# Goals"),
+            call("c2", "shell_exec", serde_json::json!({"command": "ls"})),
+            result("c2", "a b c"),
+        ];
+
+        let round = mask_observations(&mut messages, 5, &mut cm);
+        assert_eq!(round.masked_count, 0, "shell output is from the current turn");
+
+        messages.push(ChatMessage::assistant("Still thinking."));
+        let round = mask_observations(&mut messages, 5, &mut cm);
+        assert_eq!(round.masked_count, 1);
+        assert_eq!(masked(&messages), vec![false, true]);
+    }
+
+    #[test]
+    fn test_mask_policy_keeps_head_and_tail_lines() {
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5).with_mask_policies(policies(
+            "shell_exec",
+            MaskPolicy {
+                keep_head_lines: 1,
+                keep_tail_lines: 2,
+                ..Default::default()
+            },
+        ));
+        let output: String = (1..=10).map(|n| format!("line {n}\n")).collect();
+        let mut messages = vec![
+            call("c1", "shell_exec", serde_json::json!({"command": "make"})),
+            result("c1", &output),
+        ];
+        mask_observations(&mut messages, 1, &mut cm);
+
+        let content = extract_tool_content(&messages[1]);
+        assert!(is_already_masked(&content));
+        assert!(content.ends_with("\nline 1\n... [7 lines omitted]\nline 9\nline 10"));
+    }
+
+    #[test]
+    fn test_mask_skips_output_its_policy_keeps_whole() {
+        let mut cm = ContextManager::new(1000, 0.70, 0.90, 5).with_mask_policies(HashMap::from([
+            ("file_read".to_string(), MaskPolicy { keep_head_lines: 5, ..Default::default() }),
+            ("shell_exec".to_string(), MaskPolicy { max_retained_bytes: Some(100), ..Default::default() }),
+        ]));
+        let mut messages = vec![
+            call("c1", "file_read", serde_json::json!({"path": "notes.md"})),
+            result("c1", "one\ntwo\nthree"),
+            call("c2", "shell_exec", serde_json::json!({"command": "ls"})),
+            result("c2", "a b c"),
+            call("c3", "shell_exec", serde_json::json!({"command": "ls -R"})),
+            result("c3", &"src/main.rs\n".repeat(20)),
+        ];
+
+        let round = mask_observations(&mut messages, 5, &mut cm);
+        assert_eq!(round.masked_count, 1);
+        assert_eq!(masked(&messages), vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_retained_excerpt_respects_byte_cap() {
+        let content = "alpha\nbeta\ngamma\ndelta";
+        let head_cap = MaskPolicy {
+            max_retained_bytes: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(retained_excerpt(content, &head_cap), "alpha\nbe");
+
+        let tail_cap = MaskPolicy {
+            keep_tail_lines: 2,
+            max_retained_bytes: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(retained_excerpt(content, &tail_cap), "ma\ndelta");
+
+        assert_eq!(retained_excerpt(content, &MaskPolicy::default()), "");
+    }
+
     #[test]
     fn test_generate_mask_notification() {
         let notification = generate_mask_notification(3, 10, 15.0);
@@ -0,0 +1,403 @@
+//! Workspace activity digest for restarted sessions.
+//!
+//! A restarted agent otherwise spends its first turns listing and re-reading
+//! its own workspace to find out what it was doing. The digest answers that
+//! up front, from the previous session's log and a workspace scan:
+//!
+//! - files written with `file_write`
+//! - other files modified during the session (by shell commands, etc.)
+//! - shell commands that failed
+//! - the agent's last text message
+//!
+//! The rendered digest is cut to a token budget (`[context] digest_tokens`),
+//! measured with [`LocalEstimator`], and placed in the system prompt by
+//! [`crate::agent::system_prompt::build_system_prompt`].
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+
+use crate::agent::logging::LogEntry;
+use crate::agent::tokens::LocalEstimator;
+
+/// Most files reported from the workspace scan.
+const MAX_SCANNED_FILES: usize = 50;
+
+/// Most directory entries visited by the workspace scan.
+const MAX_SCAN_ENTRIES: usize = 10_000;
+
+/// Longest excerpt of a failed command's error output.
+const MAX_ERROR_CHARS: usize = 160;
+
+/// Longest excerpt of the agent's last message.
+const MAX_LAST_TEXT_CHARS: usize = 1500;
+
+/// What happened in the previous session, gathered from its log.
+#[derive(Debug, Default, PartialEq)]
+pub struct SessionDigest {
+    pub session_number: u32,
+    pub turns: u64,
+    /// Paths successfully written with `file_write`, in first-write order.
+    pub files_written: Vec<String>,
+    /// Other workspace files modified since the session started, newest first.
+    pub files_modified: Vec<String>,
+    /// Failed shell commands with a short reason.
+    pub failed_commands: Vec<(String, String)>,
+    pub last_assistant_text: Option<String>,
+    /// When the session started, for the workspace scan.
+    pub started_at: Option<SystemTime>,
+}
+
+impl SessionDigest {
+    /// Gather a digest from a session's log entries.
+    pub fn from_entries(entries: &[LogEntry]) -> Self {
+        let mut digest = SessionDigest::default();
+        let mut calls: HashMap<&str, &serde_json::Value> = HashMap::new();
+
+        for entry in entries {
+            match entry {
+                LogEntry::SessionStart {
+                    timestamp,
+                    session_number,
+                    ..
+                } => {
+                    digest.session_number = *session_number;
+                    digest.started_at = DateTime::parse_from_rfc3339(timestamp)
+                        .ok()
+                        .map(|t| SystemTime::from(t.with_timezone(&Utc)));
+                }
+                LogEntry::AssistantText { turn, content, .. } => {
+                    digest.turns = digest.turns.max(*turn);
+                    if !content.trim().is_empty() {
+                        digest.last_assistant_text = Some(content.trim().to_string());
+                    }
+                }
+                LogEntry::ToolCall {
+                    turn,
+                    call_id,
+                    fn_arguments,
+                    ..
+                } => {
+                    digest.turns = digest.turns.max(*turn);
+                    calls.insert(call_id, fn_arguments);
+                }
+                LogEntry::ToolResult {
+                    call_id,
+                    fn_name,
+                    result,
+                    ..
+                } => {
+                    let Some(args) = calls.get(call_id.as_str()) else {
+                        continue;
+                    };
+                    let argument = |key: &str| args.get(key).and_then(|v| v.as_str());
+                    match fn_name.as_str() {
+                        "file_write" if !is_error_result(result) => {
+                            if let Some(path) = argument("path")
+                                && !digest.files_written.iter().any(|f| f == path)
+                            {
+                                digest.files_written.push(path.to_string());
+                            }
+                        }
+                        "shell_exec" => {
+                            if let (Some(command), Some(reason)) =
+                                (argument("command"), shell_failure(result))
+                            {
+                                digest.failed_commands.push((command.to_string(), reason));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        digest
+    }
+
+    /// Add workspace files modified since the session started that were not
+    /// written with `file_write`.
+    pub fn scan_workspace(&mut self, workspace: &Path) {
+        let Some(since) = self.started_at else {
+            return;
+        };
+        let mut modified = Vec::new();
+        let mut visited = 0;
+        collect_modified(workspace, workspace, since, &mut modified, &mut visited);
+        modified.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+        self.files_modified = modified
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| !self.files_written.contains(path))
+            .take(MAX_SCANNED_FILES)
+            .collect();
+    }
+
+    /// Render the digest as markdown of at most `max_tokens` tokens.
+    ///
+    /// Sections are added in priority order (files written, failed commands,
+    /// last message, other modified files); whole lines that would exceed the
+    /// budget are dropped. Returns `None` if there is nothing to report.
+    pub fn render(&self, max_tokens: usize) -> Option<String> {
+        let mut sections: Vec<(&str, Vec<String>)> = Vec::new();
+        sections.push((
+            "Files you wrote",
+            self.files_written.iter().map(|f| format!("- {f}")).collect(),
+        ));
+        sections.push((
+            "Commands that failed",
+            self.failed_commands
+                .iter()
+                .map(|(command, reason)| format!("- `{command}` -- {reason}"))
+                .collect(),
+        ));
+        if let Some(text) = &self.last_assistant_text {
+            let excerpt = truncate_chars(text, MAX_LAST_TEXT_CHARS);
+            sections.push((
+                "Your last message",
+                excerpt.lines().map(|line| format!("> {line}")).collect(),
+            ));
+        }
+        sections.push((
+            "Other files modified",
+            self.files_modified.iter().map(|f| format!("- {f}")).collect(),
+        ));
+        if sections.iter().all(|(_, lines)| lines.is_empty()) {
+            return None;
+        }
+
+        let estimator = LocalEstimator;
+        let mut out = format!(
+            "Gathered by the harness from session #{}'s log ({} turns).",
+            self.session_number, self.turns
+        );
+        let mut used = estimator.estimate(&out);
+        for (heading, lines) in sections {
+            let header = format!("\n\n### {heading}");
+            let mut block = String::new();
+            let mut block_tokens = estimator.estimate(&header);
+            for line in lines {
+                let line = format!("\n{line}");
+                let tokens = estimator.estimate(&line);
+                if used + block_tokens + tokens > max_tokens {
+                    break;
+                }
+                block.push_str(&line);
+                block_tokens += tokens;
+            }
+            if !block.is_empty() {
+                out.push_str(&header);
+                out.push_str(&block);
+                used += block_tokens;
+            }
+        }
+        Some(out)
+    }
+}
+
+/// Build the digest of the session logged at `log_path`, scanning
+/// `workspace` for modified files, within `max_tokens`.
+pub fn previous_session_digest(
+    log_path: &Path,
+    workspace: &Path,
+    max_tokens: usize,
+) -> anyhow::Result<Option<String>> {
+    let entries = crate::agent::resume::read_log_entries(log_path)?;
+    let mut digest = SessionDigest::from_entries(&entries);
+    digest.scan_workspace(workspace);
+    Ok(digest.render(max_tokens))
+}
+
+/// Whether a tool result is a `{"error": ...}` object.
+fn is_error_result(result: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(result)
+        .is_ok_and(|value| value.get("error").is_some())
+}
+
+/// Why a `shell_exec` result counts as failed, or `None` if it succeeded.
+fn shell_failure(result: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(result).ok()?;
+    if let Some(error) = value.get("error").and_then(|v| v.as_str()) {
+        return Some(truncate_chars(error, MAX_ERROR_CHARS));
+    }
+    let stderr = value.get("stderr").and_then(|v| v.as_str()).unwrap_or("");
+    let first_error = stderr
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| format!(": {}", truncate_chars(line, MAX_ERROR_CHARS)))
+        .unwrap_or_default();
+    if value.get("timed_out").and_then(|v| v.as_bool()) == Some(true) {
+        return Some(format!("timed out{first_error}"));
+    }
+    if let Some(limit) = value.get("killed_by").and_then(|v| v.as_str()) {
+        return Some(format!("killed by {limit} limit{first_error}"));
+    }
+    match value.get("exit_code").and_then(|v| v.as_i64()) {
+        Some(0) | None => None,
+        Some(code) => Some(format!("exit {code}{first_error}")),
+    }
+}
+
+/// Recursively collect files under `dir` modified at or after `since`, as
+/// paths relative to `root`. Hidden files and directories are skipped.
+fn collect_modified(
+    root: &Path,
+    dir: &Path,
+    since: SystemTime,
+    out: &mut Vec<(String, SystemTime)>,
+    visited: &mut usize,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        *visited += 1;
+        if *visited > MAX_SCAN_ENTRIES {
+            return;
+        }
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_modified(root, &path, since, out, visited);
+        } else if file_type.is_file() {
+            let modified = entry.metadata().and_then(|m| m.modified());
+            if let Ok(modified) = modified
+                && modified >= since
+                && let Ok(relative) = path.strip_prefix(root)
+            {
+                out.push((relative.display().to_string(), modified));
+            }
+        }
+    }
+}
+
+/// Cut `text` to at most `max` chars, marking the cut.
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn call(turn: u64, id: &str, fn_name: &str, args: serde_json::Value) -> LogEntry {
+        LogEntry::ToolCall {
+            timestamp: "2026-01-01T00:00:00.000Z".into(),
+            turn,
+            call_id: id.into(),
+            fn_name: fn_name.into(),
+            fn_arguments: args,
+        }
+    }
+
+    fn result(turn: u64, id: &str, fn_name: &str, result: serde_json::Value) -> LogEntry {
+        LogEntry::ToolResult {
+            timestamp: "2026-01-01T00:00:00.000Z".into(),
+            turn,
+            call_id: id.into(),
+            fn_name: fn_name.into(),
+            result: result.to_string(),
+            error: None,
+        }
+    }
+
+    fn sample_log() -> Vec<LogEntry> {
+        vec![
+            LogEntry::SessionStart {
+                timestamp: "2026-01-01T00:00:00.000Z".into(),
+                model: "m".into(),
+                workspace: "/ws".into(),
+                session_number: 2,
+                context_limit: 0,
+                context_limit_source: String::new(),
+                resumed_from: None,
+            },
+            call(1, "w1", "file_write", json!({"path": "notes.md", "content": "x"})),
+            result(1, "w1", "file_write", json!({"written_bytes": 1, "path": "notes.md"})),
+            call(2, "w2", "file_write", json!({"path": "/etc/x", "content": "x"})),
+            result(2, "w2", "file_write", json!({"error": "outside the workspace"})),
+            call(3, "s1", "shell_exec", json!({"command": "cargo test"})),
+            result(
+                3,
+                "s1",
+                "shell_exec",
+                json!({"stdout": "", "stderr": "\nerror[E0425]: cannot find value\nmore", "exit_code": 101, "timed_out": false}),
+            ),
+            call(4, "s2", "shell_exec", json!({"command": "ls"})),
+            result(4, "s2", "shell_exec", json!({"stdout": "a", "stderr": "", "exit_code": 0, "timed_out": false})),
+            LogEntry::AssistantText {
+                timestamp: "2026-01-01T00:00:00.000Z".into(),
+                turn: 5,
+                content: "Next I will fix the test.".into(),
+            },
+        ]
+    }
+
+    #[test]
+    fn gathers_writes_failures_and_last_text() {
+        let digest = SessionDigest::from_entries(&sample_log());
+        assert_eq!(digest.session_number, 2);
+        assert_eq!(digest.turns, 5);
+        assert_eq!(digest.files_written, vec!["notes.md"]);
+        assert_eq!(
+            digest.failed_commands,
+            vec![("cargo test".to_string(), "exit 101: error[E0425]: cannot find value".to_string())]
+        );
+        assert_eq!(digest.last_assistant_text.as_deref(), Some("Next I will fix the test."));
+        assert!(digest.started_at.is_some());
+    }
+
+    #[test]
+    fn render_respects_token_budget() {
+        let mut digest = SessionDigest::from_entries(&sample_log());
+        digest.files_modified = (0..200).map(|i| format!("out/file{i}.txt")).collect();
+
+        let full = digest.render(100_000).unwrap();
+        assert!(full.contains("### Files you wrote\n- notes.md"));
+        assert!(full.contains("### Commands that failed\n- `cargo test` -- exit 101"));
+        assert!(full.contains("### Your last message\n> Next I will fix the test."));
+        assert!(full.contains("out/file199.txt"));
+
+        let small = digest.render(80).unwrap();
+        assert!(LocalEstimator.estimate(&small) <= 80);
+        assert!(small.contains("notes.md"), "higher-priority sections come first");
+        assert!(!small.contains("out/file199.txt"));
+
+        assert_eq!(SessionDigest::default().render(1000), None);
+    }
+
+    #[test]
+    fn scan_finds_recent_files_but_not_hidden_or_written_ones() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("sub")).unwrap();
+        std::fs::create_dir_all(tmp.path().join(".git")).unwrap();
+        std::fs::write(tmp.path().join("notes.md"), "x").unwrap();
+        std::fs::write(tmp.path().join("sub/out.txt"), "x").unwrap();
+        std::fs::write(tmp.path().join(".git/HEAD"), "x").unwrap();
+
+        let mut digest = SessionDigest {
+            files_written: vec!["notes.md".into()],
+            started_at: Some(SystemTime::now() - std::time::Duration::from_secs(60)),
+            ..Default::default()
+        };
+        digest.scan_workspace(tmp.path());
+        assert_eq!(digest.files_modified, vec![format!("sub{}out.txt", std::path::MAIN_SEPARATOR)]);
+
+        digest.started_at = Some(SystemTime::now() + std::time::Duration::from_secs(60));
+        digest.scan_workspace(tmp.path());
+        assert!(digest.files_modified.is_empty());
+    }
+}
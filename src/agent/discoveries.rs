@@ -0,0 +1,256 @@
+//! Durable discoveries flagged by the agent via the `flag_discovery` tool.
+//!
+//! Each discovery is recorded twice: as a [`LogEntry::Discovery`] in the
+//! session log (so replay shows it), and as one JSON line appended to
+//! `.ouro-logs/discoveries.jsonl`. The latter is shared by every session and
+//! sub-agent of a workspace and is read back on startup, so the Discoveries
+//! tab survives restarts.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::logging::{LogEntry, SessionLogger};
+
+/// File name of the discoveries store inside the log directory.
+pub const DISCOVERIES_FILE: &str = "discoveries.jsonl";
+
+/// A finding the agent flagged for the operator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Discovery {
+    pub timestamp: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Related files, as given by the agent (relative to its workspace).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+impl Discovery {
+    /// Parse and validate `flag_discovery` tool-call arguments.
+    pub fn from_arguments(args: &serde_json::Value, timestamp: String) -> Result<Self, String> {
+        let title = args
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .ok_or("flag_discovery: missing or invalid 'title' argument")?;
+        let body = args
+            .get("body")
+            .and_then(|v| v.as_str())
+            .ok_or("flag_discovery: missing or invalid 'body' argument")?;
+        Ok(Self {
+            timestamp,
+            title: title.to_string(),
+            body: body.trim().to_string(),
+            tags: string_list(args, "tags")?,
+            files: string_list(args, "files")?,
+        })
+    }
+
+    /// Single-line rendering for the Discoveries tab:
+    /// `title #tag1 #tag2 -- body (files: a, b)`.
+    pub fn summary(&self) -> String {
+        let mut line = self.title.clone();
+        for tag in &self.tags {
+            line.push_str(" #");
+            line.push_str(tag);
+        }
+        let body = self.body.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !body.is_empty() {
+            line.push_str(" -- ");
+            line.push_str(&body);
+        }
+        if !self.files.is_empty() {
+            line.push_str(&format!(" (files: {})", self.files.join(", ")));
+        }
+        line
+    }
+
+    /// The session log entry recording this discovery.
+    pub fn to_log_entry(&self, turn: u64) -> LogEntry {
+        LogEntry::Discovery {
+            timestamp: self.timestamp.clone(),
+            turn,
+            title: self.title.clone(),
+            body: self.body.clone(),
+            tags: self.tags.clone(),
+            files: self.files.clone(),
+        }
+    }
+}
+
+/// Read an optional array-of-strings argument. Absent or null yields empty.
+fn string_list(args: &serde_json::Value, key: &str) -> Result<Vec<String>, String> {
+    match args.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|s| s.trim().to_string())
+                    .ok_or_else(|| format!("flag_discovery: '{key}' must be an array of strings"))
+            })
+            .filter(|item| !matches!(item, Ok(s) if s.is_empty()))
+            .collect(),
+        Some(_) => Err(format!("flag_discovery: '{key}' must be an array of strings")),
+    }
+}
+
+/// Path of the discoveries store inside a log directory.
+pub fn discoveries_path(log_root: &Path) -> PathBuf {
+    log_root.join(DISCOVERIES_FILE)
+}
+
+/// Append a discovery as one JSON line, creating the file if needed.
+pub fn append_discovery(path: &Path, discovery: &Discovery) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(discovery)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Handle a `flag_discovery` call: validate the arguments and persist them.
+///
+/// Returns the recorded discovery, or an error message for the model.
+pub fn record_discovery(
+    args: &serde_json::Value,
+    timestamp: String,
+    path: &Path,
+) -> Result<Discovery, String> {
+    let discovery = Discovery::from_arguments(args, timestamp)?;
+    append_discovery(path, &discovery).map_err(|e| format!("flag_discovery: {e}"))?;
+    Ok(discovery)
+}
+
+/// Load every discovery previously flagged for the given workspace.
+///
+/// Returns an empty list if none have been flagged yet. Malformed lines are
+/// skipped with a warning so one bad write cannot hide the rest.
+pub fn load_discoveries(workspace: &Path) -> anyhow::Result<Vec<Discovery>> {
+    let path = discoveries_path(&SessionLogger::log_dir_for(workspace)?);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut discoveries = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Discovery>(line) {
+            Ok(discovery) => discoveries.push(discovery),
+            Err(e) => {
+                tracing::warn!("Skipping unparseable line {} in {}: {}", i + 1, path.display(), e);
+            }
+        }
+    }
+    Ok(discoveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn sample(title: &str) -> Discovery {
+        Discovery {
+            timestamp: "2026-01-01T00:00:00.000Z".into(),
+            title: title.into(),
+            body: "details".into(),
+            tags: vec!["perf".into()],
+            files: vec!["src/main.rs".into()],
+        }
+    }
+
+    #[test]
+    fn from_arguments_parses_all_fields() {
+        let args = json!({
+            "title": " Found a leak ",
+            "body": "The cache never evicts.",
+            "tags": ["memory", " "],
+            "files": ["src/cache.rs"]
+        });
+        let d = Discovery::from_arguments(&args, "ts".into()).unwrap();
+        assert_eq!(d.title, "Found a leak");
+        assert_eq!(d.tags, vec!["memory"]);
+        assert_eq!(d.files, vec!["src/cache.rs"]);
+
+        let minimal = Discovery::from_arguments(&json!({"title": "t", "body": "b"}), "ts".into())
+            .unwrap();
+        assert!(minimal.tags.is_empty());
+        assert!(minimal.files.is_empty());
+    }
+
+    #[test]
+    fn from_arguments_rejects_bad_input() {
+        assert!(Discovery::from_arguments(&json!({"body": "b"}), "ts".into()).is_err());
+        assert!(Discovery::from_arguments(&json!({"title": "  ", "body": "b"}), "ts".into()).is_err());
+        assert!(Discovery::from_arguments(&json!({"title": "t"}), "ts".into()).is_err());
+        let err = Discovery::from_arguments(
+            &json!({"title": "t", "body": "b", "tags": "perf"}),
+            "ts".into(),
+        )
+        .unwrap_err();
+        assert!(err.contains("'tags'"));
+        assert!(
+            Discovery::from_arguments(&json!({"title": "t", "body": "b", "files": [1]}), "ts".into())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn summary_is_single_line() {
+        let mut d = sample("Slow query");
+        d.body = "Takes 3s\non every request".into();
+        assert_eq!(
+            d.summary(),
+            "Slow query #perf -- Takes 3s on every request (files: src/main.rs)"
+        );
+    }
+
+    #[test]
+    fn record_discovery_appends_only_valid_calls() {
+        let tmp = TempDir::new().unwrap();
+        let path = discoveries_path(tmp.path());
+
+        assert!(record_discovery(&json!({"body": "b"}), "ts".into(), &path).is_err());
+        assert!(!path.exists());
+
+        let d = record_discovery(&json!({"title": "t", "body": "b"}), "ts".into(), &path).unwrap();
+        assert_eq!(d.title, "t");
+        let line = fs::read_to_string(&path).unwrap();
+        assert_eq!(line, "{\"timestamp\":\"ts\",\"title\":\"t\",\"body\":\"b\"}\n");
+    }
+
+    #[test]
+    fn append_then_load_round_trips() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        assert!(load_discoveries(&workspace).unwrap().is_empty());
+
+        let path = discoveries_path(&tmp.path().join(".ouro-logs"));
+        append_discovery(&path, &sample("first")).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        append_discovery(&path, &sample("second")).unwrap();
+
+        let loaded = load_discoveries(&workspace).unwrap();
+        assert_eq!(loaded, vec![sample("first"), sample("second")]);
+    }
+}
@@ -0,0 +1,239 @@
+//! Structured session handoff written by the harness at wind-down.
+//!
+//! Raw carryover (see [`crate::agent::agent_loop::extract_carryover`]) hands
+//! the next session the last few turns verbatim, which often misses the big
+//! picture. In handoff mode, when the context manager asks the agent to wind
+//! down, the harness also asks the model for a structured handoff -- current
+//! goal, open threads, files touched, next steps -- and writes it to
+//! `HANDOFF.md` in the workspace. The next session's system prompt includes
+//! it (see [`crate::agent::system_prompt::build_system_prompt`]).
+//!
+//! Files the agent wrote with `file_write` are collected from the
+//! conversation by the harness and merged with whatever the model lists.
+
+use std::path::{Path, PathBuf};
+
+use genai::chat::{ChatMessage, ChatRequest, ChatRole};
+use genai::Client;
+use serde::Deserialize;
+
+/// Well-known file name of the handoff inside the workspace.
+pub const HANDOFF_FILE: &str = "HANDOFF.md";
+
+const HANDOFF_INSTRUCTIONS: &str = "\
+[Harness: this session is about to restart because the context window is \
+nearly full. Write a handoff for your next session, which will not see this \
+conversation. Reply with ONLY a JSON object, no tool calls, with these fields: \
+\"goal\" (string: what you are working towards right now), \
+\"open_threads\" (array of strings: work started but not finished, questions \
+still open), \"files_touched\" (array of strings: workspace files that matter), \
+\"next_steps\" (array of strings: concrete first actions for the next session).]";
+
+/// A handoff from one session to the next.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Handoff {
+    pub goal: String,
+    pub open_threads: Vec<String>,
+    pub files_touched: Vec<String>,
+    pub next_steps: Vec<String>,
+    /// The model's reply, kept verbatim when it was not valid JSON.
+    #[serde(skip)]
+    pub notes: String,
+}
+
+impl Handoff {
+    /// Parse the model's reply. The JSON object may be wrapped in prose or a
+    /// code fence; if none can be parsed, the whole reply becomes `notes`.
+    pub fn from_reply(reply: &str) -> Self {
+        let parsed = match (reply.find('{'), reply.rfind('}')) {
+            (Some(start), Some(end)) if start < end => {
+                serde_json::from_str::<Handoff>(&reply[start..=end]).ok()
+            }
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| Handoff {
+            notes: reply.trim().to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Add files the harness saw being written, keeping the model's order.
+    pub fn merge_files(&mut self, files: Vec<String>) {
+        for file in files {
+            if !self.files_touched.contains(&file) {
+                self.files_touched.push(file);
+            }
+        }
+    }
+
+    /// Render as the markdown written to [`HANDOFF_FILE`].
+    pub fn to_markdown(&self, session_number: u32, timestamp: &str) -> String {
+        let mut out = format!(
+            "# Session Handoff\n\nWritten by the harness at the end of session #{session_number} ({timestamp}).\n"
+        );
+        if !self.goal.trim().is_empty() {
+            out.push_str(&format!("\n## Current Goal\n{}\n", self.goal.trim()));
+        }
+        push_list(&mut out, "Open Threads", &self.open_threads);
+        push_list(&mut out, "Files Touched", &self.files_touched);
+        push_list(&mut out, "Next Steps", &self.next_steps);
+        if !self.notes.is_empty() {
+            out.push_str(&format!("\n## Notes\n{}\n", self.notes));
+        }
+        out
+    }
+}
+
+/// Append a markdown section with one bullet per item (nothing if empty).
+fn push_list(out: &mut String, heading: &str, items: &[String]) {
+    let items: Vec<&str> = items
+        .iter()
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .collect();
+    if items.is_empty() {
+        return;
+    }
+    out.push_str(&format!("\n## {heading}\n"));
+    for item in items {
+        out.push_str(&format!("- {item}\n"));
+    }
+}
+
+/// Build the request asking the model for a handoff: the session's system
+/// prompt and conversation so far, without tools, plus the instructions.
+pub fn handoff_request(chat_req: &ChatRequest) -> ChatRequest {
+    let mut request = ChatRequest::new(chat_req.messages.clone())
+        .append_message(ChatMessage::user(HANDOFF_INSTRUCTIONS));
+    if let Some(system) = &chat_req.system {
+        request = request.with_system(system.clone());
+    }
+    request
+}
+
+/// Ask `model` for a handoff of the conversation in `chat_req`.
+///
+/// Files written during the conversation are merged into `files_touched`.
+pub async fn request_handoff(
+    client: &Client,
+    model: &str,
+    chat_req: &ChatRequest,
+) -> anyhow::Result<Handoff> {
+    let response = client
+        .exec_chat(model, handoff_request(chat_req), None)
+        .await?;
+    let reply = response
+        .first_text()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("model returned no handoff text"))?;
+    let mut handoff = Handoff::from_reply(reply);
+    handoff.merge_files(files_written(&chat_req.messages));
+    Ok(handoff)
+}
+
+/// Paths passed to `file_write` in the conversation, in first-write order.
+pub fn files_written(messages: &[ChatMessage]) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for msg in messages.iter().filter(|m| m.role == ChatRole::Assistant) {
+        for call in msg.content.tool_calls() {
+            if call.fn_name != "file_write" {
+                continue;
+            }
+            if let Some(path) = call.fn_arguments.get("path").and_then(|v| v.as_str())
+                && !files.iter().any(|f| f == path)
+            {
+                files.push(path.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// Path of the handoff file in a workspace.
+pub fn handoff_path(workspace: &Path) -> PathBuf {
+    workspace.join(HANDOFF_FILE)
+}
+
+/// Read the handoff left by the previous session, if there is one.
+pub async fn load_handoff(workspace: &Path) -> Option<String> {
+    tokio::fs::read_to_string(handoff_path(workspace))
+        .await
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genai::chat::ToolCall;
+
+    #[test]
+    fn parses_json_reply_inside_prose() {
+        let reply = "Here you go:\n```json\n{\"goal\": \"Fix the parser\", \
+                     \"next_steps\": [\"Run tests\"], \"extra\": 1}\n```";
+        let handoff = Handoff::from_reply(reply);
+        assert_eq!(handoff.goal, "Fix the parser");
+        assert_eq!(handoff.next_steps, vec!["Run tests"]);
+        assert!(handoff.open_threads.is_empty());
+        assert!(handoff.notes.is_empty());
+    }
+
+    #[test]
+    fn keeps_unparseable_reply_as_notes() {
+        let handoff = Handoff::from_reply("I was fixing {the parser");
+        assert_eq!(handoff.goal, "");
+        assert_eq!(handoff.notes, "I was fixing {the parser");
+        assert!(handoff.to_markdown(2, "ts").contains("## Notes\nI was fixing {the parser"));
+    }
+
+    #[test]
+    fn markdown_skips_empty_sections() {
+        let mut handoff = Handoff {
+            goal: "Ship v2".into(),
+            next_steps: vec!["Tag release".into(), " ".into()],
+            files_touched: vec!["notes.md".into()],
+            ..Default::default()
+        };
+        handoff.merge_files(vec!["notes.md".into(), "src/lib.rs".into()]);
+        let md = handoff.to_markdown(3, "2026-01-01T00:00:00Z");
+        assert!(md.starts_with("# Session Handoff\n\nWritten by the harness at the end of session #3"));
+        assert!(md.contains("## Current Goal\nShip v2\n"));
+        assert!(md.contains("## Files Touched\n- notes.md\n- src/lib.rs\n"));
+        assert!(md.contains("## Next Steps\n- Tag release\n"));
+        assert!(!md.contains("Open Threads"));
+    }
+
+    #[test]
+    fn handoff_request_drops_tools_and_appends_instructions() {
+        let chat_req = ChatRequest::from_system("system")
+            .append_message(ChatMessage::assistant("working"));
+        let request = handoff_request(&chat_req);
+        assert_eq!(request.system.as_deref(), Some("system"));
+        assert!(request.tools.is_none());
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[1].role, ChatRole::User);
+    }
+
+    #[test]
+    fn collects_written_files_once() {
+        let write = |id: &str, path: &str| ToolCall {
+            call_id: id.into(),
+            fn_name: "file_write".into(),
+            fn_arguments: serde_json::json!({"path": path, "content": ""}),
+            thought_signatures: None,
+        };
+        let read = ToolCall {
+            call_id: "r".into(),
+            fn_name: "file_read".into(),
+            fn_arguments: serde_json::json!({"path": "other.md"}),
+            thought_signatures: None,
+        };
+        let messages = vec![
+            ChatMessage::from(vec![write("1", "a.md"), read]),
+            ChatMessage::from(vec![write("2", "b.md"), write("3", "a.md")]),
+        ];
+        assert_eq!(files_written(&messages), vec!["a.md", "b.md"]);
+    }
+}
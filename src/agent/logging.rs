@@ -12,7 +12,7 @@ use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
 use chrono::Utc;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Returns the current UTC time as an ISO 8601 string with milliseconds.
 fn now_iso() -> String {
@@ -22,7 +22,7 @@ fn now_iso() -> String {
 /// A structured log entry serialized as a single JSON line.
 ///
 /// Tagged with `event_type` so each line is self-describing for replay.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "event_type")]
 pub enum LogEntry {
     /// Marks the beginning of an agent session.
@@ -31,6 +31,19 @@ pub enum LogEntry {
         timestamp: String,
         model: String,
         workspace: String,
+        /// 1-based session number. Absent in logs written before resume support.
+        #[serde(default)]
+        session_number: u32,
+        /// Effective context limit in tokens. Absent in older logs.
+        #[serde(default)]
+        context_limit: usize,
+        /// Where the limit came from: "default", "config", or "model".
+        #[serde(default)]
+        context_limit_source: String,
+        /// Log of the earlier session whose carryover seeded this one, for a
+        /// restarted or resumed session.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        resumed_from: Option<String>,
     },
 
     /// An assistant text response (thinking out loud or final answer).
@@ -59,7 +72,7 @@ pub enum LogEntry {
         call_id: String,
         fn_name: String,
         result: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         error: Option<String>,
     },
 
@@ -78,6 +91,28 @@ pub enum LogEntry {
         message: String,
     },
 
+    /// A failed LLM request that will be retried after `delay_ms`.
+    #[serde(rename = "retry")]
+    Retry {
+        timestamp: String,
+        turn: u64,
+        /// The failed attempt (1-based); the retry is attempt + 1.
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u64,
+        error: String,
+    },
+
+    /// The session switched to the next model in the fallback chain.
+    #[serde(rename = "model_switch")]
+    ModelSwitch {
+        timestamp: String,
+        turn: u64,
+        from: String,
+        to: String,
+        reason: String,
+    },
+
     /// Marks the end of an agent session.
     #[serde(rename = "session_end")]
     SessionEnd {
@@ -97,6 +132,18 @@ pub enum LogEntry {
         context_used_pct: f64,
     },
 
+    /// Our own token estimate for the prompt just sent, next to the count the
+    /// provider reported (if any), to measure how far off the estimate is.
+    #[serde(rename = "token_estimate")]
+    TokenEstimate {
+        timestamp: String,
+        turn: u64,
+        /// Estimator that produced the count ("provider" or "local").
+        estimator: String,
+        estimated_tokens: usize,
+        reported_tokens: Option<usize>,
+    },
+
     /// Logged when observation masking occurs to reclaim context.
     #[serde(rename = "context_mask")]
     ContextMask {
@@ -106,6 +153,43 @@ pub enum LogEntry {
         context_reclaimed_pct: f64,
     },
 
+    /// Logged when the oldest turns are replaced by a model-written summary.
+    #[serde(rename = "context_summary")]
+    ContextSummary {
+        timestamp: String,
+        turns_summarized: usize,
+        messages_replaced: usize,
+        summary_chars: usize,
+        model: String,
+        /// The summary that replaced the turns, so a resumed session can
+        /// rebuild the conversation the model saw. Empty in older logs.
+        #[serde(default)]
+        summary: String,
+    },
+
+    /// Logged when the harness writes a session handoff at wind-down.
+    #[serde(rename = "handoff")]
+    Handoff {
+        timestamp: String,
+        turn: u64,
+        /// Where the handoff was written.
+        path: String,
+        content: String,
+    },
+
+    /// A finding flagged by the agent via the `flag_discovery` tool.
+    #[serde(rename = "discovery")]
+    Discovery {
+        timestamp: String,
+        turn: u64,
+        title: String,
+        body: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        files: Vec<String>,
+    },
+
     /// Logged when a session restart occurs due to context exhaustion.
     #[serde(rename = "session_restart")]
     SessionRestart {
@@ -133,8 +217,15 @@ impl SessionLogger {
     /// is named `session-{ISO8601}.jsonl` with colons replaced by dashes
     /// for filesystem safety.
     pub fn new(workspace_path: &Path) -> anyhow::Result<Self> {
-        let log_dir = Self::log_dir_for(workspace_path)?;
-        fs::create_dir_all(&log_dir)?;
+        Self::in_dir(&Self::log_dir_for(workspace_path)?)
+    }
+
+    /// Create a session logger that writes into an explicit log directory.
+    ///
+    /// Used for sub-agents, whose workspace is nested inside the parent's and
+    /// whose logs go to `.ouro-logs/sub-{id}/` instead.
+    pub fn in_dir(log_dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(log_dir)?;
 
         let session_id = Utc::now()
             .format("%Y-%m-%dT%H-%M-%S")
@@ -156,7 +247,7 @@ impl SessionLogger {
     /// Compute the log directory for a given workspace path.
     ///
     /// Returns `{workspace_parent}/.ouro-logs/`.
-    fn log_dir_for(workspace_path: &Path) -> anyhow::Result<PathBuf> {
+    pub fn log_dir_for(workspace_path: &Path) -> anyhow::Result<PathBuf> {
         let parent = workspace_path.parent().ok_or_else(|| {
             anyhow::anyhow!(
                 "Workspace path '{}' has no parent directory",
@@ -180,11 +271,23 @@ impl SessionLogger {
     }
 
     /// Convenience: log a session_start event.
-    pub fn log_session_start(&mut self, model: &str, workspace: &Path) -> anyhow::Result<()> {
+    pub fn log_session_start(
+        &mut self,
+        model: &str,
+        workspace: &Path,
+        session_number: u32,
+        context_limit: usize,
+        context_limit_source: &str,
+        resumed_from: Option<&Path>,
+    ) -> anyhow::Result<()> {
         self.log_event(&LogEntry::SessionStart {
             timestamp: now_iso(),
             model: model.to_string(),
             workspace: workspace.display().to_string(),
+            session_number,
+            context_limit,
+            context_limit_source: context_limit_source.to_string(),
+            resumed_from: resumed_from.map(|path| path.display().to_string()),
         })
     }
 
@@ -241,7 +344,7 @@ mod tests {
         let workspace = PathBuf::from("/tmp/test-workspace");
 
         logger
-            .log_session_start("qwen2.5:7b", &workspace)
+            .log_session_start("qwen2.5:7b", &workspace, 1, 32768, "model", None)
             .expect("log_session_start");
 
         // Read the log file
@@ -260,6 +363,9 @@ mod tests {
         assert_eq!(entry["event_type"], "session_start");
         assert_eq!(entry["model"], "qwen2.5:7b");
         assert_eq!(entry["workspace"], "/tmp/test-workspace");
+        assert_eq!(entry["session_number"], 1);
+        assert_eq!(entry["context_limit"], 32768);
+        assert_eq!(entry["context_limit_source"], "model");
         assert!(entry["timestamp"].is_string());
     }
 
@@ -292,7 +398,9 @@ mod tests {
         let (mut logger, _tmp) = make_logger();
         let workspace = PathBuf::from("/tmp/ws");
 
-        logger.log_session_start("test-model", &workspace).unwrap();
+        logger
+            .log_session_start("test-model", &workspace, 1, 32768, "default", None)
+            .unwrap();
         logger
             .log_event(&LogEntry::AssistantText {
                 timestamp: now_iso(),
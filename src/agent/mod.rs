@@ -1,5 +1,17 @@
 pub mod agent_loop;
 pub mod context_manager;
+pub mod digest;
+pub mod discoveries;
+pub mod handoff;
 pub mod logging;
+pub mod observations;
+pub mod provider;
+pub mod resume;
+pub mod retry;
+pub mod snapshots;
+pub mod sub_agent;
+pub mod summarize;
 pub mod system_prompt;
+pub mod tokens;
 pub mod tools;
+pub mod truncation;
@@ -0,0 +1,290 @@
+//! On-disk store of masked observations, read back by `recall_observation`.
+//!
+//! Masking (see [`crate::agent::context_manager`]) replaces old tool output
+//! with a short placeholder. Before it does, the original output is saved
+//! here as `observations/{session}/{call_id}.json` in the session's log
+//! directory, where the session is the session log's file stem, and the
+//! placeholder names both. The agent can then page the output back in --
+//! whole or as a line range -- instead of re-running the tool.
+//!
+//! Providers such as Ollama number call ids per response, so the same id
+//! recurs across sessions; keying by session keeps each one's outputs apart.
+//! The store lives beside the session logs, so it survives restarts along
+//! with the carried-over placeholders that point into it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Directory name of the store inside a log directory.
+pub const OBSERVATIONS_DIR: &str = "observations";
+
+/// A tool observation saved before it was masked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredObservation {
+    pub call_id: String,
+    pub fn_name: String,
+    pub content: String,
+}
+
+/// Masked observations of one agent, keyed by session and call_id.
+#[derive(Debug, Clone)]
+pub struct ObservationStore {
+    dir: PathBuf,
+    session: String,
+}
+
+impl ObservationStore {
+    /// Store inside the given log directory, saving under `session`.
+    pub fn new(log_dir: &Path, session: &str) -> Self {
+        Self {
+            dir: log_dir.join(OBSERVATIONS_DIR),
+            session: session.to_string(),
+        }
+    }
+
+    /// Session that observations are saved under.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Save an observation, replacing any earlier one with the same call_id
+    /// in this session.
+    pub fn save(&self, observation: &StoredObservation) -> anyhow::Result<()> {
+        let path = self.path_for(&self.session, &observation.call_id);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(observation)?)?;
+        Ok(())
+    }
+
+    /// Load the observation saved for `call_id` in `session`, if any.
+    pub fn load(&self, session: &str, call_id: &str) -> anyhow::Result<Option<StoredObservation>> {
+        match fs::read_to_string(self.path_for(session, call_id)) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// File for `call_id` in `session`. Both are encoded as plain file names.
+    fn path_for(&self, session: &str, call_id: &str) -> PathBuf {
+        self.dir
+            .join(file_name(session))
+            .join(format!("{}.json", file_name(call_id)))
+    }
+}
+
+/// Session key of the session that writes the log at `log_path`: the log's
+/// file stem, e.g. `session-2026-01-01T00-00-00`.
+pub fn session_key(log_path: &Path) -> String {
+    log_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Encode `s` as a file name: ASCII letters, digits, and `-` are kept, and
+/// every other byte becomes `_` and two hex digits, so distinct ids never
+/// share a file and none can reach outside the store.
+fn file_name(s: &str) -> String {
+    let mut name = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("_{byte:02X}"));
+        }
+    }
+    name
+}
+
+/// Handle a `recall_observation` call: load the stored observation and
+/// return it (or the requested 1-based, inclusive line range) as JSON. The
+/// observation is looked up in the `session` argument's session, by default
+/// the current one.
+///
+/// Errors are returned as `{"error": ...}` like every other tool.
+pub fn recall_observation(args: &serde_json::Value, store: &ObservationStore) -> String {
+    let Some(call_id) = args.get("call_id").and_then(|v| v.as_str()) else {
+        return json!({"error": "recall_observation: missing or invalid 'call_id' argument"})
+            .to_string();
+    };
+    let session = match args.get("session") {
+        None | Some(serde_json::Value::Null) => store.session(),
+        Some(value) => match value.as_str() {
+            Some(session) => session,
+            None => {
+                return json!({"error": "recall_observation: 'session' must be a string"})
+                    .to_string();
+            }
+        },
+    };
+    let start_line = match optional_line(args, "start_line") {
+        Ok(line) => line,
+        Err(e) => return json!({ "error": e }).to_string(),
+    };
+    let end_line = match optional_line(args, "end_line") {
+        Ok(line) => line,
+        Err(e) => return json!({ "error": e }).to_string(),
+    };
+
+    let observation = match store.load(session, call_id) {
+        Ok(Some(observation)) => observation,
+        Ok(None) => {
+            return json!({
+                "error": format!(
+                    "recall_observation: no stored observation for call_id '{call_id}' in session '{session}'"
+                )
+            })
+            .to_string();
+        }
+        Err(e) => return json!({"error": format!("recall_observation: {e}")}).to_string(),
+    };
+
+    let lines: Vec<&str> = observation.content.lines().collect();
+    let total_lines = lines.len();
+    let start = start_line.unwrap_or(1);
+    let end = end_line.unwrap_or(total_lines).min(total_lines);
+    if start_line.is_none() && end_line.is_none() {
+        return json!({
+            "call_id": observation.call_id,
+            "fn_name": observation.fn_name,
+            "total_lines": total_lines,
+            "content": observation.content,
+        })
+        .to_string();
+    }
+    if start > end {
+        return json!({
+            "error": format!(
+                "recall_observation: empty line range {start}-{end} (observation has {total_lines} lines)"
+            )
+        })
+        .to_string();
+    }
+
+    json!({
+        "call_id": observation.call_id,
+        "fn_name": observation.fn_name,
+        "total_lines": total_lines,
+        "start_line": start,
+        "end_line": end,
+        "content": lines[start - 1..end].join("\n"),
+    })
+    .to_string()
+}
+
+/// Read an optional positive line-number argument.
+fn optional_line(args: &serde_json::Value, key: &str) -> Result<Option<usize>, String> {
+    match args.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(line) if line >= 1 => Ok(Some(line as usize)),
+            _ => Err(format!("recall_observation: '{key}' must be a positive integer")),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn store_with(tmp: &TempDir, call_id: &str, content: &str) -> ObservationStore {
+        let store = ObservationStore::new(tmp.path(), "session-1");
+        store
+            .save(&StoredObservation {
+                call_id: call_id.into(),
+                fn_name: "shell_exec".into(),
+                content: content.into(),
+            })
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let store = store_with(&tmp, "call/1:x", "output");
+        let loaded = store.load("session-1", "call/1:x").unwrap().unwrap();
+        assert_eq!(loaded.content, "output");
+        assert!(tmp.path().join("observations/session-1/call_2F1_3Ax.json").exists());
+        assert_eq!(store.load("session-1", "other").unwrap(), None);
+        assert_eq!(store.load("session-2", "call/1:x").unwrap(), None);
+    }
+
+    #[test]
+    fn file_names_keep_distinct_ids_apart() {
+        assert_eq!(file_name("call_1"), "call_5F1");
+        assert_ne!(file_name("call_1"), file_name("call/1"));
+        assert_eq!(file_name("../x"), "_2E_2E_2Fx");
+    }
+
+    #[test]
+    fn recall_reads_other_sessions_by_name() {
+        let tmp = TempDir::new().unwrap();
+        store_with(&tmp, "c1", "earlier");
+        let store = ObservationStore::new(tmp.path(), "session-2");
+        store
+            .save(&StoredObservation {
+                call_id: "c1".into(),
+                fn_name: "shell_exec".into(),
+                content: "current".into(),
+            })
+            .unwrap();
+
+        let current: serde_json::Value =
+            serde_json::from_str(&recall_observation(&json!({"call_id": "c1"}), &store)).unwrap();
+        assert_eq!(current["content"], "current");
+        let earlier: serde_json::Value = serde_json::from_str(&recall_observation(
+            &json!({"call_id": "c1", "session": "session-1"}),
+            &store,
+        ))
+        .unwrap();
+        assert_eq!(earlier["content"], "earlier");
+    }
+
+    #[test]
+    fn recall_returns_whole_observation_or_line_range() {
+        let tmp = TempDir::new().unwrap();
+        let store = store_with(&tmp, "c1", "one\ntwo\nthree\nfour");
+
+        let whole: serde_json::Value =
+            serde_json::from_str(&recall_observation(&json!({"call_id": "c1"}), &store)).unwrap();
+        assert_eq!(whole["content"], "one\ntwo\nthree\nfour");
+        assert_eq!(whole["total_lines"], 4);
+
+        let range: serde_json::Value = serde_json::from_str(&recall_observation(
+            &json!({"call_id": "c1", "start_line": 2, "end_line": 9}),
+            &store,
+        ))
+        .unwrap();
+        assert_eq!(range["content"], "two\nthree\nfour");
+        assert_eq!(range["end_line"], 4);
+    }
+
+    #[test]
+    fn recall_reports_errors_as_json() {
+        let tmp = TempDir::new().unwrap();
+        let store = store_with(&tmp, "c1", "one\ntwo");
+        for args in [
+            json!({}),
+            json!({"call_id": "missing"}),
+            json!({"call_id": "c1", "session": "session-9"}),
+            json!({"call_id": "c1", "session": 3}),
+            json!({"call_id": "c1", "start_line": 0}),
+            json!({"call_id": "c1", "start_line": 5}),
+        ] {
+            let result: serde_json::Value =
+                serde_json::from_str(&recall_observation(&args, &store)).unwrap();
+            assert!(
+                result["error"].as_str().unwrap().starts_with("recall_observation:"),
+                "{args} -> {result}"
+            );
+        }
+    }
+}
@@ -0,0 +1,700 @@
+//! LLM provider selection: genai client construction and health checks.
+//!
+//! The `[provider]` config section picks the API flavour (`kind`), the server
+//! (`base_url`), the API key (read from the environment variable named by
+//! `api_key_env`) and the request timeout. [`build_client`] installs a
+//! `ServiceTargetResolver` so every request goes to that server whatever the
+//! model name looks like, and [`check_provider_ready`] runs a health check
+//! that matches the server's API instead of assuming Ollama.
+//!
+//! `[model_options]` sampling settings become [`ChatOptions`] via
+//! [`chat_options`]. Ollama's `num_ctx` has no chat-request equivalent in
+//! genai, so [`prepare_model`] instead derives a model with that context
+//! window baked in (`ouro/{model}:ctx{n}`, created once via `/api/create` and
+//! kept on the server) and the agent loop sends its requests to the derived
+//! model.
+//!
+//! The health check also reports the model's maximum context length when the
+//! server exposes it; [`resolve_context_limit`] uses it to lower
+//! `context_limit` to what the model can actually hold.
+//!
+//! When `[general] model` lists a fallback chain, [`select_available_model`]
+//! walks it and settles on the first model the provider actually serves.
+
+use std::time::Duration;
+
+use genai::adapter::AdapterKind;
+use genai::chat::ChatOptions;
+use genai::resolver::{AuthData, Endpoint, ServiceTargetResolver};
+use genai::{Client, ModelIden, ServiceTarget, WebConfig};
+
+use crate::config::{AppConfig, ContextLimitSource, ProviderKind};
+use crate::error::AgentError;
+
+/// Placeholder key sent when no `api_key_env` is configured.
+///
+/// Local servers ignore it, but some adapters refuse to send a request
+/// without any key at all.
+const NO_API_KEY: &str = "none";
+
+/// Build a genai client that sends every request to the configured provider.
+pub fn build_client(config: &AppConfig) -> Client {
+    let adapter_kind = adapter_kind(config.provider_kind);
+    let endpoint_url = endpoint_url(&config.provider_base_url);
+    let api_key_env = config.provider_api_key_env.clone();
+
+    let resolver = ServiceTargetResolver::from_resolver_fn(
+        move |target: ServiceTarget| -> Result<ServiceTarget, genai::resolver::Error> {
+            let auth = match &api_key_env {
+                Some(var) => AuthData::from_env(var.clone()),
+                None => AuthData::from_single(NO_API_KEY),
+            };
+            Ok(ServiceTarget {
+                endpoint: Endpoint::from_owned(endpoint_url.clone()),
+                auth,
+                model: ModelIden::new(adapter_kind, target.model.model_name),
+            })
+        },
+    );
+
+    Client::builder()
+        .with_service_target_resolver(resolver)
+        .with_web_config(
+            WebConfig::default().with_timeout(Duration::from_secs(config.provider_timeout_secs)),
+        )
+        .build()
+}
+
+/// The genai adapter that speaks the provider's API.
+fn adapter_kind(kind: ProviderKind) -> AdapterKind {
+    match kind {
+        ProviderKind::Ollama => AdapterKind::Ollama,
+        ProviderKind::OpenAi => AdapterKind::OpenAI,
+    }
+}
+
+/// Normalize a base URL so relative API paths can be appended to it.
+pub fn endpoint_url(base_url: &str) -> String {
+    if base_url.ends_with('/') {
+        base_url.to_string()
+    } else {
+        format!("{base_url}/")
+    }
+}
+
+/// Read the API key from the configured environment variable, if any.
+fn api_key(config: &AppConfig) -> Option<String> {
+    config
+        .provider_api_key_env
+        .as_ref()
+        .and_then(|var| std::env::var(var).ok())
+        .filter(|key| !key.is_empty())
+}
+
+/// Chat options carrying the configured `[model_options]` sampling settings.
+///
+/// Capture flags are left to the caller.
+pub fn chat_options(config: &AppConfig) -> ChatOptions {
+    let mut options = ChatOptions::default();
+    if let Some(temperature) = config.temperature {
+        options = options.with_temperature(temperature);
+    }
+    if let Some(top_p) = config.top_p {
+        options = options.with_top_p(top_p);
+    }
+    if let Some(max_tokens) = config.max_tokens {
+        options = options.with_max_tokens(max_tokens);
+    }
+    if let Some(seed) = config.seed {
+        options = options.with_seed(seed);
+    }
+    if !config.stop_sequences.is_empty() {
+        options = options.with_stop_sequences(config.stop_sequences.clone());
+    }
+    options
+}
+
+/// The Ollama context window to request: `num_ctx` if set, otherwise the
+/// effective `context_limit`. `None` keeps the server's default.
+fn effective_num_ctx(config: &AppConfig) -> Option<u32> {
+    match config.num_ctx {
+        None => u32::try_from(config.context_limit).ok(),
+        Some(0) => None,
+        Some(n) => Some(n),
+    }
+}
+
+/// Return the model name chat requests should use.
+///
+/// For Ollama with a context window to apply, returns a derived model whose
+/// `num_ctx` is that window, creating it first if the server does not have it
+/// yet. Otherwise returns the configured model unchanged. Call after
+/// [`resolve_context_limit`] so the window matches the effective limit.
+///
+/// Derived models (`ouro/<model>:ctx<n>`) persist on the Ollama server, one
+/// per model and window, and are not refreshed when the base model is
+/// re-pulled; remove stale ones with `ollama rm`. If the derived model cannot
+/// be created, requests go to the base model at the server's default window.
+pub async fn prepare_model(config: &AppConfig) -> String {
+    let num_ctx = match (config.provider_kind, effective_num_ctx(config)) {
+        (ProviderKind::Ollama, Some(num_ctx)) => num_ctx,
+        _ => return config.model.clone(),
+    };
+
+    let derived = derived_model_name(&config.model, num_ctx);
+    let base_url = endpoint_url(&config.provider_base_url);
+    let http = reqwest::Client::new();
+
+    let exists = http
+        .post(format!("{base_url}api/show"))
+        .json(&serde_json::json!({ "model": derived }))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success());
+    if exists {
+        tracing::info!("Using {derived} ({} with num_ctx {num_ctx})", config.model);
+        return derived;
+    }
+
+    let created = http
+        .post(format!("{base_url}api/create"))
+        .json(&serde_json::json!({
+            "model": derived,
+            "from": config.model,
+            "parameters": { "num_ctx": num_ctx },
+            "stream": false,
+        }))
+        .timeout(Duration::from_secs(60))
+        .send()
+        .await;
+    match created {
+        Ok(resp) if resp.status().is_success() => {
+            tracing::info!("Created {derived} ({} with num_ctx {num_ctx})", config.model);
+            derived
+        }
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            tracing::warn!(
+                "Failed to apply num_ctx {num_ctx} to {} (HTTP {status}): {body}; using the server's default",
+                config.model
+            );
+            config.model.clone()
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Failed to apply num_ctx {num_ctx} to {}: {e}; using the server's default",
+                config.model
+            );
+            config.model.clone()
+        }
+    }
+}
+
+/// Name of the derived Ollama model with a fixed context window.
+///
+/// `qwen2.5:7b` with 32768 becomes `ouro/qwen2.5-7b:ctx32768`.
+fn derived_model_name(model: &str, num_ctx: u32) -> String {
+    let base: String = model
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("ouro/{base}:ctx{num_ctx}")
+}
+
+/// Apply the model's maximum context length to `context_limit`.
+///
+/// - No explicit `context_limit`: use the model's maximum, but never more than
+///   the default, which keeps Ollama's KV-cache allocation sane for models
+///   advertising 128k+ windows. Set `context_limit` explicitly to go higher.
+/// - Explicit `context_limit` above the model's maximum: cap it, and return a
+///   warning for the operator.
+///
+/// Returns the adjusted config and the warning, if any.
+pub fn resolve_context_limit(
+    config: &AppConfig,
+    model_max: Option<usize>,
+) -> (AppConfig, Option<String>) {
+    let mut resolved = config.clone();
+    let Some(model_max) = model_max.filter(|&max| max > 0) else {
+        return (resolved, None);
+    };
+    if model_max >= config.context_limit {
+        return (resolved, None);
+    }
+
+    resolved.context_limit = model_max;
+    resolved.context_limit_source = ContextLimitSource::Model;
+    let warning = (config.context_limit_source == ContextLimitSource::Config).then(|| {
+        format!(
+            "context_limit {} exceeds the maximum context length of {} ({model_max} tokens); \
+             using {model_max}",
+            config.context_limit, config.model
+        )
+    });
+    (resolved, warning)
+}
+
+// ---------------------------------------------------------------------------
+// Health checks
+// ---------------------------------------------------------------------------
+
+/// What the health check learned about the model.
+#[derive(Debug, Default, PartialEq)]
+pub struct ModelInfo {
+    /// Maximum context length reported by the server, if it exposes one.
+    pub context_length: Option<usize>,
+}
+
+/// Validate that the provider is reachable and serves the configured model.
+///
+/// - Ollama: `GET /` then `POST /api/show` for the model; the context length
+///   comes from `model_info.<arch>.context_length`.
+/// - OpenAI-compatible: `GET models` and look for the model id in the list;
+///   the context length comes from vLLM's `max_model_len` or llama.cpp's
+///   `meta.n_ctx_train`.
+///
+/// Returns [`AgentError::ProviderUnavailable`] if the server cannot be reached
+/// and [`AgentError::ModelNotAvailable`] if it does not have the model.
+pub async fn check_provider_ready(config: &AppConfig) -> Result<ModelInfo, AgentError> {
+    let base_url = endpoint_url(&config.provider_base_url);
+    match config.provider_kind {
+        ProviderKind::Ollama => check_ollama_ready(&base_url, &config.model).await,
+        ProviderKind::OpenAi => {
+            check_openai_ready(&base_url, &config.model, api_key(config).as_deref()).await
+        }
+    }
+}
+
+/// The primary model followed by its fallbacks, without duplicates.
+pub fn model_chain(config: &AppConfig) -> Vec<String> {
+    let mut chain = vec![config.model.clone()];
+    for model in &config.fallback_models {
+        if !chain.contains(model) {
+            chain.push(model.clone());
+        }
+    }
+    chain
+}
+
+/// The model picked from a fallback chain by [`select_available_model`].
+pub struct ModelSelection {
+    /// The input config with `model` set to the selected model.
+    pub config: AppConfig,
+    pub info: ModelInfo,
+    /// Models passed over because the provider does not serve them, with why.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Health-check `models` in order and select the first the provider serves.
+///
+/// Only [`AgentError::ModelNotAvailable`] moves on to the next model; an
+/// unreachable provider fails immediately since no other model would fare
+/// better. If no model is available, returns the last model's error.
+pub async fn select_available_model(
+    config: &AppConfig,
+    models: &[String],
+) -> Result<ModelSelection, AgentError> {
+    let mut skipped = Vec::new();
+    let mut last_error = None;
+    for model in models {
+        let mut candidate = config.clone();
+        candidate.model = model.clone();
+        match check_provider_ready(&candidate).await {
+            Ok(info) => {
+                return Ok(ModelSelection {
+                    config: candidate,
+                    info,
+                    skipped,
+                });
+            }
+            Err(e @ AgentError::ModelNotAvailable { .. }) => {
+                skipped.push((model.clone(), e.to_string()));
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| AgentError::ModelNotAvailable {
+        model: config.model.clone(),
+        message: "No models left in the fallback chain".to_string(),
+    }))
+}
+
+/// Ollama: the server answers on its root URL and `/api/show` knows the model.
+async fn check_ollama_ready(base_url: &str, model: &str) -> Result<ModelInfo, AgentError> {
+    let http = reqwest::Client::new();
+
+    // Step 1: Check Ollama is running.
+    http.get(base_url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| AgentError::ProviderUnavailable {
+            url: base_url.to_string(),
+            message: format!("Is Ollama running? {e}"),
+        })?;
+
+    // Step 2: Check model is available.
+    let show_url = format!("{base_url}api/show");
+    let resp = http
+        .post(&show_url)
+        .json(&serde_json::json!({ "model": model }))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| AgentError::ModelNotAvailable {
+            model: model.to_string(),
+            message: format!("Failed to query model info: {e}"),
+        })?;
+
+    if !resp.status().is_success() {
+        return Err(AgentError::ModelNotAvailable {
+            model: model.to_string(),
+            message: format!(
+                "Model not found (HTTP {}). Run `ollama pull {model}` to download it.",
+                resp.status()
+            ),
+        });
+    }
+
+    // Metadata is best-effort: an unparseable body still means the model exists.
+    let show: serde_json::Value = resp.json().await.unwrap_or_default();
+    Ok(ModelInfo {
+        context_length: ollama_context_length(&show),
+    })
+}
+
+/// Find `model_info.<arch>.context_length` in an `/api/show` response.
+fn ollama_context_length(show: &serde_json::Value) -> Option<usize> {
+    show["model_info"]
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())
+        .and_then(|n| usize::try_from(n).ok())
+}
+
+/// OpenAI-compatible: `GET models` succeeds and lists the model.
+///
+/// Servers without a usable `models` endpoint are accepted once they answer,
+/// since the model cannot be verified up front; the first request will fail
+/// loudly if it is wrong.
+async fn check_openai_ready(
+    base_url: &str,
+    model: &str,
+    api_key: Option<&str>,
+) -> Result<ModelInfo, AgentError> {
+    let models_url = format!("{base_url}models");
+    let mut req = reqwest::Client::new()
+        .get(&models_url)
+        .timeout(Duration::from_secs(10));
+    if let Some(key) = api_key {
+        req = req.bearer_auth(key);
+    }
+
+    let resp = req.send().await.map_err(|e| AgentError::ProviderUnavailable {
+        url: base_url.to_string(),
+        message: format!("Is the server running? {e}"),
+    })?;
+
+    let status = resp.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(AgentError::ProviderUnavailable {
+            url: base_url.to_string(),
+            message: format!("Authentication failed (HTTP {status}). Check provider.api_key_env."),
+        });
+    }
+    if !status.is_success() {
+        tracing::warn!("{models_url} returned HTTP {status}; skipping model check");
+        return Ok(ModelInfo::default());
+    }
+
+    let models: Vec<serde_json::Value> = match resp.json::<serde_json::Value>().await {
+        Ok(mut body) => match body["data"].take() {
+            serde_json::Value::Array(models) => models,
+            _ => Vec::new(),
+        },
+        Err(e) => {
+            tracing::warn!("Unparseable model list from {models_url}: {e}; skipping model check");
+            return Ok(ModelInfo::default());
+        }
+    };
+    if models.is_empty() {
+        return Ok(ModelInfo::default());
+    }
+
+    let Some(entry) = models.iter().find(|m| m["id"].as_str() == Some(model)) else {
+        let ids: Vec<&str> = models.iter().filter_map(|m| m["id"].as_str()).collect();
+        return Err(AgentError::ModelNotAvailable {
+            model: model.to_string(),
+            message: format!("Server offers: {}", ids.join(", ")),
+        });
+    };
+
+    let context_length = entry["max_model_len"]
+        .as_u64()
+        .or_else(|| entry["meta"]["n_ctx_train"].as_u64())
+        .and_then(|n| usize::try_from(n).ok());
+    Ok(ModelInfo { context_length })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PartialConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve canned `(path, status, body)` responses on a local port.
+    async fn stand_in(routes: Vec<(&'static str, u16, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let (status, body) = routes
+                    .iter()
+                    .find(|(p, _, _)| *p == path)
+                    .map_or((404, ""), |(_, s, b)| (*s, *b));
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}/")
+    }
+
+    fn config(kind: ProviderKind, base_url: &str, model: &str) -> AppConfig {
+        PartialConfig {
+            model: Some(model.to_string()),
+            provider_kind: Some(kind),
+            provider_base_url: Some(base_url.to_string()),
+            ..Default::default()
+        }
+        .finalize()
+    }
+
+    /// Verify that the health check returns a sensible error when Ollama is
+    /// not running (which is the expected state in CI / test environments).
+    #[tokio::test]
+    async fn health_check_returns_error_when_ollama_unavailable() {
+        let config = PartialConfig {
+            model: Some("test-model".to_string()),
+            ..Default::default()
+        }
+        .finalize();
+        let result = check_provider_ready(&config).await;
+
+        // In test environments Ollama is typically not running, so we expect
+        // a ProviderUnavailable error. If Ollama happens to be running, the
+        // test still passes (the model check may or may not succeed).
+        match result {
+            Err(AgentError::ProviderUnavailable { url, message }) => {
+                assert!(url.contains("11434"));
+                assert!(!message.is_empty());
+            }
+            // Ollama is running but model not found -- also acceptable.
+            Err(AgentError::ModelNotAvailable { model, message }) => {
+                assert_eq!(model, "test-model");
+                assert!(!message.is_empty());
+            }
+            // Ollama is running AND the model exists -- unlikely but fine.
+            Ok(_) => {}
+            // Any other error variant is unexpected.
+            Err(other) => panic!("Unexpected error variant: {other}"),
+        }
+    }
+
+    #[test]
+    fn endpoint_url_adds_trailing_slash() {
+        assert_eq!(endpoint_url("http://box:8000/v1"), "http://box:8000/v1/");
+        assert_eq!(endpoint_url("http://box:11434/"), "http://box:11434/");
+    }
+
+    #[test]
+    fn derived_model_name_is_a_valid_ollama_name() {
+        assert_eq!(derived_model_name("qwen2.5:7b", 32768), "ouro/qwen2.5-7b:ctx32768");
+        assert_eq!(derived_model_name("hf.co/org/m:Q4", 8192), "ouro/hf.co-org-m-Q4:ctx8192");
+    }
+
+    #[test]
+    fn chat_options_maps_model_options() {
+        let config = PartialConfig {
+            temperature: Some(0.2),
+            seed: Some(7),
+            stop_sequences: Some(vec!["END".to_string()]),
+            ..Default::default()
+        }
+        .finalize();
+        let options = chat_options(&config);
+        assert_eq!(options.temperature, Some(0.2));
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.top_p, None);
+        assert_eq!(options.max_tokens, None);
+        assert_eq!(options.stop_sequences, vec!["END"]);
+    }
+
+    #[tokio::test]
+    async fn prepare_model_derives_ollama_model_with_num_ctx() {
+        let base = stand_in(vec![
+            ("/api/show", 404, r#"{"error":"model not found"}"#),
+            ("/api/create", 200, r#"{"status":"success"}"#),
+        ])
+        .await;
+        let mut config = config(ProviderKind::Ollama, &base, "qwen2.5:7b");
+        config.num_ctx = Some(16384);
+        assert_eq!(prepare_model(&config).await, "ouro/qwen2.5-7b:ctx16384");
+
+        config.num_ctx = None;
+        config.context_limit = 8192;
+        assert_eq!(prepare_model(&config).await, "ouro/qwen2.5-7b:ctx8192");
+
+        config.num_ctx = Some(0);
+        assert_eq!(prepare_model(&config).await, "qwen2.5:7b");
+
+        let mut config = self::config(ProviderKind::OpenAi, "http://127.0.0.1:1/", "m");
+        config.num_ctx = Some(16384);
+        assert_eq!(prepare_model(&config).await, "m", "num_ctx is Ollama-only");
+    }
+
+    #[tokio::test]
+    async fn prepare_model_reuses_existing_derived_model() {
+        // No create route: creating would fail and fall back to the base model.
+        let base = stand_in(vec![("/api/show", 200, "{}")]).await;
+        let mut config = config(ProviderKind::Ollama, &base, "qwen2.5:7b");
+        config.num_ctx = Some(16384);
+        assert_eq!(prepare_model(&config).await, "ouro/qwen2.5-7b:ctx16384");
+    }
+
+    #[tokio::test]
+    async fn prepare_model_falls_back_to_base_model_when_create_fails() {
+        let base = stand_in(vec![
+            ("/api/show", 404, r#"{"error":"model not found"}"#),
+            ("/api/create", 404, r#"{"error":"model not found"}"#),
+        ])
+        .await;
+        let mut config = config(ProviderKind::Ollama, &base, "qwen2.5:7b");
+        config.num_ctx = Some(16384);
+        assert_eq!(prepare_model(&config).await, "qwen2.5:7b");
+    }
+
+    #[tokio::test]
+    async fn unreachable_server_is_provider_unavailable() {
+        let config = config(ProviderKind::OpenAi, "http://127.0.0.1:1/v1", "m");
+        match check_provider_ready(&config).await {
+            Err(AgentError::ProviderUnavailable { url, .. }) => {
+                assert_eq!(url, "http://127.0.0.1:1/v1/");
+            }
+            other => panic!("expected ProviderUnavailable, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ollama_check_uses_configured_base_url() {
+        let base = stand_in(vec![("/", 200, "Ollama is running"), ("/api/show", 404, "{}")]).await;
+        let result = check_provider_ready(&config(ProviderKind::Ollama, &base, "qwen")).await;
+        assert!(matches!(result, Err(AgentError::ModelNotAvailable { .. })));
+
+        let base = stand_in(vec![("/", 200, "Ollama is running"), ("/api/show", 200, "{}")]).await;
+        let info = check_provider_ready(&config(ProviderKind::Ollama, &base, "qwen")).await;
+        assert_eq!(info.unwrap(), ModelInfo::default());
+    }
+
+    #[tokio::test]
+    async fn ollama_check_reports_context_length() {
+        let show = r#"{"model_info":{"general.architecture":"qwen2","qwen2.context_length":32768}}"#;
+        let base = stand_in(vec![("/", 200, "Ollama is running"), ("/api/show", 200, show)]).await;
+        let info = check_provider_ready(&config(ProviderKind::Ollama, &base, "qwen")).await;
+        assert_eq!(info.unwrap().context_length, Some(32768));
+    }
+
+    #[tokio::test]
+    async fn openai_check_looks_for_model_in_list() {
+        let models = r#"{"object":"list","data":[{"id":"qwen2.5-7b","max_model_len":32768},{"id":"llama-3"}]}"#;
+        let base = stand_in(vec![("/v1/models", 200, models)]).await;
+        let base = format!("{base}v1");
+
+        let info = check_provider_ready(&config(ProviderKind::OpenAi, &base, "llama-3")).await;
+        assert_eq!(info.unwrap().context_length, None);
+        let info = check_provider_ready(&config(ProviderKind::OpenAi, &base, "qwen2.5-7b")).await;
+        assert_eq!(info.unwrap().context_length, Some(32768));
+        match check_provider_ready(&config(ProviderKind::OpenAi, &base, "gpt-4")).await {
+            Err(AgentError::ModelNotAvailable { message, .. }) => {
+                assert!(message.contains("qwen2.5-7b, llama-3"));
+            }
+            other => panic!("expected ModelNotAvailable, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn openai_check_reports_auth_failure_and_tolerates_missing_endpoint() {
+        let base = stand_in(vec![("/models", 401, "{}")]).await;
+        let result = check_provider_ready(&config(ProviderKind::OpenAi, &base, "m")).await;
+        assert!(matches!(result, Err(AgentError::ProviderUnavailable { .. })));
+
+        // No /models route: the server answered, so the check passes.
+        let base = stand_in(vec![]).await;
+        assert!(check_provider_ready(&config(ProviderKind::OpenAi, &base, "m")).await.is_ok());
+    }
+
+    #[test]
+    fn resolve_context_limit_applies_model_maximum() {
+        let default = PartialConfig::default().finalize();
+
+        // Default limit: lowered to a smaller model maximum, never raised.
+        let (resolved, warning) = resolve_context_limit(&default, Some(8192));
+        assert_eq!(resolved.context_limit, 8192);
+        assert_eq!(resolved.context_limit_source, ContextLimitSource::Model);
+        assert!(warning.is_none());
+        let (resolved, _) = resolve_context_limit(&default, Some(131072));
+        assert_eq!(resolved.context_limit, 32768);
+        assert_eq!(resolved.context_limit_source, ContextLimitSource::Default);
+
+        // Explicit limit above the model maximum: capped with a warning.
+        let explicit = PartialConfig {
+            context_limit: Some(65536),
+            ..Default::default()
+        }
+        .finalize();
+        let (resolved, warning) = resolve_context_limit(&explicit, Some(32768));
+        assert_eq!(resolved.context_limit, 32768);
+        assert!(warning.unwrap().contains("exceeds"));
+        let (resolved, warning) = resolve_context_limit(&explicit, None);
+        assert_eq!(resolved.context_limit, 65536);
+        assert_eq!(resolved.context_limit_source, ContextLimitSource::Config);
+        assert!(warning.is_none());
+    }
+
+    #[tokio::test]
+    async fn select_available_model_skips_missing_models() {
+        let models = r#"{"object":"list","data":[{"id":"llama-3"}]}"#;
+        let base = stand_in(vec![("/models", 200, models)]).await;
+        let mut config = config(ProviderKind::OpenAi, &base, "gone");
+        config.fallback_models = vec!["gone".into(), "llama-3".into(), "other".into()];
+
+        let chain = model_chain(&config);
+        assert_eq!(chain, vec!["gone", "llama-3", "other"]);
+
+        let selection = select_available_model(&config, &chain).await.unwrap();
+        assert_eq!(selection.config.model, "llama-3");
+        assert_eq!(selection.skipped.len(), 1);
+        assert_eq!(selection.skipped[0].0, "gone");
+
+        let result = select_available_model(&config, &chain[2..]).await;
+        assert!(matches!(result, Err(AgentError::ModelNotAvailable { model, .. }) if model == "other"));
+    }
+}
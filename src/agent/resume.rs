@@ -0,0 +1,812 @@
+//! Session resume: rehydrate conversation state from a JSONL session log.
+//!
+//! `ouro resume` locates the most recent `session-*.jsonl` in `.ouro-logs/`,
+//! parses it back into [`LogEntry`] values, and rebuilds the `ChatMessage`
+//! history the agent loop had built up. The outer restart loop then continues
+//! with that history as carryover, exactly as it would after a context restart.
+//!
+//! Rebuilding mirrors what the live loop appends to the chat request:
+//! - text-only assistant turns become assistant messages
+//! - a turn's tool calls become one assistant message followed by its tool
+//!   responses (assistant text that accompanied tool calls is dropped, as live)
+//! - harness system messages (wind-down notices, etc.) are kept
+//! - turns replaced by a summary are replaced by it again
+//! - masking rounds are replayed, masking the same number of observations
+//!
+//! Tool calls that never got a result (the harness died mid-execution) receive
+//! a synthetic error response so call/response pairing stays intact.
+
+use std::path::{Path, PathBuf};
+
+use genai::chat::{ChatMessage, ChatRequest, ChatRole, ToolCall, ToolResponse};
+
+use crate::agent::agent_loop::{extract_carryover, restart_marker};
+use crate::agent::context_manager::{generate_mask_notification, mask_observations, ContextManager};
+use crate::agent::logging::{LogEntry, SessionLogger};
+use crate::agent::observations::{session_key, ObservationStore};
+use crate::agent::summarize::{select_oldest_turns, summary_message};
+use crate::agent::tokens::{request_text, LocalEstimator};
+use crate::config::AppConfig;
+
+/// Prefix of the system message appended to a resumed conversation.
+///
+/// The agent loop checks for it so it does not also inject the
+/// "context was full" restart marker into a resumed session.
+pub const RESUME_MARKER_PREFIX: &str = "[Session resumed";
+
+/// Result content for tool calls that were interrupted before completing.
+const INTERRUPTED_TOOL_RESULT: &str =
+    r#"{"error":"interrupted: the harness stopped before this tool call completed"}"#;
+
+/// Conversation state recovered from a session log.
+#[derive(Debug)]
+pub struct ResumeState {
+    /// Log file the state was recovered from.
+    pub log_path: PathBuf,
+    /// Session number to continue with.
+    pub session_number: u32,
+    /// Messages to pass to `run_agent_session` as carryover.
+    pub carryover_messages: Vec<ChatMessage>,
+    /// Turns completed in the logged session.
+    pub turns: u64,
+}
+
+/// Find the most recent `session-*.jsonl` log for the given workspace.
+///
+/// Session filenames embed a sortable UTC timestamp, so the lexicographically
+/// greatest name is the latest. Returns `Ok(None)` if no logs exist.
+pub fn find_latest_session_log(workspace: &Path) -> anyhow::Result<Option<PathBuf>> {
+    Ok(session_logs(workspace)?.pop())
+}
+
+/// Find the most recent session log other than `current`, the log of the
+/// session that is just starting.
+pub fn find_previous_session_log(
+    workspace: &Path,
+    current: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+    Ok(session_logs(workspace)?
+        .into_iter()
+        .rev()
+        .find(|path| path != current))
+}
+
+/// All `session-*.jsonl` logs for the given workspace, oldest first.
+fn session_logs(workspace: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let log_dir = SessionLogger::log_dir_for(workspace)?;
+    let entries = match std::fs::read_dir(&log_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut logs = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_session_log = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("session-") && n.ends_with(".jsonl"));
+        if is_session_log {
+            logs.push(path);
+        }
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+/// Parse a JSONL session log into log entries.
+///
+/// Lines that fail to parse are skipped with a warning -- a crash can leave a
+/// truncated final line, and that should not prevent resuming.
+pub fn read_log_entries(path: &Path) -> anyhow::Result<Vec<LogEntry>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut entries = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<LogEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                tracing::warn!("Skipping unparseable line {} in {}: {}", i + 1, path.display(), e);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Load resume state from the latest session log in the workspace.
+///
+/// If the logged session ended in a context restart, the next session number
+/// is used and only the last `carryover_turns` turns are carried over (the
+/// same as a live restart). Otherwise the session was interrupted: it is
+/// continued under the same number with its history, as masked and
+/// summarized when it stopped, plus a resume notice. If that history alone
+/// is past the hard threshold, it is treated as a restart instead.
+///
+/// A session that was itself restarted or resumed began with its
+/// predecessor's carryover, so the chain of logs named by `resumed_from` is
+/// rebuilt first, oldest to newest, each seeding the next.
+pub fn load_resume_state(config: &AppConfig) -> anyhow::Result<ResumeState> {
+    let workspace = &config.workspace;
+    let log_path = find_latest_session_log(workspace)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No session logs found in {}",
+            SessionLogger::log_dir_for(workspace)
+                .map(|d| d.display().to_string())
+                .unwrap_or_default()
+        )
+    })?;
+
+    let mut chain = vec![(log_path.clone(), read_log_entries(&log_path)?)];
+    while let Some(previous) = chain.last().and_then(|(_, entries)| resumed_from(entries)) {
+        if chain.iter().any(|(path, _)| *path == previous) {
+            tracing::warn!(
+                "Session logs resume each other in a cycle at {}; stopping there",
+                previous.display()
+            );
+            break;
+        }
+        match read_log_entries(&previous) {
+            Ok(entries) => chain.push((previous, entries)),
+            Err(e) => {
+                tracing::warn!(
+                    "Cannot read earlier session log {}: {}; resuming without its history",
+                    previous.display(),
+                    e
+                );
+                break;
+            }
+        }
+    }
+
+    let mut seed = Vec::new();
+    let mut state = None;
+    for (path, entries) in chain.into_iter().rev() {
+        let resumed = resume_from_entries(config, path, &entries, seed);
+        seed = seeded_messages(&resumed);
+        state = Some(resumed);
+    }
+    Ok(state.expect("the chain holds at least the latest log"))
+}
+
+/// The log a session's `session_start` entry names as its predecessor.
+fn resumed_from(entries: &[LogEntry]) -> Option<PathBuf> {
+    entries.iter().find_map(|entry| match entry {
+        LogEntry::SessionStart { resumed_from, .. } => resumed_from.as_ref().map(PathBuf::from),
+        _ => None,
+    })
+}
+
+/// Resume state for one logged session that began with `seed`.
+fn resume_from_entries(
+    config: &AppConfig,
+    log_path: PathBuf,
+    entries: &[LogEntry],
+    seed: Vec<ChatMessage>,
+) -> ResumeState {
+    let mut context_manager = ContextManager::new(
+        config.context_limit,
+        config.soft_threshold_pct,
+        config.hard_threshold_pct,
+        config.carryover_turns,
+    )
+    .with_mask_policies(config.mask_policies.clone());
+    // Replayed masking names the logged session in its placeholders, as the
+    // live session did, so the agent can still recall what it masked.
+    if let Some(log_dir) = log_path.parent() {
+        context_manager = context_manager
+            .with_observation_store(ObservationStore::new(log_dir, &session_key(&log_path)));
+    }
+    let rebuilt = rebuild_history(seed, entries, &mut context_manager);
+
+    let estimated_tokens =
+        LocalEstimator.estimate(&request_text(&ChatRequest::new(rebuilt.messages.clone())));
+    let too_large = estimated_tokens as f64 >= config.context_limit as f64 * config.hard_threshold_pct;
+    if too_large && !rebuilt.ended_with_restart {
+        tracing::info!(
+            estimated_tokens,
+            context_limit = config.context_limit,
+            "Interrupted session is past the hard threshold; resuming as a restart"
+        );
+    }
+
+    let (session_number, carryover_messages) = if rebuilt.ended_with_restart || too_large {
+        (
+            rebuilt.session_number + 1,
+            extract_carryover(&rebuilt.messages, config.carryover_turns),
+        )
+    } else {
+        let mut messages = rebuilt.messages;
+        messages.push(ChatMessage::system(format!(
+            "{RESUME_MARKER_PREFIX}. Session #{} was interrupted after {} turns and has been \
+             restored from its log. Continue where you left off.]",
+            rebuilt.session_number, rebuilt.turns
+        )));
+        (rebuilt.session_number, messages)
+    };
+
+    ResumeState {
+        log_path,
+        session_number,
+        carryover_messages,
+        turns: rebuilt.turns,
+    }
+}
+
+/// The messages a session continuing from `state` starts with: its
+/// carryover, plus the restart notice the agent loop adds after a restart.
+fn seeded_messages(state: &ResumeState) -> Vec<ChatMessage> {
+    let mut messages = state.carryover_messages.clone();
+    if state.session_number > 1 && !messages.last().is_some_and(is_resume_marker) {
+        messages.push(restart_marker(state.session_number));
+    }
+    messages
+}
+
+/// Whether a message is the resume notice appended by [`load_resume_state`].
+pub fn is_resume_marker(msg: &ChatMessage) -> bool {
+    msg.role == ChatRole::System
+        && msg
+            .content
+            .first_text()
+            .is_some_and(|t| t.starts_with(RESUME_MARKER_PREFIX))
+}
+
+// ---------------------------------------------------------------------------
+// History reconstruction
+// ---------------------------------------------------------------------------
+
+/// Conversation history rebuilt from one session's log entries.
+#[derive(Debug)]
+pub struct RebuiltHistory {
+    /// Messages in the order the agent loop appended them.
+    pub messages: Vec<ChatMessage>,
+    /// Session number recorded in `session_start` / `session_restart`.
+    pub session_number: u32,
+    /// Highest turn number seen.
+    pub turns: u64,
+    /// Whether the session ended with a context-full restart.
+    pub ended_with_restart: bool,
+}
+
+/// Accumulates one turn's tool calls and their results until the turn ends.
+#[derive(Default)]
+struct ToolGroup {
+    turn: u64,
+    calls: Vec<ToolCall>,
+    results: Vec<ToolResponse>,
+}
+
+impl ToolGroup {
+    /// Emit the assistant tool-call message followed by its responses.
+    fn flush_into(&mut self, messages: &mut Vec<ChatMessage>) {
+        if self.calls.is_empty() {
+            self.results.clear();
+            return;
+        }
+        let calls = std::mem::take(&mut self.calls);
+        let mut results = std::mem::take(&mut self.results);
+
+        // Pair every call with a response, synthesizing one if it is missing.
+        for call in &calls {
+            if !results.iter().any(|r| r.call_id == call.call_id) {
+                results.push(ToolResponse::new(call.call_id.clone(), INTERRUPTED_TOOL_RESULT));
+            }
+        }
+
+        messages.push(ChatMessage::from(calls));
+        for result in results {
+            messages.push(result.into());
+        }
+    }
+}
+
+/// Rebuild the chat history from a single session's log entries, starting
+/// from `seed`, the messages the session began with.
+///
+/// Masking rounds are replayed with `context_manager`'s mask policies.
+pub fn rebuild_history(
+    seed: Vec<ChatMessage>,
+    entries: &[LogEntry],
+    context_manager: &mut ContextManager,
+) -> RebuiltHistory {
+    let mut messages = seed;
+    let mut session_number: u32 = 1;
+    let mut turns: u64 = 0;
+    let mut ended_with_restart = false;
+
+    // Assistant text is held back until we know whether its turn made tool
+    // calls: the live loop only appends text from text-only turns.
+    let mut pending_text: Option<(u64, String)> = None;
+    let mut group = ToolGroup::default();
+
+    for entry in entries {
+        match entry {
+            LogEntry::SessionStart {
+                session_number: n, ..
+            } => {
+                if *n > 0 {
+                    session_number = *n;
+                }
+            }
+            LogEntry::AssistantText { turn, content, .. } => {
+                turns = turns.max(*turn);
+                group.flush_into(&mut messages);
+                if let Some((_, text)) = pending_text.take() {
+                    messages.push(ChatMessage::assistant(text));
+                }
+                pending_text = Some((*turn, content.clone()));
+            }
+            LogEntry::ToolCall {
+                turn,
+                call_id,
+                fn_name,
+                fn_arguments,
+                ..
+            } => {
+                turns = turns.max(*turn);
+                match pending_text.take() {
+                    Some((text_turn, _)) if text_turn == *turn => {}
+                    Some((_, text)) => messages.push(ChatMessage::assistant(text)),
+                    None => {}
+                }
+                if group.turn != *turn {
+                    group.flush_into(&mut messages);
+                    group.turn = *turn;
+                }
+                group.calls.push(ToolCall {
+                    call_id: call_id.clone(),
+                    fn_name: fn_name.clone(),
+                    fn_arguments: fn_arguments.clone(),
+                    thought_signatures: None,
+                });
+            }
+            LogEntry::ToolResult {
+                turn,
+                call_id,
+                result,
+                ..
+            } => {
+                if group.turn == *turn {
+                    group
+                        .results
+                        .push(ToolResponse::new(call_id.clone(), result.clone()));
+                }
+            }
+            LogEntry::SystemMessage { content, .. } => {
+                group.flush_into(&mut messages);
+                if let Some((_, text)) = pending_text.take() {
+                    messages.push(ChatMessage::assistant(text));
+                }
+                messages.push(ChatMessage::system(content.clone()));
+            }
+            LogEntry::SessionRestart {
+                session_number: n, ..
+            } => {
+                session_number = *n;
+                ended_with_restart = true;
+            }
+            LogEntry::Error { turn, .. } => {
+                turns = turns.max(*turn);
+            }
+            LogEntry::ContextMask {
+                observations_masked,
+                total_masked,
+                context_reclaimed_pct,
+                ..
+            } => {
+                group.flush_into(&mut messages);
+                if let Some((_, text)) = pending_text.take() {
+                    messages.push(ChatMessage::assistant(text));
+                }
+                mask_observations(&mut messages, *observations_masked, context_manager);
+                messages.push(ChatMessage::system(generate_mask_notification(
+                    *observations_masked,
+                    *total_masked,
+                    *context_reclaimed_pct,
+                )));
+            }
+            LogEntry::ContextSummary {
+                turns_summarized,
+                summary,
+                ..
+            } => {
+                group.flush_into(&mut messages);
+                if let Some((_, text)) = pending_text.take() {
+                    messages.push(ChatMessage::assistant(text));
+                }
+                // Logs from before the summary text was recorded keep the
+                // turns verbatim.
+                if !summary.is_empty()
+                    && let Some(range) = select_oldest_turns(&messages, *turns_summarized)
+                {
+                    messages.splice(range, [summary_message(*turns_summarized, summary)]);
+                }
+            }
+            LogEntry::SessionEnd { .. }
+            | LogEntry::TokenUsage { .. }
+            | LogEntry::TokenEstimate { .. }
+            | LogEntry::Handoff { .. }
+            | LogEntry::Discovery { .. }
+            | LogEntry::Retry { .. }
+            | LogEntry::ModelSwitch { .. } => {}
+        }
+    }
+
+    group.flush_into(&mut messages);
+    if let Some((_, text)) = pending_text.take() {
+        messages.push(ChatMessage::assistant(text));
+    }
+
+    RebuiltHistory {
+        messages,
+        session_number,
+        turns,
+        ended_with_restart,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(workspace: &Path, carryover_turns: usize) -> AppConfig {
+        AppConfig {
+            carryover_turns,
+            ..crate::config::test_config(workspace)
+        }
+    }
+
+    fn rebuild(entries: &[LogEntry]) -> RebuiltHistory {
+        rebuild_history(Vec::new(), entries, &mut ContextManager::new(32768, 0.7, 0.9, 5))
+    }
+
+    fn ts() -> String {
+        "2026-01-01T00:00:00.000Z".to_string()
+    }
+
+    fn start(n: u32) -> LogEntry {
+        resumed_start(n, None)
+    }
+
+    fn resumed_start(n: u32, resumed_from: Option<&Path>) -> LogEntry {
+        LogEntry::SessionStart {
+            timestamp: ts(),
+            model: "m".into(),
+            workspace: "/ws".into(),
+            session_number: n,
+            context_limit: 32768,
+            context_limit_source: "default".into(),
+            resumed_from: resumed_from.map(|p| p.display().to_string()),
+        }
+    }
+
+    fn text(turn: u64, content: &str) -> LogEntry {
+        LogEntry::AssistantText {
+            timestamp: ts(),
+            turn,
+            content: content.into(),
+        }
+    }
+
+    fn call(turn: u64, id: &str) -> LogEntry {
+        LogEntry::ToolCall {
+            timestamp: ts(),
+            turn,
+            call_id: id.into(),
+            fn_name: "shell_exec".into(),
+            fn_arguments: serde_json::json!({"command": "ls"}),
+        }
+    }
+
+    fn result(turn: u64, id: &str, content: &str) -> LogEntry {
+        LogEntry::ToolResult {
+            timestamp: ts(),
+            turn,
+            call_id: id.into(),
+            fn_name: "shell_exec".into(),
+            result: content.into(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn rebuild_text_only_turns() {
+        let entries = vec![start(1), text(1, "first"), text(2, "second")];
+        let rebuilt = rebuild(&entries);
+
+        assert_eq!(rebuilt.messages.len(), 2);
+        assert_eq!(rebuilt.messages[0].content.first_text(), Some("first"));
+        assert_eq!(rebuilt.messages[1].content.first_text(), Some("second"));
+        assert_eq!(rebuilt.turns, 2);
+        assert_eq!(rebuilt.session_number, 1);
+        assert!(!rebuilt.ended_with_restart);
+    }
+
+    #[test]
+    fn rebuild_groups_interleaved_tool_calls_per_turn() {
+        // The live loop logs call/result pairs interleaved, but the chat
+        // history holds one assistant message with all calls, then results.
+        let entries = vec![
+            start(1),
+            text(1, "Let me look around."),
+            call(1, "c1"),
+            result(1, "c1", "out1"),
+            call(1, "c2"),
+            result(1, "c2", "out2"),
+            text(2, "Done."),
+        ];
+        let rebuilt = rebuild(&entries);
+
+        // Text from the tool-call turn is dropped, as in the live loop.
+        assert_eq!(rebuilt.messages.len(), 4);
+        assert_eq!(rebuilt.messages[0].role, ChatRole::Assistant);
+        assert_eq!(rebuilt.messages[0].content.tool_calls().len(), 2);
+        assert_eq!(rebuilt.messages[1].role, ChatRole::Tool);
+        assert_eq!(rebuilt.messages[1].content.tool_responses()[0].call_id, "c1");
+        assert_eq!(rebuilt.messages[2].content.tool_responses()[0].call_id, "c2");
+        assert_eq!(rebuilt.messages[3].content.first_text(), Some("Done."));
+    }
+
+    #[test]
+    fn rebuild_synthesizes_missing_tool_results() {
+        let entries = vec![start(1), call(1, "c1"), result(1, "c1", "ok"), call(1, "c2")];
+        let rebuilt = rebuild(&entries);
+
+        assert_eq!(rebuilt.messages.len(), 3);
+        let last = rebuilt.messages[2].content.tool_responses()[0].clone();
+        assert_eq!(last.call_id, "c2");
+        assert!(last.content.contains("interrupted"));
+    }
+
+    #[test]
+    fn rebuild_keeps_system_messages_and_session_number() {
+        let entries = vec![
+            start(3),
+            text(1, "thinking"),
+            LogEntry::SystemMessage {
+                timestamp: ts(),
+                content: "[Context window 91% full.]".into(),
+            },
+            LogEntry::SessionRestart {
+                timestamp: ts(),
+                session_number: 3,
+                previous_turns: 1,
+                carryover_messages: 2,
+                reason: "hard_threshold_exceeded".into(),
+            },
+        ];
+        let rebuilt = rebuild(&entries);
+
+        assert_eq!(rebuilt.session_number, 3);
+        assert!(rebuilt.ended_with_restart);
+        assert_eq!(rebuilt.messages.len(), 2);
+        assert_eq!(rebuilt.messages[1].role, ChatRole::System);
+    }
+
+    #[test]
+    fn rebuild_replaces_summarized_turns_with_the_summary() {
+        let mut entries = vec![start(1)];
+        for turn in 1..=4 {
+            let id = format!("c{turn}");
+            entries.push(call(turn, &id));
+            entries.push(result(turn, &id, &"x".repeat(1000)));
+        }
+        entries.push(LogEntry::ContextSummary {
+            timestamp: ts(),
+            turns_summarized: 2,
+            messages_replaced: 4,
+            summary_chars: 12,
+            model: "m".into(),
+            summary: "Listed files".into(),
+        });
+        entries.push(text(5, "next"));
+        let rebuilt = rebuild(&entries);
+
+        // One summary, turns 3 and 4 (call + result each), then the text.
+        assert_eq!(rebuilt.messages.len(), 6);
+        assert_eq!(rebuilt.messages[0].role, ChatRole::System);
+        let summary = rebuilt.messages[0].content.first_text().unwrap();
+        assert!(summary.contains("(2 turns)") && summary.ends_with("Listed files"), "{summary}");
+        assert_eq!(rebuilt.messages[1].content.tool_calls()[0].call_id, "c3");
+        assert_eq!(rebuilt.messages[5].content.first_text(), Some("next"));
+    }
+
+    /// Write entries to a session log inside `{tmp}/.ouro-logs/`.
+    fn write_log(tmp: &TempDir, name: &str, entries: &[LogEntry]) {
+        let dir = tmp.path().join(".ouro-logs");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut body = String::new();
+        for e in entries {
+            body.push_str(&serde_json::to_string(e).unwrap());
+            body.push('\n');
+        }
+        std::fs::write(dir.join(name), body).unwrap();
+    }
+
+    #[test]
+    fn find_latest_picks_newest_session_file() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        assert!(find_latest_session_log(&workspace).unwrap().is_none());
+
+        write_log(&tmp, "session-2026-01-01T00-00-00.jsonl", &[start(1)]);
+        write_log(&tmp, "session-2026-01-02T00-00-00.jsonl", &[start(2)]);
+        std::fs::write(tmp.path().join(".ouro-logs/other.txt"), "x").unwrap();
+
+        let latest = find_latest_session_log(&workspace).unwrap().unwrap();
+        assert!(latest.ends_with("session-2026-01-02T00-00-00.jsonl"));
+
+        let previous = find_previous_session_log(&workspace, &latest).unwrap().unwrap();
+        assert!(previous.ends_with("session-2026-01-01T00-00-00.jsonl"));
+    }
+
+    #[test]
+    fn load_resume_state_continues_interrupted_session() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        write_log(
+            &tmp,
+            "session-2026-01-01T00-00-00.jsonl",
+            &[start(2), text(1, "hello"), text(2, "still here")],
+        );
+        // Simulate a truncated final line from a crash.
+        let path = tmp.path().join(".ouro-logs/session-2026-01-01T00-00-00.jsonl");
+        let mut body = std::fs::read_to_string(&path).unwrap();
+        body.push_str("{\"event_type\":\"assistant_te");
+        std::fs::write(&path, body).unwrap();
+
+        let state = load_resume_state(&config(&workspace, 5)).unwrap();
+        assert_eq!(state.session_number, 2);
+        assert_eq!(state.turns, 2);
+        assert_eq!(state.carryover_messages.len(), 3);
+        assert!(is_resume_marker(state.carryover_messages.last().unwrap()));
+    }
+
+    #[test]
+    fn load_resume_state_advances_after_restart() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        write_log(
+            &tmp,
+            "session-2026-01-01T00-00-00.jsonl",
+            &[
+                start(4),
+                text(1, "a"),
+                text(2, "b"),
+                LogEntry::SessionRestart {
+                    timestamp: ts(),
+                    session_number: 4,
+                    previous_turns: 2,
+                    carryover_messages: 1,
+                    reason: "hard_threshold_exceeded".into(),
+                },
+            ],
+        );
+
+        let state = load_resume_state(&config(&workspace, 1)).unwrap();
+        assert_eq!(state.session_number, 5);
+        assert!(!state.carryover_messages.is_empty());
+        assert!(!state.carryover_messages.iter().any(is_resume_marker));
+    }
+
+    #[test]
+    fn load_resume_state_follows_earlier_resumes() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        let first = tmp.path().join(".ouro-logs/session-2026-01-01T00-00-00.jsonl");
+        write_log(
+            &tmp,
+            "session-2026-01-01T00-00-00.jsonl",
+            &[start(1), text(1, "first"), text(2, "second")],
+        );
+        write_log(
+            &tmp,
+            "session-2026-01-01T01-00-00.jsonl",
+            &[resumed_start(1, Some(&first)), text(3, "third")],
+        );
+
+        let state = load_resume_state(&config(&workspace, 5)).unwrap();
+        assert_eq!(state.session_number, 1);
+        let texts: Vec<_> = state
+            .carryover_messages
+            .iter()
+            .filter(|m| !is_resume_marker(m))
+            .filter_map(|m| m.content.first_text())
+            .collect();
+        assert_eq!(texts, ["first", "second", "third"]);
+        // The first resume's notice stays where the live session saw it.
+        assert_eq!(state.carryover_messages.len(), 5);
+        assert!(is_resume_marker(&state.carryover_messages[2]));
+        assert!(is_resume_marker(state.carryover_messages.last().unwrap()));
+    }
+
+    #[test]
+    fn load_resume_state_tolerates_missing_earlier_log() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        let gone = tmp.path().join("gone.jsonl");
+        write_log(
+            &tmp,
+            "session-2026-01-01T00-00-00.jsonl",
+            &[resumed_start(2, Some(&gone)), text(1, "only")],
+        );
+
+        let state = load_resume_state(&config(&workspace, 5)).unwrap();
+        assert_eq!(state.session_number, 2);
+        assert_eq!(state.carryover_messages.len(), 2);
+        assert_eq!(state.carryover_messages[0].content.first_text(), Some("only"));
+    }
+
+    /// A session of `turns` turns, each reading a 4000-char file.
+    fn large_session(turns: u64) -> Vec<LogEntry> {
+        let mut entries = vec![start(1)];
+        for turn in 1..=turns {
+            let id = format!("c{turn}");
+            entries.push(call(turn, &id));
+            entries.push(result(turn, &id, &"word ".repeat(800)));
+        }
+        entries
+    }
+
+    #[test]
+    fn load_resume_state_replays_masking() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        let mut entries = large_session(10);
+        entries.push(LogEntry::ContextMask {
+            timestamp: ts(),
+            observations_masked: 8,
+            total_masked: 8,
+            context_reclaimed_pct: 60.0,
+        });
+        write_log(&tmp, "session-2026-01-01T00-00-00.jsonl", &entries);
+        // Unmasked, the ten results alone are far past 90% of this limit.
+        let config = AppConfig {
+            context_limit: 4000,
+            ..config(&workspace, 2)
+        };
+
+        let state = load_resume_state(&config).unwrap();
+        assert_eq!(state.session_number, 1);
+        assert!(is_resume_marker(state.carryover_messages.last().unwrap()));
+        let masked = state
+            .carryover_messages
+            .iter()
+            .flat_map(|m| m.content.tool_responses())
+            .filter(|r| !r.content.starts_with("word"))
+            .count();
+        assert_eq!(masked, 8);
+        let notice = &state.carryover_messages[state.carryover_messages.len() - 2];
+        assert!(notice.content.first_text().unwrap().starts_with("[Context compressed"));
+    }
+
+    #[test]
+    fn load_resume_state_restarts_sessions_past_the_hard_threshold() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        write_log(&tmp, "session-2026-01-01T00-00-00.jsonl", &large_session(10));
+        let config = AppConfig {
+            context_limit: 4000,
+            ..config(&workspace, 2)
+        };
+
+        let state = load_resume_state(&config).unwrap();
+        assert_eq!(state.session_number, 2);
+        assert!(!state.carryover_messages.iter().any(is_resume_marker));
+        // Only the tail of the 20 logged messages is carried over.
+        assert!(state.carryover_messages.len() < 10);
+        let last = state.carryover_messages.last().unwrap().content.tool_responses()[0].clone();
+        assert_eq!(last.call_id, "c10");
+    }
+
+    #[test]
+    fn load_resume_state_errors_without_logs() {
+        let tmp = TempDir::new().unwrap();
+        let result = load_resume_state(&config(&tmp.path().join("workspace"), 5));
+        assert!(result.is_err());
+    }
+}
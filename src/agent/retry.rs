@@ -0,0 +1,188 @@
+//! Retry policy for LLM requests.
+//!
+//! Local model servers fail in mundane ways: the server restarts, a model is
+//! still loading into memory, a proxy returns 502, or the stream is cut off
+//! mid-response. These are worth retrying with exponential backoff. Failures
+//! that will not go away on their own (bad credentials, unknown model,
+//! malformed request) end the session as before.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::config::AppConfig;
+
+/// Backoff settings resolved from the `[retry]` config section.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first. Always at least 1.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of each delay randomly added or removed (0.0 - 1.0).
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            max_attempts: config.retry_max_attempts.max(1),
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+            jitter: config.retry_jitter.clamp(0.0, 1.0),
+        }
+    }
+
+    /// True if a failed attempt (1-based) may be followed by another.
+    pub fn should_retry(&self, attempt: u32, transient: bool) -> bool {
+        transient && attempt < self.max_attempts
+    }
+
+    /// Delay before retrying after failed attempt `attempt` (1-based).
+    ///
+    /// `base_delay * 2^(attempt-1)`, capped at `max_delay`, then spread by
+    /// `jitter` using `spread` in `[-1.0, 1.0]`.
+    pub fn delay_with_spread(&self, attempt: u32, spread: f64) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+        let factor = 1.0 + self.jitter * spread.clamp(-1.0, 1.0);
+        backoff.mul_f64(factor.max(0.0))
+    }
+
+    /// Delay before retrying after failed attempt `attempt`, with random jitter.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        self.delay_with_spread(attempt, random_spread())
+    }
+}
+
+/// A pseudo-random value in `[-1.0, 1.0]`. Jitter only needs to keep
+/// concurrent agents from retrying in lockstep, not real randomness.
+fn random_spread() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() as f64 / u64::MAX as f64) * 2.0 - 1.0
+}
+
+/// Error text fragments (lowercase) that mark a failure as transient.
+const TRANSIENT_MARKERS: &[&str] = &[
+    "connection refused",
+    "connectionrefused",
+    "connection reset",
+    "connectionreset",
+    "connection closed",
+    "connection aborted",
+    "tcp connect error",
+    "error sending request",
+    "broken pipe",
+    "timed out",
+    "timedout",
+    "unexpected eof",
+    "unexpectedeof",
+    "incomplete message",
+    "incompletemessage",
+    "loading model",
+    "model is loading",
+    "server busy",
+    "overloaded",
+    "temporarily unavailable",
+    "too many requests",
+];
+
+/// Classify an LLM request error message as transient (worth retrying).
+///
+/// Transient: connection failures and timeouts, HTTP 408/429/5xx, and servers
+/// reporting that the model is still loading. Everything else -- including
+/// other 4xx statuses such as auth failures or unknown models -- is fatal.
+pub fn is_transient(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    if TRANSIENT_MARKERS.iter().any(|marker| lower.contains(marker)) {
+        return true;
+    }
+    http_status(&lower).is_some_and(|status| matches!(status, 408 | 429 | 500..=599))
+}
+
+/// The HTTP status in an error message: the first number following a word
+/// ending in "status" (or "statuscode"), e.g. `status: 503`.
+fn http_status(text: &str) -> Option<u16> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.windows(2).find_map(|pair| {
+        let is_label = pair[0].ends_with("status") || pair[0].ends_with("statuscode");
+        is_label.then(|| pair[1].parse::<u16>().ok()).flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_attempts: u32, jitter: f64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(4),
+            jitter,
+        }
+    }
+
+    #[test]
+    fn delays_double_up_to_the_cap() {
+        let policy = policy(10, 0.0);
+        let delays: Vec<u128> = (1..=6)
+            .map(|attempt| policy.delay_with_spread(attempt, 0.0).as_millis())
+            .collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 4000, 4000]);
+        assert_eq!(policy.delay_for(40), Duration::from_secs(4), "no overflow");
+    }
+
+    #[test]
+    fn jitter_spreads_delay_within_bounds() {
+        let policy = policy(10, 0.5);
+        assert_eq!(policy.delay_with_spread(1, 1.0), Duration::from_millis(750));
+        assert_eq!(policy.delay_with_spread(1, -1.0), Duration::from_millis(250));
+        for _ in 0..20 {
+            let delay = policy.delay_for(1).as_millis();
+            assert!((250..=750).contains(&delay), "delay {delay} out of range");
+        }
+    }
+
+    #[test]
+    fn only_transient_failures_within_budget_retry() {
+        let policy = policy(3, 0.0);
+        assert!(policy.should_retry(1, true));
+        assert!(policy.should_retry(2, true));
+        assert!(!policy.should_retry(3, true), "budget spent");
+        assert!(!policy.should_retry(1, false), "fatal errors never retry");
+    }
+
+    #[test]
+    fn classifies_transient_errors() {
+        assert!(is_transient(
+            "WebModelCall { webc_error: Reqwest(reqwest::Error { kind: Request, source: \
+             ConnectError(\"tcp connect error\", Os { code: 111, kind: ConnectionRefused }) }) }"
+        ));
+        assert!(is_transient("ResponseFailedStatus { status: 503, body: \"server busy\" }"));
+        assert!(is_transient("ResponseFailedStatus { status: 502, body: \"\" }"));
+        assert!(is_transient("HTTP status 429 Too Many Requests"));
+        assert!(is_transient("{\"error\":\"llm server loading model\"}"));
+        assert!(is_transient("operation timed out"));
+    }
+
+    #[test]
+    fn classifies_fatal_errors() {
+        assert!(!is_transient("ResponseFailedStatus { status: 401, body: \"invalid api key\" }"));
+        assert!(!is_transient("ResponseFailedStatus { status: 404, body: \"model 'x' not found\" }"));
+        assert!(!is_transient("ResponseFailedStatus { status: 400, body: \"tokens: 500\" }"));
+        assert!(!is_transient("Invalid tool call arguments"));
+    }
+}
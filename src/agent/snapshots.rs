@@ -0,0 +1,465 @@
+//! Git-backed workspace snapshots, one per turn, with rollback.
+//!
+//! With `[snapshots] enabled = true`, the harness commits the workspace to a
+//! private git repository after every turn that ran `file_write`,
+//! `shell_exec`, `spawn_agent`, or `job_start`, and tags the commit
+//! `r{run}-s{session}-t{turn}`. The run is the harness process's start time
+//! in Unix seconds, since session numbers start over with every `ouro run`.
+//! The repository lives in `.ouro-logs/snapshots.git`, outside the workspace,
+//! so the agent never sees it and cannot rewrite its own history.
+//!
+//! `ouro snapshots list|diff|restore [<run>/]<session>:<turn>` reads it back;
+//! without a run, the latest run with that session and turn is meant. A
+//! restore first snapshots the current workspace, so it can be undone.
+//!
+//! The workspace's own `.gitignore` files are honored, and a `.git`
+//! directory the agent creates in the workspace is never captured. A nested
+//! git repository is recorded only as a reference to its current commit
+//! (a gitlink), so its files are neither snapshotted nor restored.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::process::Command;
+
+use crate::agent::logging::SessionLogger;
+use crate::cli::SnapshotAction;
+use crate::config::AppConfig;
+
+/// Directory name of the snapshot repository inside the log directory.
+pub const SNAPSHOTS_DIR: &str = "snapshots.git";
+
+/// Start time of this harness process in Unix seconds, naming its run.
+static CURRENT_RUN: LazyLock<u64> = LazyLock::new(|| {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+});
+
+/// A snapshot's name on the command line: `[<run>/]<session>:<turn>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotId {
+    /// Run that took the snapshot. `None` on the command line means the
+    /// latest run with this session and turn.
+    pub run: Option<u64>,
+    pub session: u32,
+    pub turn: u64,
+}
+
+impl SnapshotId {
+    /// The snapshot of `session` and `turn` in this harness process.
+    pub fn current_run(session: u32, turn: u64) -> Self {
+        Self {
+            run: Some(*CURRENT_RUN),
+            session,
+            turn,
+        }
+    }
+
+    /// Tag of the snapshot commit, or `None` if the run is not known.
+    pub fn tag(&self) -> Option<String> {
+        self.run
+            .map(|run| format!("r{run}-s{}-t{}", self.session, self.turn))
+    }
+
+    /// Parse a tag written by [`SnapshotId::tag`].
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let (run, rest) = tag.strip_prefix('r')?.split_once("-s")?;
+        let (session, turn) = rest.split_once("-t")?;
+        Some(Self {
+            run: Some(run.parse().ok()?),
+            session: session.parse().ok()?,
+            turn: turn.parse().ok()?,
+        })
+    }
+}
+
+impl FromStr for SnapshotId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = (|| {
+            let (run, rest) = match s.split_once('/') {
+                Some((run, rest)) => (Some(run.trim().parse().ok()?), rest),
+                None => (None, s),
+            };
+            let (session, turn) = rest.split_once(':')?;
+            Some(Self {
+                run,
+                session: session.trim().parse().ok()?,
+                turn: turn.trim().parse().ok()?,
+            })
+        })();
+        parsed.ok_or_else(|| {
+            anyhow::anyhow!("invalid snapshot '{s}': expected [<run>/]<session>:<turn>")
+        })
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(run) = self.run {
+            write!(f, "{run}/")?;
+        }
+        write!(f, "{}:{}", self.session, self.turn)
+    }
+}
+
+/// One tagged snapshot in the repository.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub id: SnapshotId,
+    /// Abbreviated commit hash.
+    pub commit: String,
+    /// Commit time, ISO 8601.
+    pub timestamp: String,
+}
+
+/// The snapshot repository of one workspace.
+#[derive(Debug, Clone)]
+pub struct SnapshotRepo {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+}
+
+impl SnapshotRepo {
+    /// Repository at `git_dir` tracking `work_tree`.
+    pub fn new(git_dir: &Path, work_tree: &Path) -> Self {
+        Self {
+            git_dir: git_dir.to_path_buf(),
+            work_tree: work_tree.to_path_buf(),
+        }
+    }
+
+    /// The repository for a workspace, in its log directory.
+    pub fn for_workspace(workspace: &Path) -> anyhow::Result<Self> {
+        let log_dir = SessionLogger::log_dir_for(workspace)?;
+        Ok(Self::new(&log_dir.join(SNAPSHOTS_DIR), workspace))
+    }
+
+    /// Whether the repository has been created.
+    pub fn exists(&self) -> bool {
+        self.git_dir.join("HEAD").is_file()
+    }
+
+    /// Create the repository if it does not exist yet.
+    pub async fn init(&self) -> anyhow::Result<()> {
+        if self.exists() {
+            return Ok(());
+        }
+        tokio::fs::create_dir_all(&self.git_dir).await?;
+        self.git(&["init", "--quiet"]).await?;
+        Ok(())
+    }
+
+    /// Commit the whole workspace and tag it with `id`, which must name its
+    /// run. A commit is made even when nothing changed, so every snapshot
+    /// turn can be named.
+    ///
+    /// Returns the abbreviated commit hash.
+    pub async fn snapshot(&self, id: SnapshotId) -> anyhow::Result<String> {
+        let tag = id
+            .tag()
+            .ok_or_else(|| anyhow::anyhow!("snapshot {id} does not name its run"))?;
+        let commit = self
+            .commit_all(&format!("Snapshot {id} (session {} turn {})", id.session, id.turn))
+            .await?;
+        self.git(&["tag", &tag]).await?;
+        Ok(commit)
+    }
+
+    /// All tagged snapshots, oldest first.
+    pub async fn list(&self) -> anyhow::Result<Vec<Snapshot>> {
+        self.require_exists()?;
+        let out = self
+            .git(&[
+                "for-each-ref",
+                "--sort=committerdate",
+                "--format=%(refname:short)%09%(objectname:short)%09%(committerdate:iso-strict)",
+                "refs/tags",
+            ])
+            .await?;
+        let mut snapshots: Vec<Snapshot> = out
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(Snapshot {
+                    id: SnapshotId::from_tag(fields.next()?)?,
+                    commit: fields.next()?.to_string(),
+                    timestamp: fields.next()?.to_string(),
+                })
+            })
+            .collect();
+        // Commits within the same second keep run/session/turn order.
+        snapshots.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then(a.id.run.cmp(&b.id.run))
+                .then(a.id.session.cmp(&b.id.session))
+                .then(a.id.turn.cmp(&b.id.turn))
+        });
+        Ok(snapshots)
+    }
+
+    /// Unified diff from snapshot `from` to snapshot `to`, or to the current
+    /// workspace when `to` is `None`.
+    pub async fn diff(&self, from: SnapshotId, to: Option<SnapshotId>) -> anyhow::Result<String> {
+        let from = self.resolve(from).await?;
+        match to {
+            Some(to) => {
+                let to = self.resolve(to).await?;
+                self.git(&["diff", &from, &to]).await
+            }
+            None => {
+                // Stage the workspace so new files show up in the diff.
+                self.git(&["add", "--all"]).await?;
+                self.git(&["diff", "--cached", &from]).await
+            }
+        }
+    }
+
+    /// Roll the workspace back to snapshot `id`: files are restored, and
+    /// files created since are removed. The current state is committed first.
+    ///
+    /// Returns the abbreviated hash of that pre-restore commit.
+    pub async fn restore(&self, id: SnapshotId) -> anyhow::Result<String> {
+        let target = self.resolve(id).await?;
+        let saved = self.commit_all(&format!("Before restoring {id}")).await?;
+        self.git(&["read-tree", "-u", "--reset", &target]).await?;
+        self.commit_all(&format!("Restore {id}")).await?;
+        Ok(saved)
+    }
+
+    /// Stage everything and commit; returns the abbreviated hash.
+    async fn commit_all(&self, message: &str) -> anyhow::Result<String> {
+        self.git(&["add", "--all"]).await?;
+        self.git(&["commit", "--quiet", "--allow-empty", "--no-verify", "-m", message])
+            .await?;
+        Ok(self
+            .git(&["rev-parse", "--short", "HEAD"])
+            .await?
+            .trim()
+            .to_string())
+    }
+
+    /// Commit hash of a snapshot, or an error naming the missing snapshot.
+    /// Without a run, the latest run with the session and turn is used.
+    async fn resolve(&self, id: SnapshotId) -> anyhow::Result<String> {
+        self.require_exists()?;
+        let tag = match id.tag() {
+            Some(tag) => tag,
+            None => self
+                .list()
+                .await?
+                .into_iter()
+                .filter(|s| s.id.session == id.session && s.id.turn == id.turn)
+                .max_by_key(|s| s.id.run)
+                .and_then(|s| s.id.tag())
+                .ok_or_else(|| anyhow::anyhow!("no snapshot {id}"))?,
+        };
+        self.git(&["rev-parse", "--verify", "--quiet", &format!("refs/tags/{tag}^{{commit}}")])
+            .await
+            .map(|hash| hash.trim().to_string())
+            .map_err(|_| anyhow::anyhow!("no snapshot {id}"))
+    }
+
+    fn require_exists(&self) -> anyhow::Result<()> {
+        if self.exists() {
+            Ok(())
+        } else {
+            anyhow::bail!(
+                "no snapshots at {} (enable them with [snapshots] enabled = true)",
+                self.git_dir.display()
+            )
+        }
+    }
+
+    /// Run git against this repository and return its stdout. The user's
+    /// identity, signing, and hook settings are overridden so commits never
+    /// prompt or fail on the host's git configuration.
+    async fn git(&self, args: &[&str]) -> anyhow::Result<String> {
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .arg("--work-tree")
+            .arg(&self.work_tree)
+            .args([
+                "-c",
+                "user.name=ouro",
+                "-c",
+                "user.email=ouro@localhost",
+                "-c",
+                "commit.gpgsign=false",
+                "-c",
+                "core.autocrlf=false",
+            ])
+            .args(args)
+            .current_dir(&self.work_tree)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to run git: {e}"))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.first().copied().unwrap_or_default(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Whether a turn that called these tools may have changed the workspace.
+pub fn changes_workspace<'a>(mut fn_names: impl Iterator<Item = &'a str>) -> bool {
+    fn_names.any(|name| matches!(name, "file_write" | "shell_exec" | "spawn_agent" | "job_start"))
+}
+
+/// Run `ouro snapshots <action>` against the configured workspace.
+pub async fn run_snapshots_command(config: &AppConfig, action: SnapshotAction) -> anyhow::Result<()> {
+    let repo = SnapshotRepo::for_workspace(&config.workspace)?;
+    match action {
+        SnapshotAction::List => {
+            let snapshots = repo.list().await?;
+            if snapshots.is_empty() {
+                println!("No snapshots yet.");
+            }
+            for snapshot in snapshots {
+                println!(
+                    "{:<20} {}  {}",
+                    snapshot.id.to_string(),
+                    snapshot.commit,
+                    snapshot.timestamp
+                );
+            }
+        }
+        SnapshotAction::Diff { from, to } => {
+            print!("{}", repo.diff(from, to).await?);
+        }
+        SnapshotAction::Restore { snapshot } => {
+            let saved = repo.restore(snapshot).await?;
+            println!(
+                "Restored {} to snapshot {snapshot}. The previous state was saved as commit {saved}.",
+                config.workspace.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn repo(tmp: &TempDir) -> SnapshotRepo {
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        SnapshotRepo::for_workspace(&workspace).unwrap()
+    }
+
+    fn id(session: u32, turn: u64) -> SnapshotId {
+        SnapshotId::current_run(session, turn)
+    }
+
+    fn in_run(run: u64, session: u32, turn: u64) -> SnapshotId {
+        SnapshotId {
+            run: Some(run),
+            session,
+            turn,
+        }
+    }
+
+    #[test]
+    fn snapshot_ids_parse_and_round_trip_through_tags() {
+        let parsed: SnapshotId = "1700000000/2:14".parse().unwrap();
+        assert_eq!(parsed, in_run(1700000000, 2, 14));
+        assert_eq!(parsed.tag().as_deref(), Some("r1700000000-s2-t14"));
+        assert_eq!(SnapshotId::from_tag("r1700000000-s2-t14"), Some(parsed));
+        assert_eq!(parsed.to_string(), "1700000000/2:14");
+
+        let latest: SnapshotId = "2:14".parse().unwrap();
+        assert_eq!(latest.run, None);
+        assert_eq!(latest.tag(), None);
+        assert_eq!(latest.to_string(), "2:14");
+
+        for bad in ["2", "2:x", ":3", "x/2:14", "r1-s2-t14"] {
+            assert!(bad.parse::<SnapshotId>().is_err(), "{bad}");
+        }
+        assert_eq!(SnapshotId::from_tag("v1.0"), None);
+        assert_eq!(SnapshotId::from_tag("s2-t14"), None);
+    }
+
+    #[test]
+    fn only_mutating_tools_trigger_snapshots() {
+        assert!(changes_workspace(["file_read", "shell_exec"].into_iter()));
+        assert!(!changes_workspace(["file_read", "web_fetch"].into_iter()));
+        assert!(changes_workspace(["job_status", "job_start"].into_iter()));
+        assert!(!changes_workspace(["job_status", "job_list"].into_iter()));
+    }
+
+    #[tokio::test]
+    async fn restore_rolls_back_changed_and_new_files() {
+        let tmp = TempDir::new().unwrap();
+        let repo = repo(&tmp);
+        let ws = tmp.path().join("workspace");
+        repo.init().await.unwrap();
+        assert!(tmp.path().join(".ouro-logs/snapshots.git/HEAD").exists());
+
+        std::fs::write(ws.join("SYSTEM_PROMPT.md"), "be careful").unwrap();
+        repo.snapshot(id(1, 1)).await.unwrap();
+        std::fs::write(ws.join("SYSTEM_PROMPT.md"), "").unwrap();
+        std::fs::write(ws.join("junk.txt"), "junk").unwrap();
+        repo.snapshot(id(1, 2)).await.unwrap();
+
+        let listed: Vec<SnapshotId> = repo.list().await.unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(listed, vec![id(1, 1), id(1, 2)]);
+
+        let diff = repo.diff(id(1, 1), Some(id(1, 2))).await.unwrap();
+        assert!(diff.contains("-be careful"));
+        assert!(diff.contains("+++ b/junk.txt"));
+
+        repo.restore(id(1, 1)).await.unwrap();
+        assert_eq!(std::fs::read_to_string(ws.join("SYSTEM_PROMPT.md")).unwrap(), "be careful");
+        assert!(!ws.join("junk.txt").exists());
+        assert_eq!(repo.diff(id(1, 1), None).await.unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn runs_keep_their_own_snapshots() {
+        let tmp = TempDir::new().unwrap();
+        let repo = repo(&tmp);
+        let ws = tmp.path().join("workspace");
+        repo.init().await.unwrap();
+
+        std::fs::write(ws.join("notes.md"), "first run").unwrap();
+        repo.snapshot(in_run(100, 1, 1)).await.unwrap();
+        std::fs::write(ws.join("notes.md"), "second run").unwrap();
+        repo.snapshot(in_run(200, 1, 1)).await.unwrap();
+        assert!(repo.snapshot(in_run(200, 1, 1)).await.is_err(), "tags are never moved");
+
+        let listed: Vec<SnapshotId> = repo.list().await.unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(listed, vec![in_run(100, 1, 1), in_run(200, 1, 1)]);
+
+        repo.restore(in_run(100, 1, 1)).await.unwrap();
+        assert_eq!(std::fs::read_to_string(ws.join("notes.md")).unwrap(), "first run");
+        repo.restore("1:1".parse().unwrap()).await.unwrap();
+        assert_eq!(std::fs::read_to_string(ws.join("notes.md")).unwrap(), "second run");
+    }
+
+    #[tokio::test]
+    async fn missing_repo_and_snapshots_are_reported() {
+        let tmp = TempDir::new().unwrap();
+        let repo = repo(&tmp);
+        let err = repo.list().await.unwrap_err().to_string();
+        assert!(err.contains("no snapshots at"), "{err}");
+
+        repo.init().await.unwrap();
+        let err = repo.restore("3:4".parse().unwrap()).await.unwrap_err().to_string();
+        assert_eq!(err, "no snapshot 3:4");
+    }
+}
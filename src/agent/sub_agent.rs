@@ -0,0 +1,452 @@
+//! Sub-agent sessions spawned by the `spawn_agent` tool.
+//!
+//! A sub-agent is a child agent session with its own task prompt, a workspace
+//! scoped to a sub-directory of the parent's, a turn budget, and its own log
+//! file under `.ouro-logs/{id}/`. The parent's tool call blocks until the
+//! child finishes, and the child's final answer becomes the tool result.
+//!
+//! Child events are forwarded to the parent's event channel wrapped in
+//! [`AgentEvent::SubAgent`], so the TUI sub-agent panel can track each child
+//! by id. Sub-agents may spawn their own children up to [`MAX_DEPTH`].
+
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use genai::chat::ToolCall;
+use serde_json::json;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::agent::agent_loop::{run_sub_agent_session, SessionResult, ShutdownReason};
+use crate::agent::logging::SessionLogger;
+use crate::config::AppConfig;
+use crate::safety::SafetyLayer;
+use crate::tui::event::AgentEvent;
+
+/// Maximum nesting depth. The main agent's children are depth 1.
+pub const MAX_DEPTH: u32 = 3;
+
+/// Turn budget used when the caller does not pass `max_turns`.
+pub const DEFAULT_MAX_TURNS: u64 = 20;
+
+/// Upper bound on a requested turn budget.
+pub const MAX_TURNS_LIMIT: u64 = 200;
+
+/// Source of process-unique sub-agent ids ("sub-1", "sub-2", ...).
+static NEXT_AGENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Identity and limits of a sub-agent session.
+#[derive(Debug, Clone)]
+pub struct SubAgentTask {
+    /// Harness-assigned id, also the name of its log directory.
+    pub id: String,
+    /// Id of the spawning sub-agent, `None` if spawned by the main agent.
+    pub parent_id: Option<String>,
+    /// Task prompt, sent as the first user message.
+    pub task: String,
+    /// Turns the sub-agent may take before it is stopped.
+    pub max_turns: u64,
+    /// Nesting depth (1 for children of the main agent).
+    pub depth: u32,
+    /// Directory the sub-agent's session log is written to.
+    pub log_dir: PathBuf,
+    /// Top-level `.ouro-logs` directory shared by the whole agent tree.
+    pub log_root: PathBuf,
+}
+
+/// Session state a parent passes down when dispatching `spawn_agent`.
+pub struct SpawnContext<'a> {
+    /// The parent's configuration; the child inherits it with a scoped workspace.
+    pub config: &'a AppConfig,
+    /// The parent's safety layer; the child's shares its protected files.
+    pub safety: &'a SafetyLayer,
+    /// The parent's own sub-agent identity, `None` for the main agent.
+    pub parent: Option<&'a SubAgentTask>,
+    /// Shared shutdown flag; the child stops between turns when it is set.
+    pub shutdown: Arc<AtomicBool>,
+    /// The parent's TUI event channel, if any.
+    pub event_tx: Option<UnboundedSender<AgentEvent>>,
+    /// The parent's pause flag; pausing the parent also pauses its children.
+    pub pause_flag: Option<Arc<AtomicBool>>,
+}
+
+/// Validated arguments of a `spawn_agent` call.
+#[derive(Debug, PartialEq)]
+pub struct SpawnAgentArgs {
+    pub task: String,
+    pub directory: String,
+    pub max_turns: u64,
+}
+
+impl SpawnAgentArgs {
+    /// Parse and validate tool-call arguments.
+    pub fn from_arguments(args: &serde_json::Value) -> Result<Self, String> {
+        let task = args
+            .get("task")
+            .and_then(|v| v.as_str())
+            .filter(|t| !t.trim().is_empty())
+            .ok_or("spawn_agent: missing or invalid 'task' argument")?;
+        let directory = args
+            .get("directory")
+            .and_then(|v| v.as_str())
+            .ok_or("spawn_agent: missing or invalid 'directory' argument")?;
+        let max_turns = match args.get("max_turns") {
+            None | Some(serde_json::Value::Null) => DEFAULT_MAX_TURNS,
+            Some(v) => v
+                .as_u64()
+                .filter(|n| *n > 0)
+                .ok_or("spawn_agent: 'max_turns' must be a positive integer")?
+                .min(MAX_TURNS_LIMIT),
+        };
+        Ok(Self {
+            task: task.to_string(),
+            directory: directory.to_string(),
+            max_turns,
+        })
+    }
+}
+
+/// Resolve `directory` to a path inside `workspace`.
+///
+/// Only plain relative paths are accepted: no absolute paths and no `..`
+/// components, so a child can never be scoped outside its parent.
+fn scoped_workspace(workspace: &Path, directory: &str) -> Result<PathBuf, String> {
+    let rel = Path::new(directory);
+    let has_normal = rel.components().any(|c| matches!(c, Component::Normal(_)));
+    let all_plain = rel
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !has_normal || !all_plain {
+        return Err(format!(
+            "spawn_agent: 'directory' must be a relative sub-directory of the workspace, got '{directory}'"
+        ));
+    }
+    Ok(workspace.join(rel))
+}
+
+/// Wrap a child's event for the parent's channel.
+///
+/// Events that are already tagged (from grandchildren) or are sub-agent
+/// lifecycle events pass through unchanged, since ids are unique.
+/// Discoveries also pass through so they show up in the Discoveries tab.
+fn tag_event(agent_id: &str, event: AgentEvent) -> AgentEvent {
+    match event {
+        AgentEvent::SubAgent { .. }
+        | AgentEvent::SubAgentSpawned { .. }
+        | AgentEvent::SubAgentFinished { .. }
+        | AgentEvent::Discovery { .. } => event,
+        other => AgentEvent::SubAgent {
+            agent_id: agent_id.to_string(),
+            event: Box::new(other),
+        },
+    }
+}
+
+/// Execute a `spawn_agent` tool call: run a sub-agent to completion and
+/// return its outcome as a JSON string.
+///
+/// Like [`crate::agent::tools::dispatch_tool_call`], this never fails --
+/// errors are returned as `{"error": "..."}` for the model to react to.
+pub async fn dispatch_spawn_agent(call: &ToolCall, ctx: &SpawnContext<'_>) -> String {
+    let args = match SpawnAgentArgs::from_arguments(&call.fn_arguments) {
+        Ok(args) => args,
+        Err(e) => return json!({ "error": e }).to_string(),
+    };
+
+    let depth = ctx.parent.map_or(0, |p| p.depth) + 1;
+    if depth > MAX_DEPTH {
+        return json!({
+            "error": format!("spawn_agent: maximum sub-agent nesting depth ({MAX_DEPTH}) reached")
+        })
+        .to_string();
+    }
+
+    let workspace = match scoped_workspace(&ctx.config.workspace, &args.directory) {
+        Ok(ws) => ws,
+        Err(e) => return json!({ "error": e }).to_string(),
+    };
+
+    let id = format!("sub-{}", NEXT_AGENT_ID.fetch_add(1, Ordering::SeqCst));
+
+    // Nested sub-agents log inside their parent's log directory.
+    let log_root = match ctx.parent {
+        Some(parent) => parent.log_root.clone(),
+        None => match SessionLogger::log_dir_for(&ctx.config.workspace) {
+            Ok(dir) => dir,
+            Err(e) => return json!({ "error": format!("spawn_agent: {e}") }).to_string(),
+        },
+    };
+    let parent_log_dir = ctx.parent.map_or_else(|| log_root.clone(), |p| p.log_dir.clone());
+
+    let mut child_config = ctx.config.clone();
+    child_config.workspace = workspace;
+
+    // The child gets its own safety layer rooted at the scoped workspace
+    // (this also creates the directory), guarding the parent's protected
+    // files.
+    let child_safety = match ctx.safety.for_subdirectory(&child_config) {
+        Ok(s) => s,
+        Err(e) => {
+            return json!({ "error": format!("spawn_agent: failed to set up workspace: {e}") })
+                .to_string();
+        }
+    };
+
+    let task = SubAgentTask {
+        log_dir: parent_log_dir.join(&id),
+        log_root,
+        id,
+        parent_id: ctx.parent.map(|p| p.id.clone()),
+        task: args.task,
+        max_turns: args.max_turns,
+        depth,
+    };
+
+    if let Some(tx) = &ctx.event_tx {
+        let _ = tx.send(AgentEvent::SubAgentSpawned {
+            agent_id: task.id.clone(),
+            parent_id: task.parent_id.clone(),
+            task: task.task.clone(),
+        });
+    }
+
+    // The child always gets an event channel, which also keeps it from
+    // writing to the terminal in headless mode. A forwarder tags its events
+    // for the parent and remembers its last text reply.
+    let (child_tx, mut child_rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
+    let parent_tx = ctx.event_tx.clone();
+    let agent_id = task.id.clone();
+    let forwarder = tokio::spawn(async move {
+        let mut last_text: Option<String> = None;
+        while let Some(event) = child_rx.recv().await {
+            if let AgentEvent::ThoughtText { content, .. } = &event {
+                last_text = Some(content.clone());
+            }
+            if let Some(tx) = &parent_tx {
+                let _ = tx.send(tag_event(&agent_id, event));
+            }
+        }
+        last_text
+    });
+
+    let result = run_child(
+        &child_config,
+        &child_safety,
+        &task,
+        ctx.shutdown.clone(),
+        child_tx,
+        ctx.pause_flag.clone(),
+    )
+    .await;
+    let last_text = forwarder.await.ok().flatten();
+
+    let (status, turns, final_answer, reason) = match result {
+        Ok(SessionResult {
+            shutdown_reason,
+            turns_completed,
+            ..
+        }) => match shutdown_reason {
+            ShutdownReason::Completed { final_answer } => {
+                ("completed", turns_completed, Some(final_answer), None)
+            }
+            ShutdownReason::MaxTurnsOrError(msg) => ("stopped", turns_completed, last_text, Some(msg)),
+            ShutdownReason::ContextFull { .. } => (
+                "context_full",
+                turns_completed,
+                last_text,
+                Some("context window exhausted".to_string()),
+            ),
+            ShutdownReason::UserShutdown => ("shutdown", turns_completed, last_text, None),
+        },
+        Err(e) => ("error", 0, last_text, Some(e.to_string())),
+    };
+
+    if let Some(tx) = &ctx.event_tx {
+        let _ = tx.send(AgentEvent::SubAgentFinished {
+            agent_id: task.id.clone(),
+            status: status.to_string(),
+            turns,
+        });
+    }
+
+    let mut response = json!({
+        "agent_id": task.id,
+        "status": status,
+        "turns": turns,
+        "final_answer": final_answer,
+        "log_dir": task.log_dir.display().to_string(),
+    });
+    if let Some(reason) = reason {
+        response["reason"] = json!(reason);
+    }
+    response.to_string()
+}
+
+/// Run the child session behind a boxed future.
+///
+/// The agent loop dispatches `spawn_agent`, which runs another agent loop, so
+/// the futures are recursive; boxing with an explicit `Send` bound breaks the
+/// cycle for the compiler.
+fn run_child<'a>(
+    config: &'a AppConfig,
+    safety: &'a SafetyLayer,
+    task: &'a SubAgentTask,
+    shutdown: Arc<AtomicBool>,
+    event_tx: UnboundedSender<AgentEvent>,
+    pause_flag: Option<Arc<AtomicBool>>,
+) -> Pin<Box<dyn Future<Output = anyhow::Result<SessionResult>> + Send + 'a>> {
+    Box::pin(run_sub_agent_session(
+        config,
+        safety,
+        task,
+        shutdown,
+        Some(event_tx),
+        pause_flag,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::event::AgentState;
+
+    #[test]
+    fn args_apply_defaults_and_limits() {
+        let args = SpawnAgentArgs::from_arguments(&json!({
+            "task": "count lines",
+            "directory": "scratch"
+        }))
+        .unwrap();
+        assert_eq!(args.max_turns, DEFAULT_MAX_TURNS);
+
+        let args = SpawnAgentArgs::from_arguments(&json!({
+            "task": "count lines",
+            "directory": "scratch",
+            "max_turns": 10_000
+        }))
+        .unwrap();
+        assert_eq!(args.max_turns, MAX_TURNS_LIMIT);
+    }
+
+    #[test]
+    fn args_reject_missing_or_invalid_fields() {
+        assert!(SpawnAgentArgs::from_arguments(&json!({"directory": "d"})).is_err());
+        assert!(SpawnAgentArgs::from_arguments(&json!({"task": "  ", "directory": "d"})).is_err());
+        assert!(SpawnAgentArgs::from_arguments(&json!({"task": "t"})).is_err());
+        assert!(
+            SpawnAgentArgs::from_arguments(&json!({"task": "t", "directory": "d", "max_turns": 0}))
+                .is_err()
+        );
+        assert!(
+            SpawnAgentArgs::from_arguments(&json!({"task": "t", "directory": "d", "max_turns": "5"}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn scoped_workspace_stays_inside_parent() {
+        let ws = Path::new("/ws");
+        assert_eq!(scoped_workspace(ws, "sub").unwrap(), Path::new("/ws/sub"));
+        assert_eq!(scoped_workspace(ws, "./a/b").unwrap(), Path::new("/ws/./a/b"));
+
+        assert!(scoped_workspace(ws, "").is_err());
+        assert!(scoped_workspace(ws, ".").is_err());
+        assert!(scoped_workspace(ws, "../escape").is_err());
+        assert!(scoped_workspace(ws, "a/../../escape").is_err());
+        assert!(scoped_workspace(ws, "/etc").is_err());
+    }
+
+    #[test]
+    fn tag_event_wraps_only_untagged_events() {
+        let wrapped = tag_event("sub-1", AgentEvent::StateChanged(AgentState::Thinking));
+        assert!(matches!(
+            wrapped,
+            AgentEvent::SubAgent { ref agent_id, .. } if agent_id == "sub-1"
+        ));
+
+        // A grandchild's event keeps its own tag.
+        let nested = AgentEvent::SubAgent {
+            agent_id: "sub-2".into(),
+            event: Box::new(AgentEvent::StateChanged(AgentState::Idle)),
+        };
+        assert!(matches!(
+            tag_event("sub-1", nested),
+            AgentEvent::SubAgent { ref agent_id, .. } if agent_id == "sub-2"
+        ));
+
+        let spawned = AgentEvent::SubAgentSpawned {
+            agent_id: "sub-3".into(),
+            parent_id: Some("sub-1".into()),
+            task: "x".into(),
+        };
+        assert!(matches!(tag_event("sub-1", spawned), AgentEvent::SubAgentSpawned { .. }));
+    }
+
+    fn test_config(tmp: &tempfile::TempDir) -> AppConfig {
+        AppConfig {
+            security_log_path: tmp.path().join("security.log"),
+            ..crate::config::test_config(&tmp.path().join("workspace"))
+        }
+    }
+
+    #[tokio::test]
+    async fn spawn_rejects_invalid_directory_before_running() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config = test_config(&tmp);
+        let safety = SafetyLayer::new(&config).unwrap();
+        let ctx = SpawnContext {
+            config: &config,
+            safety: &safety,
+            parent: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            event_tx: None,
+            pause_flag: None,
+        };
+        let call = ToolCall {
+            call_id: "c1".into(),
+            fn_name: "spawn_agent".into(),
+            fn_arguments: json!({"task": "t", "directory": "../outside"}),
+            thought_signatures: None,
+        };
+
+        let result = dispatch_spawn_agent(&call, &ctx).await;
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(parsed["error"].as_str().unwrap().contains("relative sub-directory"));
+        assert!(!tmp.path().join("outside").exists());
+    }
+
+    #[tokio::test]
+    async fn spawn_rejects_beyond_max_depth() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config = test_config(&tmp);
+        let parent = SubAgentTask {
+            id: "sub-9".into(),
+            parent_id: None,
+            task: "t".into(),
+            max_turns: 5,
+            depth: MAX_DEPTH,
+            log_dir: tmp.path().join("logs/sub-9"),
+            log_root: tmp.path().join("logs"),
+        };
+        let safety = SafetyLayer::new(&config).unwrap();
+        let ctx = SpawnContext {
+            config: &config,
+            safety: &safety,
+            parent: Some(&parent),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            event_tx: None,
+            pause_flag: None,
+        };
+        let call = ToolCall {
+            call_id: "c1".into(),
+            fn_name: "spawn_agent".into(),
+            fn_arguments: json!({"task": "t", "directory": "deeper"}),
+            thought_signatures: None,
+        };
+
+        let result = dispatch_spawn_agent(&call, &ctx).await;
+        assert!(result.contains("nesting depth"));
+    }
+}
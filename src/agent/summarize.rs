@@ -0,0 +1,283 @@
+//! LLM-written summaries as a context compression strategy.
+//!
+//! Masking (see [`crate::agent::context_manager`]) keeps every message but
+//! throws away the content of old tool outputs. Summarization instead asks a
+//! model to condense the oldest turns -- assistant text, tool calls, and their
+//! results -- into one system message that keeps the facts the agent will
+//! need later: file paths, findings, decisions, and unfinished work.
+//!
+//! Turns are only ever summarized whole, so a tool call and its response are
+//! either both replaced or both kept.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use genai::chat::{ChatMessage, ChatRequest, ChatRole};
+use genai::Client;
+
+/// Turns always left verbatim at the end of the conversation.
+const MIN_KEPT_TURNS: usize = 2;
+
+/// Maximum characters of a single tool result included in the transcript.
+const MAX_RESULT_CHARS: usize = 4000;
+
+/// Header of the system message that replaces the summarized turns.
+const SUMMARY_HEADER: &str = "[Summary of earlier turns";
+
+const SUMMARY_INSTRUCTIONS: &str = "\
+You compress an autonomous agent's working history so it can keep going with \
+less context. Summarize the transcript you are given in at most 300 words. \
+Keep: the goal being pursued, file paths and commands that matter, key \
+findings and numbers, decisions and why they were made, errors hit, and work \
+still unfinished. Drop: raw file contents, long command output, and chatter. \
+Write plain notes in the past tense, from the agent's point of view.";
+
+/// Outcome of a summarization round.
+#[derive(Debug, PartialEq)]
+pub struct SummaryOutcome {
+    /// Assistant turns folded into the summary.
+    pub turns_summarized: usize,
+    /// Messages replaced by the single summary message.
+    pub messages_replaced: usize,
+    /// Length of the summary text.
+    pub summary_chars: usize,
+    /// The summary text itself.
+    pub summary: String,
+}
+
+/// Writes summaries with a given model through the session's client.
+pub struct Summarizer<'a> {
+    pub client: &'a Client,
+    pub model: &'a str,
+}
+
+impl Summarizer<'_> {
+    /// Replace the oldest `turns` turns of `messages` with a model-written
+    /// summary.
+    ///
+    /// Returns `Ok(None)` if the conversation is too short to summarize, and
+    /// leaves `messages` untouched if the model request fails.
+    pub async fn summarize_oldest_turns(
+        &self,
+        messages: &mut Vec<ChatMessage>,
+        turns: usize,
+    ) -> anyhow::Result<Option<SummaryOutcome>> {
+        let Some(range) = select_oldest_turns(messages, turns) else {
+            return Ok(None);
+        };
+        let transcript = render_transcript(&messages[range.clone()]);
+
+        let response = self
+            .client
+            .exec_chat(self.model, summary_request(&transcript), None)
+            .await?;
+        let summary = response
+            .first_text()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("summary model returned no text"))?
+            .to_string();
+
+        let turns_summarized = count_turns(&messages[range.clone()]);
+        let messages_replaced = range.len();
+        let summary_chars = summary.len();
+        messages.splice(range, [summary_message(turns_summarized, &summary)]);
+
+        Ok(Some(SummaryOutcome {
+            turns_summarized,
+            messages_replaced,
+            summary_chars,
+            summary,
+        }))
+    }
+}
+
+/// Find the message range covering the oldest `turns` assistant turns.
+///
+/// A turn is an assistant message plus the tool responses that follow it;
+/// any user or system messages before it belong to the same turn. A leading
+/// user message (a sub-agent's task) is never summarized, and at least
+/// [`MIN_KEPT_TURNS`] turns stay verbatim. Returns `None` if nothing can be
+/// summarized.
+pub fn select_oldest_turns(messages: &[ChatMessage], turns: usize) -> Option<Range<usize>> {
+    let start = usize::from(messages.first().is_some_and(|m| m.role == ChatRole::User));
+    let available = count_turns(&messages[start..]).saturating_sub(MIN_KEPT_TURNS);
+    let turns = turns.min(available);
+    if turns == 0 {
+        return None;
+    }
+
+    let mut seen = 0;
+    let mut end = start;
+    while end < messages.len() {
+        if messages[end].role == ChatRole::Assistant {
+            seen += 1;
+        }
+        end += 1;
+        if seen == turns {
+            break;
+        }
+    }
+    while end < messages.len() && messages[end].role == ChatRole::Tool {
+        end += 1;
+    }
+    Some(start..end)
+}
+
+/// Number of assistant messages (turns) in `messages`.
+fn count_turns(messages: &[ChatMessage]) -> usize {
+    messages
+        .iter()
+        .filter(|m| m.role == ChatRole::Assistant)
+        .count()
+}
+
+/// Render messages as a plain-text transcript for the summary model.
+///
+/// Long tool results are cut to [`MAX_RESULT_CHARS`].
+pub fn render_transcript(messages: &[ChatMessage]) -> String {
+    let mut fn_names: HashMap<String, String> = HashMap::new();
+    let mut lines = Vec::new();
+
+    for msg in messages {
+        match msg.role {
+            ChatRole::System => {
+                if let Some(text) = msg.content.first_text() {
+                    lines.push(format!("[system] {text}"));
+                }
+            }
+            ChatRole::User => {
+                if let Some(text) = msg.content.first_text() {
+                    lines.push(format!("[user] {text}"));
+                }
+            }
+            ChatRole::Assistant => {
+                if let Some(text) = msg.content.first_text() {
+                    lines.push(format!("[assistant] {text}"));
+                }
+                for call in msg.content.tool_calls() {
+                    fn_names.insert(call.call_id.clone(), call.fn_name.clone());
+                    lines.push(format!("[tool call] {}({})", call.fn_name, call.fn_arguments));
+                }
+            }
+            ChatRole::Tool => {
+                for response in msg.content.tool_responses() {
+                    let fn_name = fn_names
+                        .get(&response.call_id)
+                        .map_or("tool", String::as_str);
+                    lines.push(format!(
+                        "[{fn_name} result] {}",
+                        truncate_chars(&response.content, MAX_RESULT_CHARS)
+                    ));
+                }
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Cut `text` to at most `max` bytes on a char boundary, marking the cut.
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... [{} more bytes]", &text[..end], text.len() - end)
+}
+
+/// Build the chat request asking a model to summarize `transcript`.
+pub fn summary_request(transcript: &str) -> ChatRequest {
+    ChatRequest::from_system(SUMMARY_INSTRUCTIONS).append_message(ChatMessage::user(transcript))
+}
+
+/// The system message that stands in for the summarized turns.
+pub fn summary_message(turns: usize, summary: &str) -> ChatMessage {
+    ChatMessage::system(format!(
+        "{SUMMARY_HEADER} ({turns} turns), written by the harness to save context]\n{summary}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genai::chat::{ToolCall, ToolResponse};
+
+    fn call(id: &str, fn_name: &str) -> ChatMessage {
+        ChatMessage::from(vec![ToolCall {
+            call_id: id.to_string(),
+            fn_name: fn_name.to_string(),
+            fn_arguments: serde_json::json!({"path": "notes.md"}),
+            thought_signatures: None,
+        }])
+    }
+
+    fn result(id: &str, content: &str) -> ChatMessage {
+        ToolResponse::new(id, content).into()
+    }
+
+    /// Four turns: two tool turns, then two text turns.
+    fn conversation() -> Vec<ChatMessage> {
+        vec![
+            call("c1", "file_read"),
+            result("c1", "line one\nline two"),
+            ChatMessage::system("[Context compressed: ...]"),
+            call("c2", "shell_exec"),
+            result("c2", "{\"exit_code\":0}"),
+            ChatMessage::assistant("Thinking about it."),
+            ChatMessage::assistant("Done."),
+        ]
+    }
+
+    #[test]
+    fn selects_whole_turns_with_their_tool_results() {
+        let messages = conversation();
+        assert_eq!(select_oldest_turns(&messages, 1), Some(0..2));
+        assert_eq!(select_oldest_turns(&messages, 2), Some(0..5));
+    }
+
+    #[test]
+    fn keeps_recent_turns_and_sub_agent_task() {
+        let messages = conversation();
+        // Only 4 turns exist and 2 must stay verbatim.
+        assert_eq!(select_oldest_turns(&messages, 10), Some(0..5));
+
+        let mut with_task = vec![ChatMessage::user("Research X")];
+        with_task.extend(conversation());
+        assert_eq!(select_oldest_turns(&with_task, 1), Some(1..3));
+
+        let short = vec![ChatMessage::assistant("a"), ChatMessage::assistant("b")];
+        assert_eq!(select_oldest_turns(&short, 5), None);
+    }
+
+    #[test]
+    fn transcript_names_tool_results() {
+        let messages = conversation();
+        let transcript = render_transcript(&messages[0..5]);
+        assert!(transcript.contains("[tool call] file_read({\"path\":\"notes.md\"})"));
+        assert!(transcript.contains("[file_read result] line one\nline two"));
+        assert!(transcript.contains("[system] [Context compressed: ...]"));
+        assert!(transcript.contains("[shell_exec result]"));
+    }
+
+    #[test]
+    fn transcript_truncates_long_results() {
+        let long = "é".repeat(MAX_RESULT_CHARS);
+        let transcript = render_transcript(&[call("c1", "file_read"), result("c1", &long)]);
+        assert!(transcript.len() < MAX_RESULT_CHARS + 200);
+        assert!(transcript.contains("more bytes]"));
+    }
+
+    #[test]
+    fn summary_message_is_a_system_message() {
+        let msg = summary_message(3, "Read notes.md; found the bug.");
+        assert_eq!(msg.role, ChatRole::System);
+        let text = msg.content.first_text().unwrap();
+        assert!(text.starts_with(SUMMARY_HEADER));
+        assert!(text.contains("(3 turns)"));
+        assert!(text.ends_with("found the bug."));
+    }
+}
@@ -20,8 +20,10 @@ use crate::error::AgentError;
 /// The resulting prompt has this structure:
 /// 1. Harness preamble (role, environment, tools, constraints)
 /// 2. Session continuity section (if session_number > 1)
-/// 3. Separator
-/// 4. User's system prompt content from `SYSTEM_PROMPT.md`
+/// 3. Digest of the previous session's activity (if one was gathered)
+/// 4. Handoff from the previous session (if one was written)
+/// 5. Separator
+/// 6. User's system prompt content from `SYSTEM_PROMPT.md`
 ///
 /// The prompt is always re-read from disk (never cached) so that agent
 /// modifications to `SYSTEM_PROMPT.md` are picked up on restart.
@@ -32,6 +34,10 @@ use crate::error::AgentError;
 /// * `model` - Model identifier (e.g., "qwen2.5:7b") shown to the agent
 /// * `tool_descriptions` - Pre-formatted human-readable tool listing
 /// * `session_number` - 1-based session number; values > 1 add a continuity section
+/// * `digest` - Activity digest of the previous session, if any (see
+///   [`crate::agent::digest`])
+/// * `handoff` - Handoff document left by the previous session, if any (see
+///   [`crate::agent::handoff`])
 ///
 /// # Errors
 ///
@@ -42,6 +48,8 @@ pub async fn build_system_prompt(
     model: &str,
     tool_descriptions: &str,
     session_number: u32,
+    digest: Option<&str>,
+    handoff: Option<&str>,
 ) -> Result<String, AgentError> {
     let prompt_path = workspace.join("SYSTEM_PROMPT.md");
 
@@ -65,6 +73,21 @@ pub async fn build_system_prompt(
         String::new()
     };
 
+    let digest_section = match digest {
+        Some(digest) => format!("\n\n## What Happened Last Session\n{}", digest.trim()),
+        None => String::new(),
+    };
+
+    let handoff_section = match handoff {
+        Some(handoff) => format!(
+            "\n\n## Handoff From Your Previous Session\n\
+             Before the restart, you wrote this handoff (saved as HANDOFF.md in your workspace):\n\n\
+             {}",
+            handoff.trim()
+        ),
+        None => String::new(),
+    };
+
     Ok(format!(
         "\
 You are an autonomous AI agent running in the Ouroboros research harness.
@@ -82,7 +105,9 @@ You are an autonomous AI agent running in the Ouroboros research harness.
 - Shell commands are filtered against a security blocklist
 - Shell commands have a configurable timeout
 - Read access is unrestricted\
-{session_continuity}
+{session_continuity}\
+{digest_section}\
+{handoff_section}
 
 ## Your System Prompt
 The following is your system prompt, provided by your operator:
@@ -93,6 +118,48 @@ The following is your system prompt, provided by your operator:
     ))
 }
 
+/// Build the system prompt for a sub-agent spawned via `spawn_agent`.
+///
+/// Sub-agents do not load `SYSTEM_PROMPT.md` -- their instructions come
+/// entirely from the task the parent gave them, which is sent as the first
+/// user message. The prompt explains the sub-agent role, the scoped
+/// workspace, the turn budget, and how to return a final answer.
+pub fn build_sub_agent_prompt(
+    workspace: &Path,
+    model: &str,
+    tool_descriptions: &str,
+    max_turns: u64,
+) -> String {
+    let workspace_display = workspace.display();
+
+    format!(
+        "\
+You are a sub-agent running in the Ouroboros research harness. Another agent \
+spawned you to carry out a single task, which is given in the next message.
+
+## Environment
+- Model: {model}
+- Workspace: {workspace_display} (a sub-directory of your parent's workspace)
+- Shell commands execute in the workspace directory
+- Turn budget: {max_turns} turns
+
+## Available Tools
+{tool_descriptions}
+
+## Constraints
+- File writes are restricted to the workspace directory
+- Shell commands are filtered against a security blocklist
+- Shell commands have a configurable timeout
+- Read access is unrestricted
+
+## Finishing
+When the task is done, reply with your final answer as plain text without \
+calling any tools. That reply ends your session and is returned to your parent \
+as the result of its `spawn_agent` call, so make it complete and self-contained. \
+If you run out of turns, your last text reply is returned instead."
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,7 +177,7 @@ mod tests {
             .unwrap();
 
         let tool_desc = "- shell_exec: Execute a shell command\n- file_read: Read a file";
-        let result = build_system_prompt(&workspace, "qwen2.5:7b", tool_desc, 1)
+        let result = build_system_prompt(&workspace, "qwen2.5:7b", tool_desc, 1, None, None)
             .await
             .unwrap();
 
@@ -145,7 +212,7 @@ mod tests {
         let workspace = tmp.path().join("workspace");
         tokio::fs::create_dir_all(&workspace).await.unwrap();
 
-        let result = build_system_prompt(&workspace, "test-model", "tools", 1).await;
+        let result = build_system_prompt(&workspace, "test-model", "tools", 1, None, None).await;
 
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -167,7 +234,7 @@ mod tests {
             .await
             .unwrap();
 
-        let result = build_system_prompt(&workspace, "test-model", "tools", 3)
+        let result = build_system_prompt(&workspace, "test-model", "tools", 3, None, None)
             .await
             .unwrap();
 
@@ -180,5 +247,44 @@ mod tests {
 
         // User content still present
         assert!(result.contains("My prompt."));
+        assert!(!result.contains("Handoff From Your Previous Session"));
+    }
+
+    #[tokio::test]
+    async fn build_system_prompt_includes_digest_and_handoff() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+        tokio::fs::create_dir_all(&workspace).await.unwrap();
+        tokio::fs::write(workspace.join("SYSTEM_PROMPT.md"), "My prompt.")
+            .await
+            .unwrap();
+
+        let handoff = "# Session Handoff\n\n## Current Goal\nFix the parser\n";
+        let digest = "Gathered by the harness from session #1's log (4 turns).";
+        let result =
+            build_system_prompt(&workspace, "test-model", "tools", 2, Some(digest), Some(handoff))
+            .await
+            .unwrap();
+
+        let digest_section = result.find("## What Happened Last Session").unwrap();
+        let section = result.find("## Handoff From Your Previous Session").unwrap();
+        assert!(digest_section > result.find("Session Continuity").unwrap());
+        assert!(section > digest_section);
+        assert!(section < result.find("## Your System Prompt").unwrap());
+        assert!(result.contains("## Current Goal\nFix the parser"));
+    }
+
+    #[test]
+    fn build_sub_agent_prompt_includes_budget_and_finishing_rules() {
+        let workspace = Path::new("/tmp/ws/sub");
+        let result = build_sub_agent_prompt(workspace, "qwen2.5:7b", "- shell_exec", 15);
+
+        assert!(result.contains("sub-agent"));
+        assert!(result.contains("/tmp/ws/sub"));
+        assert!(result.contains("Turn budget: 15 turns"));
+        assert!(result.contains("- shell_exec"));
+        assert!(result.contains("without calling any tools"));
+        // Sub-agents never see the operator's SYSTEM_PROMPT.md section.
+        assert!(!result.contains("Your System Prompt"));
     }
 }
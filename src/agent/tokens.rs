@@ -0,0 +1,343 @@
+//! Token estimation for when the provider does not report usage.
+//!
+//! The context manager prefers the `prompt_tokens` the provider reports with
+//! each response. Some servers omit usage from streamed responses, and after a
+//! masking or summarization round the last reported count is stale. A
+//! [`TokenEstimator`] fills those gaps:
+//!
+//! - [`ProviderTokenizer`] asks the server to tokenize the text with the
+//!   model's own tokenizer (Ollama `api/tokenize`, llama.cpp and vLLM
+//!   `/tokenize`).
+//! - [`LocalEstimator`] splits text the way BPE pre-tokenizers do and prices
+//!   each piece by length. No vocabulary is needed, and it lands much closer
+//!   than a flat chars/4 on code and JSON.
+//!
+//! [`FallbackEstimator`] chains the two: provider first, local when the
+//! endpoint is missing or fails.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use genai::chat::{ChatRequest, ChatRole};
+use regex::Regex;
+
+use crate::config::{AppConfig, ProviderKind};
+
+/// A token count and the estimator that produced it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenCount {
+    pub tokens: usize,
+    /// Estimator name recorded in the session log ("provider" or "local").
+    pub estimator: &'static str,
+}
+
+/// Counts (or estimates) the tokens a piece of text costs the model.
+pub trait TokenEstimator {
+    /// Count the tokens in `text`, or `None` if this estimator cannot.
+    fn count_tokens(&self, text: &str) -> impl Future<Output = Option<TokenCount>> + Send;
+}
+
+// ---------------------------------------------------------------------------
+// Local estimator
+// ---------------------------------------------------------------------------
+
+/// GPT-2 style pre-tokenization: contractions, words and numbers with an
+/// optional leading space, punctuation runs, and whitespace runs.
+static PRETOKENIZE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"'(?:s|t|re|ve|m|ll|d)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+")
+        .expect("pre-tokenizer pattern is valid")
+});
+
+/// Vocabulary-free BPE approximation.
+///
+/// Common words are usually one token; longer words split roughly every four
+/// letters, digits group in threes, and punctuation merges poorly (about one
+/// token per two characters). Non-ASCII text costs about a token per char.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalEstimator;
+
+impl LocalEstimator {
+    /// Estimate synchronously; the trait method wraps this.
+    pub fn estimate(&self, text: &str) -> usize {
+        PRETOKENIZE
+            .find_iter(text)
+            .map(|piece| piece_tokens(piece.as_str()))
+            .sum()
+    }
+}
+
+/// Estimated tokens for one pre-tokenized piece.
+fn piece_tokens(piece: &str) -> usize {
+    let body = piece.strip_prefix(' ').unwrap_or(piece);
+    let Some(first) = body.chars().next() else {
+        return 1; // a lone space
+    };
+    if first.is_whitespace() {
+        return 1;
+    }
+    if !body.is_ascii() {
+        return body.chars().count();
+    }
+    if first.is_alphabetic() {
+        body.len().div_ceil(4)
+    } else if first.is_numeric() {
+        body.len().div_ceil(3)
+    } else {
+        body.len().div_ceil(2)
+    }
+}
+
+impl TokenEstimator for LocalEstimator {
+    async fn count_tokens(&self, text: &str) -> Option<TokenCount> {
+        Some(TokenCount {
+            tokens: self.estimate(text),
+            estimator: "local",
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Provider tokenizer
+// ---------------------------------------------------------------------------
+
+/// Tokenizes through the LLM server's tokenize endpoint.
+///
+/// After the first failure the endpoint is assumed missing and never tried
+/// again, so servers without one cost a single request per session.
+pub struct ProviderTokenizer {
+    http: reqwest::Client,
+    kind: ProviderKind,
+    url: String,
+    model: String,
+    unsupported: AtomicBool,
+}
+
+impl ProviderTokenizer {
+    /// Tokenizer for `model` on the configured provider.
+    pub fn new(config: &AppConfig, model: &str) -> Self {
+        let base = crate::agent::provider::endpoint_url(&config.provider_base_url);
+        let url = match config.provider_kind {
+            ProviderKind::Ollama => format!("{base}api/tokenize"),
+            // llama.cpp and vLLM serve /tokenize next to /v1, not under it.
+            ProviderKind::OpenAi => {
+                let root = base.strip_suffix("v1/").unwrap_or(&base);
+                format!("{root}tokenize")
+            }
+        };
+        Self {
+            http: reqwest::Client::new(),
+            kind: config.provider_kind,
+            url,
+            model: model.to_string(),
+            unsupported: AtomicBool::new(false),
+        }
+    }
+
+    async fn request(&self, text: &str) -> Option<usize> {
+        let body = match self.kind {
+            ProviderKind::Ollama => serde_json::json!({ "model": self.model, "content": text }),
+            // llama.cpp reads `content`, vLLM reads `prompt`.
+            ProviderKind::OpenAi => serde_json::json!({
+                "model": self.model,
+                "content": text,
+                "prompt": text,
+                "add_special": false,
+            }),
+        };
+        let resp = self
+            .http
+            .post(&self.url)
+            .timeout(Duration::from_secs(10))
+            .json(&body)
+            .send()
+            .await
+            .ok()?;
+        if !resp.status().is_success() {
+            return None;
+        }
+        let json: serde_json::Value = resp.json().await.ok()?;
+        json["count"]
+            .as_u64()
+            .map(|n| n as usize)
+            .or_else(|| json["tokens"].as_array().map(Vec::len))
+    }
+}
+
+impl TokenEstimator for ProviderTokenizer {
+    async fn count_tokens(&self, text: &str) -> Option<TokenCount> {
+        if self.unsupported.load(Ordering::Relaxed) {
+            return None;
+        }
+        match self.request(text).await {
+            Some(tokens) => Some(TokenCount {
+                tokens,
+                estimator: "provider",
+            }),
+            None => {
+                tracing::info!(
+                    "Tokenize endpoint {} unavailable; using local token estimates",
+                    self.url
+                );
+                self.unsupported.store(true, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fallback chain
+// ---------------------------------------------------------------------------
+
+/// Tries `primary`, then `fallback`.
+pub struct FallbackEstimator<P, F> {
+    pub primary: P,
+    pub fallback: F,
+}
+
+impl<P, F> TokenEstimator for FallbackEstimator<P, F>
+where
+    P: TokenEstimator + Sync,
+    F: TokenEstimator + Sync,
+{
+    async fn count_tokens(&self, text: &str) -> Option<TokenCount> {
+        match self.primary.count_tokens(text).await {
+            Some(count) => Some(count),
+            None => self.fallback.count_tokens(text).await,
+        }
+    }
+}
+
+/// The session's estimator: the provider's tokenizer, falling back to local.
+pub fn session_estimator(
+    config: &AppConfig,
+    model: &str,
+) -> FallbackEstimator<ProviderTokenizer, LocalEstimator> {
+    FallbackEstimator {
+        primary: ProviderTokenizer::new(config, model),
+        fallback: LocalEstimator,
+    }
+}
+
+/// Flatten a chat request into the text the model will see: system prompt,
+/// tool definitions, and every message (text, tool calls, tool results).
+pub fn request_text(chat_req: &ChatRequest) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(system) = &chat_req.system {
+        parts.push(system.clone());
+    }
+    for tool in chat_req.tools.iter().flatten() {
+        parts.push(tool.name.to_string());
+        if let Some(description) = &tool.description {
+            parts.push(description.clone());
+        }
+        if let Some(schema) = &tool.schema {
+            parts.push(schema.to_string());
+        }
+    }
+    for msg in &chat_req.messages {
+        if let Some(text) = msg.content.first_text() {
+            parts.push(text.to_string());
+        }
+        if msg.role == ChatRole::Assistant {
+            for call in msg.content.tool_calls() {
+                parts.push(format!("{}({})", call.fn_name, call.fn_arguments));
+            }
+        }
+        for response in msg.content.tool_responses() {
+            parts.push(response.content.clone());
+        }
+    }
+    parts.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PartialConfig;
+    use genai::chat::{ChatMessage, ToolResponse};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve `body` with `status` to every request; returns the base URL.
+    async fn stand_in(status: u16, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 8192];
+                let _ = stream.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}/")
+    }
+
+    fn config(kind: ProviderKind, base_url: &str) -> AppConfig {
+        PartialConfig {
+            provider_kind: Some(kind),
+            provider_base_url: Some(base_url.to_string()),
+            ..Default::default()
+        }
+        .finalize()
+    }
+
+    #[test]
+    fn local_estimate_tracks_text_shape() {
+        let local = LocalEstimator;
+        assert_eq!(local.estimate(""), 0);
+        assert_eq!(local.estimate("the cat sat"), 3);
+        // Long words and numbers split into several tokens.
+        assert_eq!(local.estimate("internationalization"), 5);
+        assert_eq!(local.estimate("1234567"), 3);
+        // Punctuation-heavy text costs more than chars/4 suggests.
+        let json = r#"{"a":[1,2],"b":{}}"#;
+        assert!(local.estimate(json) > json.len() / 4);
+    }
+
+    #[tokio::test]
+    async fn provider_tokenizer_reads_token_list_or_count() {
+        let base = stand_in(200, r#"{"tokens":[1,2,3,4]}"#).await;
+        let tokenizer = ProviderTokenizer::new(&config(ProviderKind::Ollama, &base), "m");
+        let count = tokenizer.count_tokens("hello").await.unwrap();
+        assert_eq!(count, TokenCount { tokens: 4, estimator: "provider" });
+
+        let base = stand_in(200, r#"{"count":7,"max_model_len":4096}"#).await;
+        let tokenizer = ProviderTokenizer::new(&config(ProviderKind::OpenAi, &base), "m");
+        assert_eq!(tokenizer.count_tokens("hello").await.unwrap().tokens, 7);
+    }
+
+    #[test]
+    fn openai_tokenize_url_sits_beside_v1() {
+        let tokenizer =
+            ProviderTokenizer::new(&config(ProviderKind::OpenAi, "http://box:8000/v1"), "m");
+        assert_eq!(tokenizer.url, "http://box:8000/tokenize");
+    }
+
+    #[tokio::test]
+    async fn fallback_uses_local_once_provider_fails() {
+        let base = stand_in(404, "{}").await;
+        let estimator = session_estimator(&config(ProviderKind::Ollama, &base), "m");
+        let count = estimator.count_tokens("the cat sat").await.unwrap();
+        assert_eq!(count, TokenCount { tokens: 3, estimator: "local" });
+        assert!(estimator.primary.unsupported.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn request_text_includes_system_messages_and_tool_results() {
+        let req = ChatRequest::from_system("You are ouro.")
+            .append_message(ChatMessage::assistant("Reading."))
+            .append_message(ChatMessage::from(ToolResponse::new("c1", "file body")));
+        let text = request_text(&req);
+        assert!(text.starts_with("You are ouro."));
+        assert!(text.contains("Reading."));
+        assert!(text.contains("file body"));
+    }
+}
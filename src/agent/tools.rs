@@ -1,9 +1,18 @@
 //! Tool schema definitions and dispatch for the agent loop.
 //!
-//! Defines the three core tools (`shell_exec`, `file_read`, `file_write`) as
+//! Defines the agent's tools (`shell_exec`, `file_read`, `file_write`,
+//! `web_fetch`, `spawn_agent`, `flag_discovery`, `recall_observation`, and
+//! `job_start`/`job_status`/`job_kill`/`job_list`) as
 //! [`genai::chat::Tool`] schemas and provides a dispatch function that routes
 //! tool calls to their implementations.
 //!
+//! `spawn_agent`, `flag_discovery`, and `recall_observation` need session
+//! state (config, event channel, session log), so the agent loop routes them
+//! to [`crate::agent::sub_agent`], [`crate::agent::discoveries`], and
+//! [`crate::agent::observations`] instead of [`dispatch_tool_call`]. The
+//! `job_*` tools go to [`dispatch_job_call`] with the session's
+//! [`JobRegistry`].
+//!
 //! Tool errors are always returned as structured JSON strings (never panics or
 //! `Err` variants) so the model can observe the error and react.
 
@@ -12,9 +21,13 @@ use std::path::Path;
 use genai::chat::Tool;
 use serde_json::json;
 
+use crate::agent::truncation::{
+    output_file_name, truncate_output, truncate_with_note, OutputLimits, OUTPUTS_DIR,
+};
+use crate::exec::{JobRegistry, JobStatus, OutputSink};
 use crate::safety::SafetyLayer;
 
-/// Define the three core tool schemas for the agent.
+/// Define the tool schemas for the agent.
 ///
 /// Returns a `Vec<Tool>` suitable for passing to
 /// [`genai::chat::ChatRequest::with_tools`].
@@ -23,13 +36,23 @@ use crate::safety::SafetyLayer;
 /// 1. `shell_exec` -- Execute a shell command in the workspace directory
 /// 2. `file_read` -- Read the contents of a file (unrestricted)
 /// 3. `file_write` -- Write content to a file (workspace-restricted)
+/// 4. `web_fetch` -- Fetch a URL as readable text (domain-filtered)
+/// 5. `spawn_agent` -- Run a sub-agent on a task and return its final answer
+/// 6. `flag_discovery` -- Record a notable finding for the operator
+/// 7. `recall_observation` -- Fetch back a masked tool output by call_id
+/// 8. `job_start` -- Start a shell command as a background job
+/// 9. `job_status` -- Report a job's state and the tail of its output
+/// 10. `job_kill` -- Kill a running job
+/// 11. `job_list` -- List this session's jobs
 pub fn define_tools() -> Vec<Tool> {
     vec![
         Tool::new("shell_exec")
             .with_description(
                 "Execute a shell command in the workspace directory. \
                  The command runs via `sh -c` with the workspace as the working directory. \
-                 Returns a JSON object with fields: stdout, stderr, exit_code, timed_out.",
+                 Returns a JSON object with fields: stdout, stderr, exit_code, timed_out, killed_by. \
+                 Very long stdout or stderr keeps only its start and end; the full output is \
+                 saved under .ouro-outputs/ in the workspace.",
             )
             .with_schema(json!({
                 "type": "object",
@@ -45,7 +68,8 @@ pub fn define_tools() -> Vec<Tool> {
             .with_description(
                 "Read the contents of a file. The path can be relative to the workspace \
                  root or an absolute path. Read access is unrestricted -- any file on \
-                 the filesystem can be read.",
+                 the filesystem can be read. A very long file keeps only its start and end; \
+                 the full contents are saved under .ouro-outputs/ in the workspace.",
             )
             .with_schema(json!({
                 "type": "object",
@@ -62,7 +86,7 @@ pub fn define_tools() -> Vec<Tool> {
                 "Write content to a file within the workspace directory. The path must \
                  be relative to the workspace root. Parent directories are created \
                  automatically if they do not exist. Writes outside the workspace \
-                 directory are rejected.",
+                 directory and to protected files (such as ouro.toml) are rejected.",
             )
             .with_schema(json!({
                 "type": "object",
@@ -78,6 +102,171 @@ pub fn define_tools() -> Vec<Tool> {
                 },
                 "required": ["path", "content"]
             })),
+        Tool::new("web_fetch")
+            .with_description(
+                "Fetch a web page or other text resource over HTTP(S). HTML is converted \
+                 to readable markdown-style text and long content is truncated. Some \
+                 domains may be blocked by configuration. Returns a JSON object with \
+                 fields: url, status, content_type, content, truncated.",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "Absolute http:// or https:// URL to fetch"
+                    }
+                },
+                "required": ["url"]
+            })),
+        Tool::new("spawn_agent")
+            .with_description(
+                "Spawn a sub-agent to carry out a task and wait for it to finish. The \
+                 sub-agent starts with a fresh conversation containing only the task, works \
+                 in a sub-directory of the workspace with the same tools, and stops when it \
+                 replies without calling tools or runs out of turns. Returns a JSON object \
+                 with fields: agent_id, status, turns, final_answer, log_dir.",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "task": {
+                        "type": "string",
+                        "description": "Complete instructions for the sub-agent, including any context it needs"
+                    },
+                    "directory": {
+                        "type": "string",
+                        "description": "Sub-directory of the workspace the sub-agent works in (created if missing)"
+                    },
+                    "max_turns": {
+                        "type": "integer",
+                        "description": "Turn budget for the sub-agent (default 20, max 200)"
+                    }
+                },
+                "required": ["task", "directory"]
+            })),
+        Tool::new("flag_discovery")
+            .with_description(
+                "Flag a notable finding so the operator sees it in the Discoveries tab. \
+                 Discoveries are saved permanently and survive restarts. Use this for \
+                 results worth keeping, not for routine progress. Returns a JSON object \
+                 with fields: recorded, timestamp.",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Short one-line summary of the finding"
+                    },
+                    "body": {
+                        "type": "string",
+                        "description": "Details: what was found, why it matters, how to reproduce"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional tags for grouping related discoveries"
+                    },
+                    "files": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional workspace-relative paths of related files"
+                    }
+                },
+                "required": ["title", "body"]
+            })),
+        Tool::new("recall_observation")
+            .with_description(
+                "Fetch back the full output of an earlier tool call that was masked to save \
+                 context. Masked outputs name their call_id and session. Optionally fetch only \
+                 a range of lines. Returns a JSON object with fields: call_id, fn_name, total_lines, \
+                 content (and start_line, end_line for a range).",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "call_id": {
+                        "type": "string",
+                        "description": "The call_id named in the masked output"
+                    },
+                    "session": {
+                        "type": "string",
+                        "description": "The session named in the masked output (default: the current session)"
+                    },
+                    "start_line": {
+                        "type": "integer",
+                        "description": "First line to return, 1-based (default 1)"
+                    },
+                    "end_line": {
+                        "type": "integer",
+                        "description": "Last line to return, inclusive (default: last line)"
+                    }
+                },
+                "required": ["call_id"]
+            })),
+        Tool::new("job_start")
+            .with_description(
+                "Start a shell command as a background job that may run longer than the \
+                 shell_exec timeout, such as a server or a training run. It runs like \
+                 shell_exec (same filters, limits, and sandbox) and is killed when the \
+                 session ends. Returns a JSON object with fields: job_id, command, state, \
+                 exit_code, killed_by, runtime_secs.",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The shell command to run in the background"
+                    }
+                },
+                "required": ["command"]
+            })),
+        Tool::new("job_status")
+            .with_description(
+                "Check a background job. Returns a JSON object with fields: job_id, command, \
+                 state (running, exited, or killed), exit_code, killed_by, runtime_secs, \
+                 output (the last lines of combined stdout and stderr).",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "job_id": {
+                        "type": "string",
+                        "description": "The job_id returned by job_start"
+                    },
+                    "tail_lines": {
+                        "type": "integer",
+                        "description": "Lines of output to return (default 50)"
+                    }
+                },
+                "required": ["job_id"]
+            })),
+        Tool::new("job_kill")
+            .with_description(
+                "Kill a running background job and every process it started. Returns the \
+                 job's final status as a JSON object.",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {
+                    "job_id": {
+                        "type": "string",
+                        "description": "The job_id returned by job_start"
+                    }
+                },
+                "required": ["job_id"]
+            })),
+        Tool::new("job_list")
+            .with_description(
+                "List the background jobs started in this session. Returns a JSON object \
+                 with a jobs array of job statuses.",
+            )
+            .with_schema(json!({
+                "type": "object",
+                "properties": {}
+            })),
     ]
 }
 
@@ -90,15 +279,20 @@ pub fn tool_descriptions() -> String {
 ### shell_exec
 Execute a shell command in the workspace directory.
 - **command** (string, required): The shell command to execute
-- Returns: JSON with stdout, stderr, exit_code, timed_out fields
+- Returns: JSON with stdout, stderr, exit_code, timed_out, killed_by fields
 - Commands run via `sh -c` with the workspace as the working directory
-- Commands are filtered against a security blocklist
+- Commands are filtered against a security blocklist (and, if configured, an allowlist of programs)
+- Configured CPU, memory, and file-size limits stop runaway commands; killed_by names the limit hit
+- If sandboxing is configured, only the workspace is writable, /tmp is private, and there may be no network
+- Changes to protected files (such as ouro.toml) are undone after the command
+- Long stdout/stderr is cut to its start and end with a [truncated N bytes; ...] marker naming the file in .ouro-outputs/ that holds the full output; page through it with sed -n or grep
 
 ### file_read
 Read the contents of a file.
 - **path** (string, required): File path, relative to workspace or absolute
 - Returns: The file contents as a string
 - Read access is unrestricted (can read any file on the filesystem)
+- A long file is cut to its start and end with a [truncated N bytes; ...] marker; read ranges with shell_exec (sed -n) instead
 
 ### file_write
 Write content to a file within the workspace directory.
@@ -106,16 +300,81 @@ Write content to a file within the workspace directory.
 - **content** (string, required): Content to write to the file
 - Returns: JSON with written_bytes and path fields
 - Parent directories are created automatically
-- Writes outside the workspace directory are rejected"
+- Writes outside the workspace directory are rejected
+- Protected files (such as ouro.toml) can be read but not written
+
+### web_fetch
+Fetch a web page or other text resource over HTTP(S).
+- **url** (string, required): Absolute http:// or https:// URL to fetch
+- Returns: JSON with url (after redirects), status, content_type, content, truncated fields
+- HTML is converted to markdown-style text; binary content is rejected
+- Content beyond the configured size budget is cut off (truncated: true)
+- Domains may be restricted by an allow/deny list
+
+### spawn_agent
+Spawn a sub-agent to carry out a task and wait for its final answer.
+- **task** (string, required): Complete instructions for the sub-agent, including any context it needs
+- **directory** (string, required): Sub-directory of the workspace the sub-agent works in
+- **max_turns** (integer, optional): Turn budget (default 20, max 200)
+- Returns: JSON with agent_id, status, turns, final_answer, log_dir fields
+- The sub-agent does not see your conversation -- put everything it needs in the task
+- It finishes by replying without calling tools; that reply is the final_answer
+
+### flag_discovery
+Flag a notable finding for the operator.
+- **title** (string, required): Short one-line summary of the finding
+- **body** (string, required): Details -- what was found, why it matters, how to reproduce
+- **tags** (array of strings, optional): Tags for grouping related discoveries
+- **files** (array of strings, optional): Workspace-relative paths of related files
+- Returns: JSON with recorded and timestamp fields
+- Discoveries are saved permanently and survive restarts; use for results worth keeping
+
+### recall_observation
+Fetch back a tool output that was masked to save context.
+- **call_id** (string, required): The call_id named in the masked output
+- **session** (string, optional): The session named in the masked output; defaults to the current session
+- **start_line** (integer, optional): First line to return, 1-based
+- **end_line** (integer, optional): Last line to return, inclusive
+- Returns: JSON with call_id, fn_name, total_lines, content (plus start_line, end_line for a range)
+- Recall only what you need; a line range keeps the context small
+
+### job_start
+Start a shell command as a background job.
+- **command** (string, required): The shell command to run in the background
+- Returns: JSON with job_id, command, state, exit_code, killed_by, runtime_secs fields
+- For servers, training runs, and anything else that outlasts the shell_exec timeout
+- Filtered, limited, and sandboxed like shell_exec; only a few jobs may run at once
+- Every job is killed when the session ends
+
+### job_status
+Check a background job.
+- **job_id** (string, required): The job_id returned by job_start
+- **tail_lines** (integer, optional): Lines of output to return (default 50)
+- Returns: JSON with job_id, command, state (running/exited/killed), exit_code, killed_by, runtime_secs, output
+- output is the end of the job's combined stdout and stderr
+
+### job_kill
+Kill a running background job and everything it started.
+- **job_id** (string, required): The job_id returned by job_start
+- Returns: JSON with the job's final status
+
+### job_list
+List this session's background jobs.
+- Returns: JSON with a jobs array of statuses"
         .to_string()
 }
 
 /// Dispatch a tool call to its implementation.
 ///
 /// Routes based on `call.fn_name`:
-/// - `shell_exec` -> [`SafetyLayer::execute`]
+/// - `shell_exec` -> [`SafetyLayer::execute`], streaming output
+///   lines to `output` if given
 /// - `file_read` -> [`tokio::fs::read_to_string`]
 /// - `file_write` -> workspace-validated [`tokio::fs::write`]
+/// - `web_fetch` -> [`SafetyLayer::fetch`]
+///
+/// `shell_exec` and `file_read` results longer than `limits` are truncated
+/// (see [`crate::agent::truncation`]).
 ///
 /// # Returns
 ///
@@ -128,19 +387,29 @@ pub async fn dispatch_tool_call(
     call: &genai::chat::ToolCall,
     safety: &SafetyLayer,
     workspace: &Path,
+    limits: &OutputLimits,
+    output: Option<OutputSink>,
 ) -> String {
     match call.fn_name.as_str() {
-        "shell_exec" => dispatch_shell_exec(call, safety).await,
-        "file_read" => dispatch_file_read(call, workspace).await,
+        "shell_exec" => dispatch_shell_exec(call, safety, workspace, limits, output).await,
+        "file_read" => dispatch_file_read(call, workspace, limits).await,
         "file_write" => dispatch_file_write(call, safety, workspace).await,
+        "web_fetch" => dispatch_web_fetch(call, safety).await,
         unknown => {
             json!({"error": format!("Unknown tool: {}", unknown)}).to_string()
         }
     }
 }
 
-/// Execute a shell command through the safety layer.
-async fn dispatch_shell_exec(call: &genai::chat::ToolCall, safety: &SafetyLayer) -> String {
+/// Execute a shell command through the safety layer, streaming its output to
+/// `output` if given. Long stdout and stderr are truncated separately.
+async fn dispatch_shell_exec(
+    call: &genai::chat::ToolCall,
+    safety: &SafetyLayer,
+    workspace: &Path,
+    limits: &OutputLimits,
+    output: Option<OutputSink>,
+) -> String {
     let command = match call.fn_arguments.get("command").and_then(|v| v.as_str()) {
         Some(cmd) => cmd,
         None => {
@@ -149,8 +418,23 @@ async fn dispatch_shell_exec(call: &genai::chat::ToolCall, safety: &SafetyLayer)
         }
     };
 
-    match safety.execute(command).await {
-        Ok(result) => {
+    match safety.execute(command, output).await {
+        Ok(mut result) => {
+            let max_bytes = limits.shell_exec_max_bytes;
+            result.stdout = truncate_output(
+                result.stdout,
+                max_bytes,
+                workspace,
+                &output_file_name(&call.call_id, ""),
+            )
+            .await;
+            result.stderr = truncate_output(
+                result.stderr,
+                max_bytes,
+                workspace,
+                &output_file_name(&call.call_id, ".stderr"),
+            )
+            .await;
             // ExecResult derives Serialize, so we can serialize it directly.
             serde_json::to_string(&result).unwrap_or_else(|e| {
                 json!({"error": format!("Failed to serialize exec result: {}", e)}).to_string()
@@ -160,8 +444,95 @@ async fn dispatch_shell_exec(call: &genai::chat::ToolCall, safety: &SafetyLayer)
     }
 }
 
-/// Read a file from the filesystem (unrestricted access).
-async fn dispatch_file_read(call: &genai::chat::ToolCall, workspace: &Path) -> String {
+/// Default number of output lines `job_status` returns.
+const JOB_STATUS_TAIL_LINES: usize = 50;
+
+/// Dispatch a `job_*` tool call against this session's background jobs.
+///
+/// Like [`dispatch_tool_call`], always returns a JSON string, with errors as
+/// `{"error": "..."}`.
+pub async fn dispatch_job_call(
+    call: &genai::chat::ToolCall,
+    safety: &SafetyLayer,
+    jobs: &JobRegistry,
+) -> String {
+    let fn_name = call.fn_name.as_str();
+    let argument = |key: &str| call.fn_arguments.get(key).and_then(|v| v.as_str());
+    let missing = |key: &str| {
+        json!({"error": format!("{fn_name}: missing or invalid '{key}' argument")}).to_string()
+    };
+    let status_json = |status: &JobStatus| {
+        serde_json::to_string(status).unwrap_or_else(|e| {
+            json!({"error": format!("Failed to serialize job status: {}", e)}).to_string()
+        })
+    };
+
+    match fn_name {
+        "job_start" => {
+            let Some(command) = argument("command") else {
+                return missing("command");
+            };
+            match safety.start_job(jobs, command).await {
+                Ok(status) => status_json(&status),
+                Err(e) => json!({"error": format!("job_start failed: {:#}", e)}).to_string(),
+            }
+        }
+        "job_status" => {
+            let Some(job_id) = argument("job_id") else {
+                return missing("job_id");
+            };
+            let tail_lines = call
+                .fn_arguments
+                .get("tail_lines")
+                .and_then(|v| v.as_u64())
+                .map_or(JOB_STATUS_TAIL_LINES, |n| n as usize);
+            match jobs.status(job_id, tail_lines) {
+                Some((status, output)) => {
+                    let mut value = serde_json::to_value(&status).unwrap_or_default();
+                    value["output"] = json!(output);
+                    value.to_string()
+                }
+                None => json!({"error": format!("job_status: no job '{}'", job_id)}).to_string(),
+            }
+        }
+        "job_kill" => {
+            let Some(job_id) = argument("job_id") else {
+                return missing("job_id");
+            };
+            match jobs.kill(job_id).await {
+                Ok(status) => status_json(&status),
+                Err(e) => json!({"error": format!("job_kill: {}", e)}).to_string(),
+            }
+        }
+        "job_list" => json!({"jobs": jobs.list()}).to_string(),
+        unknown => json!({"error": format!("Unknown tool: {}", unknown)}).to_string(),
+    }
+}
+
+/// Fetch a URL through the safety layer's domain filter.
+async fn dispatch_web_fetch(call: &genai::chat::ToolCall, safety: &SafetyLayer) -> String {
+    let url = match call.fn_arguments.get("url").and_then(|v| v.as_str()) {
+        Some(url) => url,
+        None => {
+            return json!({"error": "web_fetch: missing or invalid 'url' argument"}).to_string();
+        }
+    };
+
+    match safety.fetch(url).await {
+        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|e| {
+            json!({"error": format!("Failed to serialize fetch result: {}", e)}).to_string()
+        }),
+        Err(e) => json!({"error": format!("web_fetch failed: {:#}", e)}).to_string(),
+    }
+}
+
+/// Read a file from the filesystem (unrestricted access), truncating long
+/// contents.
+async fn dispatch_file_read(
+    call: &genai::chat::ToolCall,
+    workspace: &Path,
+    limits: &OutputLimits,
+) -> String {
     let path_str = match call.fn_arguments.get("path").and_then(|v| v.as_str()) {
         Some(p) => p,
         None => {
@@ -177,7 +548,19 @@ async fn dispatch_file_read(call: &genai::chat::ToolCall, workspace: &Path) -> S
     };
 
     match tokio::fs::read_to_string(&full_path).await {
-        Ok(content) => content,
+        // A saved output is already on disk; another copy would not help.
+        Ok(content) if full_path.starts_with(workspace.join(OUTPUTS_DIR)) => {
+            if limits.file_read_max_bytes == 0 || content.len() <= limits.file_read_max_bytes {
+                content
+            } else {
+                let note = format!("page through {path_str} with shell_exec (sed -n, grep)");
+                truncate_with_note(&content, limits.file_read_max_bytes, &note)
+            }
+        }
+        Ok(content) => {
+            let file_name = output_file_name(&call.call_id, "");
+            truncate_output(content, limits.file_read_max_bytes, workspace, &file_name).await
+        }
         Err(e) => json!({"error": format!("file_read: {}", e)}).to_string(),
     }
 }
@@ -249,6 +632,16 @@ async fn dispatch_file_write(
         .to_string();
     }
 
+    if let Some(pattern) = safety.protected_pattern(&full_path) {
+        return json!({
+            "error": format!(
+                "file_write: path '{}' is protected (matches '{}') and may not be modified",
+                path_str, pattern
+            )
+        })
+        .to_string();
+    }
+
     // Write the file.
     match tokio::fs::write(&full_path, content).await {
         Ok(()) => {
@@ -265,21 +658,36 @@ async fn dispatch_file_write(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::AppConfig;
+    use crate::config::{test_config, AppConfig};
     use genai::chat::ToolCall;
     use tempfile::TempDir;
 
     #[test]
-    fn define_tools_returns_three_tools() {
+    fn define_tools_returns_eleven_tools() {
         let tools = define_tools();
-        assert_eq!(tools.len(), 3);
+        assert_eq!(tools.len(), 11);
     }
 
     #[test]
     fn define_tools_has_correct_names() {
         let tools = define_tools();
         let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
-        assert_eq!(names, vec!["shell_exec", "file_read", "file_write"]);
+        assert_eq!(
+            names,
+            vec![
+                "shell_exec",
+                "file_read",
+                "file_write",
+                "web_fetch",
+                "spawn_agent",
+                "flag_discovery",
+                "recall_observation",
+                "job_start",
+                "job_status",
+                "job_kill",
+                "job_list"
+            ]
+        );
     }
 
     #[test]
@@ -312,25 +720,30 @@ mod tests {
         assert!(desc.contains("### shell_exec"));
         assert!(desc.contains("### file_read"));
         assert!(desc.contains("### file_write"));
+        assert!(desc.contains("### web_fetch"));
+        assert!(desc.contains("### spawn_agent"));
+        assert!(desc.contains("### flag_discovery"));
+        assert!(desc.contains("### recall_observation"));
+        assert!(desc.contains("### job_start"));
+        assert!(desc.contains("### job_status"));
+        assert!(desc.contains("### job_kill"));
+        assert!(desc.contains("### job_list"));
     }
 
     /// Create a SafetyLayer with a temporary workspace for testing.
     fn make_safety(tmp: &TempDir) -> SafetyLayer {
+        make_safety_protecting(tmp, &[])
+    }
+
+    /// Create a SafetyLayer whose workspace protects the given globs.
+    fn make_safety_protecting(tmp: &TempDir, protected: &[&str]) -> SafetyLayer {
         let workspace = tmp.path().join("workspace");
         std::fs::create_dir_all(&workspace).unwrap();
 
         let config = AppConfig {
-            model: "test-model".to_string(),
-            workspace,
-            shell_timeout_secs: 10,
-            context_limit: 8192,
-            blocked_patterns: vec![],
             security_log_path: tmp.path().join("security.log"),
-            soft_threshold_pct: 0.70,
-            hard_threshold_pct: 0.90,
-            carryover_turns: 5,
-            max_restarts: None,
-            auto_restart: true,
+            protected_paths: protected.iter().map(|p| p.to_string()).collect(),
+            ..test_config(&workspace)
         };
 
         SafetyLayer::new(&config).unwrap()
@@ -352,7 +765,7 @@ mod tests {
         let workspace = tmp.path().join("workspace");
 
         let call = make_tool_call("shell_exec", json!({"command": "echo hello"}));
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert_eq!(parsed["stdout"].as_str().unwrap().trim(), "hello");
@@ -367,7 +780,7 @@ mod tests {
         let workspace = tmp.path().join("workspace");
 
         let call = make_tool_call("shell_exec", json!({}));
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed["error"].as_str().unwrap().contains("missing"));
@@ -383,12 +796,120 @@ mod tests {
         std::fs::write(workspace.join("test.txt"), "file contents here").unwrap();
 
         let call = make_tool_call("file_read", json!({"path": "test.txt"}));
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         // file_read returns raw content, not JSON
         assert_eq!(result, "file contents here");
     }
 
+    #[tokio::test]
+    async fn dispatch_shell_exec_truncates_long_output() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety(&tmp);
+        let workspace = tmp.path().join("workspace");
+        let limits = OutputLimits {
+            shell_exec_max_bytes: 100,
+            file_read_max_bytes: 0,
+        };
+
+        let call = make_tool_call("shell_exec", json!({"command": "seq 1000; echo oops >&2"}));
+        let result = dispatch_tool_call(&call, &safety, &workspace, &limits, None).await;
+
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let stdout = parsed["stdout"].as_str().unwrap();
+        assert!(stdout.starts_with("1\n2\n"));
+        assert!(stdout.ends_with("999\n1000\n"));
+        assert!(stdout.contains("; full output saved to .ouro-outputs/test-call-1.txt]"));
+        assert_eq!(parsed["stderr"], "oops\n");
+
+        let saved = std::fs::read_to_string(workspace.join(".ouro-outputs/test-call-1.txt")).unwrap();
+        assert_eq!(saved.lines().count(), 1000);
+        assert!(!workspace.join(".ouro-outputs/test-call-1.stderr.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn dispatch_file_read_truncates_long_file() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety(&tmp);
+        let workspace = tmp.path().join("workspace");
+        let limits = OutputLimits {
+            shell_exec_max_bytes: 0,
+            file_read_max_bytes: 100,
+        };
+        let content: String = (1..=500).map(|i| format!("{i}\n")).collect();
+        std::fs::write(workspace.join("big.txt"), &content).unwrap();
+
+        let call = make_tool_call("file_read", json!({"path": "big.txt"}));
+        let result = dispatch_tool_call(&call, &safety, &workspace, &limits, None).await;
+        assert!(result.len() < 200);
+        assert!(result.contains("; full output saved to .ouro-outputs/test-call-1.txt]"));
+        let saved = std::fs::read_to_string(workspace.join(".ouro-outputs/test-call-1.txt")).unwrap();
+        assert_eq!(saved, content);
+
+        // Reading the saved copy truncates it again without making another.
+        let call = ToolCall {
+            call_id: "test-call-2".to_string(),
+            ..make_tool_call("file_read", json!({"path": ".ouro-outputs/test-call-1.txt"}))
+        };
+        let result = dispatch_tool_call(&call, &safety, &workspace, &limits, None).await;
+        assert!(result.contains("; page through .ouro-outputs/test-call-1.txt with shell_exec"));
+        assert!(!workspace.join(".ouro-outputs/test-call-2.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn dispatch_job_call_runs_background_job() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety(&tmp);
+        let jobs = JobRegistry::new(2);
+
+        let call = make_tool_call("job_start", json!({"command": "echo started; sleep 30"}));
+        let started: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert_eq!(started["job_id"], "job-1");
+        assert_eq!(started["state"], "running");
+
+        let call = make_tool_call("job_list", json!({}));
+        let list: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert_eq!(list["jobs"].as_array().unwrap().len(), 1);
+
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let call = make_tool_call("job_kill", json!({"job_id": "job-1"}));
+        let killed: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert_eq!(killed["state"], "killed");
+        assert_eq!(killed["killed_by"], "job_kill");
+
+        let call = make_tool_call("job_status", json!({"job_id": "job-1"}));
+        let status: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert_eq!(status["state"], "killed");
+        assert_eq!(status["output"], "started\n");
+    }
+
+    #[tokio::test]
+    async fn dispatch_job_call_errors() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety(&tmp);
+        let jobs = JobRegistry::new(2);
+
+        let call = make_tool_call("job_start", json!({}));
+        let result: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert!(result["error"].as_str().unwrap().contains("missing"));
+        assert!(jobs.list().is_empty());
+
+        let call = make_tool_call("job_status", json!({}));
+        let result: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert!(result["error"].as_str().unwrap().contains("missing"));
+
+        let call = make_tool_call("job_kill", json!({"job_id": "job-9"}));
+        let result: serde_json::Value =
+            serde_json::from_str(&dispatch_job_call(&call, &safety, &jobs).await).unwrap();
+        assert!(result["error"].as_str().unwrap().contains("no job 'job-9'"));
+    }
+
     #[tokio::test]
     async fn dispatch_file_read_nonexistent_file() {
         let tmp = TempDir::new().unwrap();
@@ -396,7 +917,7 @@ mod tests {
         let workspace = tmp.path().join("workspace");
 
         let call = make_tool_call("file_read", json!({"path": "no_such_file.txt"}));
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed["error"].as_str().unwrap().contains("file_read"));
@@ -416,7 +937,7 @@ mod tests {
             "file_read",
             json!({"path": outside.to_str().unwrap()}),
         );
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         // Reads are unrestricted
         assert_eq!(result, "outside content");
@@ -432,7 +953,7 @@ mod tests {
             "file_write",
             json!({"path": "output.txt", "content": "written content"}),
         );
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert_eq!(parsed["written_bytes"], 15);
@@ -453,7 +974,7 @@ mod tests {
             "file_write",
             json!({"path": "sub/dir/file.txt", "content": "nested"}),
         );
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert_eq!(parsed["written_bytes"], 6);
@@ -472,7 +993,7 @@ mod tests {
             "file_write",
             json!({"path": "../escape.txt", "content": "should fail"}),
         );
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(
@@ -488,6 +1009,32 @@ mod tests {
         assert!(!tmp.path().join("escape.txt").exists());
     }
 
+    #[tokio::test]
+    async fn dispatch_file_write_protected_path_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety_protecting(&tmp, &["ouro.toml", "archive/**"]);
+        let workspace = tmp.path().join("workspace");
+        std::fs::write(workspace.join("ouro.toml"), "[safety]").unwrap();
+        std::os::unix::fs::symlink(workspace.join("ouro.toml"), workspace.join("link")).unwrap();
+
+        for path in ["ouro.toml", "archive/2024/notes.md", "link"] {
+            let call = make_tool_call("file_write", json!({"path": path, "content": "x"}));
+            let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
+            let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+            assert!(
+                parsed["error"].as_str().unwrap().contains("is protected"),
+                "Expected protected-path error for {path}, got: {result}"
+            );
+        }
+        assert_eq!(std::fs::read_to_string(workspace.join("ouro.toml")).unwrap(), "[safety]");
+        assert!(!workspace.join("archive/2024/notes.md").exists());
+
+        // Unprotected neighbours are still writable.
+        let call = make_tool_call("file_write", json!({"path": "archive.md", "content": "x"}));
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
+        assert!(result.contains("written_bytes"), "{result}");
+    }
+
     #[tokio::test]
     async fn dispatch_file_write_missing_content() {
         let tmp = TempDir::new().unwrap();
@@ -495,12 +1042,38 @@ mod tests {
         let workspace = tmp.path().join("workspace");
 
         let call = make_tool_call("file_write", json!({"path": "file.txt"}));
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed["error"].as_str().unwrap().contains("content"));
     }
 
+    #[tokio::test]
+    async fn dispatch_web_fetch_missing_url() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety(&tmp);
+        let workspace = tmp.path().join("workspace");
+
+        let call = make_tool_call("web_fetch", json!({}));
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
+
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(parsed["error"].as_str().unwrap().contains("url"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_web_fetch_rejects_non_http_url() {
+        let tmp = TempDir::new().unwrap();
+        let safety = make_safety(&tmp);
+        let workspace = tmp.path().join("workspace");
+
+        let call = make_tool_call("web_fetch", json!({"url": "file:///etc/passwd"}));
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
+
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(parsed["error"].as_str().unwrap().contains("scheme"));
+    }
+
     #[tokio::test]
     async fn dispatch_unknown_tool() {
         let tmp = TempDir::new().unwrap();
@@ -508,7 +1081,7 @@ mod tests {
         let workspace = tmp.path().join("workspace");
 
         let call = make_tool_call("nonexistent_tool", json!({}));
-        let result = dispatch_tool_call(&call, &safety, &workspace).await;
+        let result = dispatch_tool_call(&call, &safety, &workspace, &OutputLimits::default(), None).await;
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed["error"]
@@ -0,0 +1,172 @@
+//! Truncation of oversized tool results before they enter the context.
+//!
+//! One `cat` of a large file can fill the context window in a single turn.
+//! A `shell_exec` stream or `file_read` result longer than its configured
+//! limit (`[output]` in the config) keeps its head and tail around a marker:
+//!
+//! ```text
+//! [truncated 48213 bytes; full output saved to .ouro-outputs/call_7.txt]
+//! ```
+//!
+//! The full output is written to `.ouro-outputs/` in the workspace first, so
+//! the agent can page through it with `shell_exec` (`sed -n`, `grep`, ...).
+
+use std::path::Path;
+
+use crate::config::AppConfig;
+
+/// Directory in the workspace holding full copies of truncated tool output.
+pub const OUTPUTS_DIR: &str = ".ouro-outputs";
+
+/// Largest result each tool may return whole, in bytes. 0 means no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Applied to stdout and stderr separately.
+    pub shell_exec_max_bytes: usize,
+    pub file_read_max_bytes: usize,
+}
+
+impl OutputLimits {
+    /// Limits from the `output_*` settings.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            shell_exec_max_bytes: config.output_shell_exec_max_bytes,
+            file_read_max_bytes: config.output_file_read_max_bytes,
+        }
+    }
+}
+
+/// File name under [`OUTPUTS_DIR`] for the output of `call_id`. Characters
+/// that are unsafe in a file name are replaced with `_`.
+pub fn output_file_name(call_id: &str, suffix: &str) -> String {
+    let id: String = call_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let id = if id.is_empty() { "call".to_string() } else { id };
+    format!("{id}{suffix}.txt")
+}
+
+/// Truncate `text` to about `max_bytes` if it is longer, saving the full text
+/// to `file_name` under [`OUTPUTS_DIR`] in `workspace` first. Returns `text`
+/// unchanged when it fits or `max_bytes` is 0.
+pub async fn truncate_output(text: String, max_bytes: usize, workspace: &Path, file_name: &str) -> String {
+    if max_bytes == 0 || text.len() <= max_bytes {
+        return text;
+    }
+    let saved = format!("{OUTPUTS_DIR}/{file_name}");
+    let dir = workspace.join(OUTPUTS_DIR);
+    let note = match tokio::fs::create_dir_all(&dir).await {
+        Ok(()) => match tokio::fs::write(dir.join(file_name), &text).await {
+            Ok(()) => format!("full output saved to {saved}"),
+            Err(e) => format!("full output could not be saved: {e}"),
+        },
+        Err(e) => format!("full output could not be saved: {e}"),
+    };
+    truncate_with_note(&text, max_bytes, &note)
+}
+
+/// Keep the head and tail of `text`, about `max_bytes` together, with a
+/// marker in between counting the bytes dropped and ending with `note`.
+pub fn truncate_with_note(text: &str, max_bytes: usize, note: &str) -> String {
+    let (head, tail) = head_and_tail(text, max_bytes);
+    let dropped = text.len() - head.len() - tail.len();
+    let mut out = String::with_capacity(head.len() + tail.len() + note.len() + 40);
+    out.push_str(head);
+    if !head.is_empty() && !head.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("[truncated {dropped} bytes; {note}]\n"));
+    out.push_str(tail);
+    out
+}
+
+/// Split off up to `max_bytes / 2` bytes from each end of `text`, cut at line
+/// boundaries where the kept part has one and at character boundaries
+/// otherwise.
+fn head_and_tail(text: &str, max_bytes: usize) -> (&str, &str) {
+    let half = max_bytes / 2;
+
+    let mut head_end = half.min(text.len());
+    while !text.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    if let Some(newline) = text[..head_end].rfind('\n') {
+        head_end = newline + 1;
+    }
+
+    let mut tail_start = text.len().saturating_sub(half).max(head_end);
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    // Start at the next line unless that would leave nothing but the end of
+    // the last line.
+    if tail_start > 0
+        && text.as_bytes()[tail_start - 1] != b'\n'
+        && let Some(newline) = text[tail_start..].find('\n')
+        && tail_start + newline + 1 < text.len()
+    {
+        tail_start += newline + 1;
+    }
+
+    (&text[..head_end], &text[tail_start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(n: usize) -> String {
+        (1..=n).map(|i| format!("line {i:04}\n")).collect()
+    }
+
+    #[test]
+    fn short_text_is_unchanged() {
+        let text = numbered_lines(3);
+        let (head, tail) = head_and_tail(&text, 1000);
+        assert_eq!(head, text);
+        assert_eq!(tail, "");
+    }
+
+    #[test]
+    fn keeps_whole_lines_from_both_ends() {
+        // 100 lines of 10 bytes each.
+        let text = numbered_lines(100);
+        let out = truncate_with_note(&text, 100, "full output saved to .ouro-outputs/x.txt");
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(&lines[..5], ["line 0001", "line 0002", "line 0003", "line 0004", "line 0005"]);
+        assert_eq!(lines[5], "[truncated 900 bytes; full output saved to .ouro-outputs/x.txt]");
+        assert_eq!(&lines[6..], ["line 0096", "line 0097", "line 0098", "line 0099", "line 0100"]);
+    }
+
+    #[test]
+    fn single_long_line_is_cut_at_char_boundaries() {
+        let text = "é".repeat(100); // 200 bytes
+        let out = truncate_with_note(&text, 51, "note");
+        assert!(out.starts_with(&"é".repeat(12)));
+        assert!(out.contains("[truncated 152 bytes; note]\n"));
+        assert!(out.ends_with(&"é".repeat(12)));
+    }
+
+    #[test]
+    fn output_file_names_are_sanitized() {
+        assert_eq!(output_file_name("call_01", ""), "call_01.txt");
+        assert_eq!(output_file_name("../../etc/passwd", ".stderr"), "______etc_passwd.stderr.txt");
+        assert_eq!(output_file_name("", ""), "call.txt");
+    }
+
+    #[tokio::test]
+    async fn truncate_output_saves_full_text() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let text = numbered_lines(100);
+
+        let out = truncate_output(text.clone(), 100, tmp.path(), "c1.txt").await;
+        assert!(out.contains("[truncated 900 bytes; full output saved to .ouro-outputs/c1.txt]"));
+        let saved = std::fs::read_to_string(tmp.path().join(".ouro-outputs/c1.txt")).unwrap();
+        assert_eq!(saved, text);
+
+        let out = truncate_output(text.clone(), 0, tmp.path(), "c2.txt").await;
+        assert_eq!(out, text);
+        assert!(!tmp.path().join(".ouro-outputs/c2.txt").exists());
+    }
+}
@@ -1,6 +1,8 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::agent::snapshots::SnapshotId;
+
 #[derive(Parser, Debug)]
 #[command(name = "ouro", version, about = "Autonomous AI research harness")]
 pub struct Cli {
@@ -12,7 +14,7 @@ pub struct Cli {
 pub enum Commands {
     /// Start a new agent session
     Run {
-        /// Ollama model name (e.g., "llama3.2", "qwen2.5:7b")
+        /// Model name as known to the provider (e.g., "llama3.2", "qwen2.5:7b")
         #[arg(short, long)]
         model: Option<String>,
 
@@ -37,5 +39,54 @@ pub enum Commands {
         /// Workspace directory to resume from
         #[arg(short, long)]
         workspace: Option<PathBuf>,
+
+        /// Run without TUI (headless mode)
+        #[arg(long)]
+        headless: bool,
+    },
+    /// Replay a session log through the TUI
+    Replay {
+        /// Session log file to replay (defaults to the latest log for the workspace)
+        log: Option<PathBuf>,
+
+        /// Workspace directory whose logs to search
+        #[arg(short, long)]
+        workspace: Option<PathBuf>,
+
+        /// Playback speed multiplier relative to real time
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+
+        /// Start paused and advance one event at a time
+        #[arg(long)]
+        step: bool,
+    },
+    /// Inspect or roll back git snapshots of the workspace
+    Snapshots {
+        #[command(subcommand)]
+        action: SnapshotAction,
+
+        /// Workspace directory whose snapshots to use
+        #[arg(short, long, global = true)]
+        workspace: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotAction {
+    /// List snapshots, oldest first
+    List,
+    /// Show changes since a snapshot (or between two snapshots)
+    Diff {
+        /// Snapshot to diff from, as [<run>/]<session>:<turn>
+        from: SnapshotId,
+
+        /// Snapshot to diff to (defaults to the current workspace)
+        to: Option<SnapshotId>,
+    },
+    /// Roll the workspace back to a snapshot
+    Restore {
+        /// Snapshot to restore, as [<run>/]<session>:<turn>
+        snapshot: SnapshotId,
     },
 }
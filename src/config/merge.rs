@@ -1,24 +1,70 @@
-use super::schema::{AppConfig, PartialConfig};
-use crate::safety::defaults::default_blocklist;
+use super::schema::{AppConfig, ContextLimitSource, PartialConfig, ProviderKind};
+use crate::safety::defaults::{default_blocklist, default_protected_paths};
 use std::path::PathBuf;
 
 impl PartialConfig {
     /// Merge self with a lower-priority fallback.
     /// Self's non-None values take precedence.
-    /// For blocked_patterns: REPLACE semantics (if self has Some, use it entirely).
+    /// For blocked_patterns, protected_paths, allowed_commands, fallback_models,
+    /// mask_policies, and the web domain lists:
+    /// REPLACE semantics (if self has Some, use it entirely).
     pub fn with_fallback(self, fallback: PartialConfig) -> PartialConfig {
         PartialConfig {
             model: self.model.or(fallback.model),
+            fallback_models: self.fallback_models.or(fallback.fallback_models),
             workspace: self.workspace.or(fallback.workspace),
             shell_timeout_secs: self.shell_timeout_secs.or(fallback.shell_timeout_secs),
             context_limit: self.context_limit.or(fallback.context_limit),
             blocked_patterns: self.blocked_patterns.or(fallback.blocked_patterns),
             security_log_path: self.security_log_path.or(fallback.security_log_path),
+            protected_paths: self.protected_paths.or(fallback.protected_paths),
+            command_filter_mode: self.command_filter_mode.or(fallback.command_filter_mode),
+            allowed_commands: self.allowed_commands.or(fallback.allowed_commands),
             soft_threshold_pct: self.soft_threshold_pct.or(fallback.soft_threshold_pct),
             hard_threshold_pct: self.hard_threshold_pct.or(fallback.hard_threshold_pct),
             carryover_turns: self.carryover_turns.or(fallback.carryover_turns),
             max_restarts: self.max_restarts.or(fallback.max_restarts),
             auto_restart: self.auto_restart.or(fallback.auto_restart),
+            context_strategy: self.context_strategy.or(fallback.context_strategy),
+            summarize_turns: self.summarize_turns.or(fallback.summarize_turns),
+            summary_model: self.summary_model.or(fallback.summary_model),
+            mask_policies: self.mask_policies.or(fallback.mask_policies),
+            carryover_mode: self.carryover_mode.or(fallback.carryover_mode),
+            digest_tokens: self.digest_tokens.or(fallback.digest_tokens),
+            web_max_bytes: self.web_max_bytes.or(fallback.web_max_bytes),
+            web_max_redirects: self.web_max_redirects.or(fallback.web_max_redirects),
+            web_timeout_secs: self.web_timeout_secs.or(fallback.web_timeout_secs),
+            web_allowed_domains: self.web_allowed_domains.or(fallback.web_allowed_domains),
+            web_blocked_domains: self.web_blocked_domains.or(fallback.web_blocked_domains),
+            provider_kind: self.provider_kind.or(fallback.provider_kind),
+            provider_base_url: self.provider_base_url.or(fallback.provider_base_url),
+            provider_api_key_env: self.provider_api_key_env.or(fallback.provider_api_key_env),
+            provider_timeout_secs: self.provider_timeout_secs.or(fallback.provider_timeout_secs),
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            seed: self.seed.or(fallback.seed),
+            stop_sequences: self.stop_sequences.or(fallback.stop_sequences),
+            num_ctx: self.num_ctx.or(fallback.num_ctx),
+            retry_max_attempts: self.retry_max_attempts.or(fallback.retry_max_attempts),
+            retry_base_delay_ms: self.retry_base_delay_ms.or(fallback.retry_base_delay_ms),
+            retry_max_delay_ms: self.retry_max_delay_ms.or(fallback.retry_max_delay_ms),
+            retry_jitter: self.retry_jitter.or(fallback.retry_jitter),
+            snapshots_enabled: self.snapshots_enabled.or(fallback.snapshots_enabled),
+            limit_memory_mb: self.limit_memory_mb.or(fallback.limit_memory_mb),
+            limit_cpu_secs: self.limit_cpu_secs.or(fallback.limit_cpu_secs),
+            limit_file_size_mb: self.limit_file_size_mb.or(fallback.limit_file_size_mb),
+            limit_max_processes: self.limit_max_processes.or(fallback.limit_max_processes),
+            limit_max_open_files: self.limit_max_open_files.or(fallback.limit_max_open_files),
+            sandbox_enabled: self.sandbox_enabled.or(fallback.sandbox_enabled),
+            sandbox_network: self.sandbox_network.or(fallback.sandbox_network),
+            output_shell_exec_max_bytes: self
+                .output_shell_exec_max_bytes
+                .or(fallback.output_shell_exec_max_bytes),
+            output_file_read_max_bytes: self
+                .output_file_read_max_bytes
+                .or(fallback.output_file_read_max_bytes),
+            jobs_max_concurrent: self.jobs_max_concurrent.or(fallback.jobs_max_concurrent),
         }
     }
 
@@ -30,19 +76,78 @@ impl PartialConfig {
         let security_log_path = self
             .security_log_path
             .unwrap_or_else(|| workspace.join("security.log"));
+        // Configured protected paths add to the defaults rather than replace
+        // them, so no config can unprotect itself or the security log.
+        let mut protected_paths = default_protected_paths(&workspace, &security_log_path);
+        for path in self.protected_paths.unwrap_or_default() {
+            if !protected_paths.contains(&path) {
+                protected_paths.push(path);
+            }
+        }
+        let provider_kind = self.provider_kind.unwrap_or(ProviderKind::Ollama);
+        let provider_base_url = self
+            .provider_base_url
+            .unwrap_or_else(|| provider_kind.default_base_url().to_string());
+        let context_limit_source = if self.context_limit.is_some() {
+            ContextLimitSource::Config
+        } else {
+            ContextLimitSource::Default
+        };
 
         AppConfig {
             model: self.model.unwrap_or_else(|| "llama3.2".to_string()),
+            fallback_models: self.fallback_models.unwrap_or_default(),
             workspace,
             shell_timeout_secs: self.shell_timeout_secs.unwrap_or(30),
             context_limit: self.context_limit.unwrap_or(32768),
+            context_limit_source,
             blocked_patterns: self.blocked_patterns.unwrap_or_else(default_blocklist),
             security_log_path,
+            protected_paths,
+            command_filter_mode: self.command_filter_mode.unwrap_or_default(),
+            allowed_commands: self.allowed_commands.unwrap_or_default(),
             soft_threshold_pct: self.soft_threshold_pct.unwrap_or(0.70),
             hard_threshold_pct: self.hard_threshold_pct.unwrap_or(0.90),
             carryover_turns: self.carryover_turns.unwrap_or(5),
             max_restarts: self.max_restarts.unwrap_or(None),
             auto_restart: self.auto_restart.unwrap_or(true),
+            context_strategy: self.context_strategy.unwrap_or_default(),
+            summarize_turns: self.summarize_turns.unwrap_or(10).max(1),
+            summary_model: self.summary_model,
+            mask_policies: self.mask_policies.unwrap_or_default(),
+            carryover_mode: self.carryover_mode.unwrap_or_default(),
+            digest_tokens: self.digest_tokens.unwrap_or(1000),
+            web_max_bytes: self.web_max_bytes.unwrap_or(50_000),
+            web_max_redirects: self.web_max_redirects.unwrap_or(5),
+            web_timeout_secs: self.web_timeout_secs.unwrap_or(30),
+            web_allowed_domains: self.web_allowed_domains.unwrap_or_default(),
+            web_blocked_domains: self.web_blocked_domains.unwrap_or_default(),
+            provider_kind,
+            provider_base_url,
+            provider_api_key_env: self.provider_api_key_env,
+            provider_timeout_secs: self.provider_timeout_secs.unwrap_or(300),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            seed: self.seed,
+            stop_sequences: self.stop_sequences.unwrap_or_default(),
+            num_ctx: self.num_ctx,
+            retry_max_attempts: self.retry_max_attempts.unwrap_or(5).max(1),
+            retry_base_delay_ms: self.retry_base_delay_ms.unwrap_or(1000),
+            retry_max_delay_ms: self.retry_max_delay_ms.unwrap_or(30_000),
+            retry_jitter: self.retry_jitter.unwrap_or(0.25).clamp(0.0, 1.0),
+            snapshots_enabled: self.snapshots_enabled.unwrap_or(false),
+            // 0 lifts a limit set by a lower-priority config.
+            limit_memory_mb: self.limit_memory_mb.filter(|&n| n > 0),
+            limit_cpu_secs: self.limit_cpu_secs.filter(|&n| n > 0),
+            limit_file_size_mb: self.limit_file_size_mb.filter(|&n| n > 0),
+            limit_max_processes: self.limit_max_processes.filter(|&n| n > 0),
+            limit_max_open_files: self.limit_max_open_files.filter(|&n| n > 0),
+            sandbox_enabled: self.sandbox_enabled.unwrap_or(false),
+            sandbox_network: self.sandbox_network.unwrap_or(false),
+            output_shell_exec_max_bytes: self.output_shell_exec_max_bytes.unwrap_or(20_000),
+            output_file_read_max_bytes: self.output_file_read_max_bytes.unwrap_or(50_000),
+            jobs_max_concurrent: self.jobs_max_concurrent.unwrap_or(4),
         }
     }
 }
@@ -106,16 +211,16 @@ mod tests {
 
     #[test]
     fn test_blocked_patterns_replace_semantics() {
+        use super::super::schema::BlocklistEntry;
+
         let workspace = PartialConfig {
-            blocked_patterns: Some(vec![
-                ("custom_pattern".to_string(), "custom reason".to_string()),
-            ]),
+            blocked_patterns: Some(vec![BlocklistEntry::program("custom", "custom reason")]),
             ..Default::default()
         };
         let global = PartialConfig {
             blocked_patterns: Some(vec![
-                ("global_pattern_1".to_string(), "global reason 1".to_string()),
-                ("global_pattern_2".to_string(), "global reason 2".to_string()),
+                BlocklistEntry::pattern("global_pattern_1", "global reason 1"),
+                BlocklistEntry::pattern("global_pattern_2", "global reason 2"),
             ]),
             ..Default::default()
         };
@@ -123,7 +228,7 @@ mod tests {
         let merged = workspace.with_fallback(global);
         let patterns = merged.blocked_patterns.unwrap();
         assert_eq!(patterns.len(), 1, "Workspace blocklist should replace global entirely");
-        assert_eq!(patterns[0].0, "custom_pattern");
+        assert_eq!(patterns[0].program.as_deref(), Some("custom"));
     }
 
     #[test]
@@ -247,4 +352,481 @@ mod tests {
         assert_eq!(config.max_restarts, Some(5), "Global max_restarts should apply");
         assert!(!config.auto_restart, "Global auto_restart should apply");
     }
+
+    #[test]
+    fn test_web_config_defaults() {
+        let config = PartialConfig::default().finalize();
+
+        assert_eq!(config.web_max_bytes, 50_000);
+        assert_eq!(config.web_max_redirects, 5);
+        assert_eq!(config.web_timeout_secs, 30);
+        assert!(config.web_allowed_domains.is_empty(), "Empty allowlist means any domain");
+        assert!(config.web_blocked_domains.is_empty());
+    }
+
+    #[test]
+    fn test_web_domain_lists_replace_semantics() {
+        let workspace = PartialConfig {
+            web_blocked_domains: Some(vec!["example.com".to_string()]),
+            ..Default::default()
+        };
+        let global = PartialConfig {
+            web_max_bytes: Some(1000),
+            web_allowed_domains: Some(vec!["docs.rs".to_string()]),
+            web_blocked_domains: Some(vec!["a.test".to_string(), "b.test".to_string()]),
+            ..Default::default()
+        };
+
+        let config = workspace.with_fallback(global).finalize();
+        assert_eq!(config.web_blocked_domains, vec!["example.com"], "Workspace denylist should replace global");
+        assert_eq!(config.web_allowed_domains, vec!["docs.rs"], "Global allowlist should apply");
+        assert_eq!(config.web_max_bytes, 1000);
+    }
+
+    #[test]
+    fn test_provider_defaults_to_local_ollama() {
+        let config = PartialConfig::default().finalize();
+
+        assert_eq!(config.provider_kind, ProviderKind::Ollama);
+        assert_eq!(config.provider_base_url, "http://localhost:11434/");
+        assert_eq!(config.provider_api_key_env, None);
+        assert_eq!(config.provider_timeout_secs, 300);
+    }
+
+    #[test]
+    fn test_provider_base_url_defaults_per_kind() {
+        let partial = PartialConfig {
+            provider_kind: Some(ProviderKind::OpenAi),
+            ..Default::default()
+        };
+        let config = partial.finalize();
+        assert_eq!(config.provider_base_url, "http://localhost:8080/v1/");
+
+        let partial = PartialConfig {
+            provider_kind: Some(ProviderKind::OpenAi),
+            provider_base_url: Some("http://gpu-box:8000/v1".to_string()),
+            ..Default::default()
+        };
+        let config = partial.finalize();
+        assert_eq!(config.provider_base_url, "http://gpu-box:8000/v1", "Explicit base_url should win");
+    }
+
+    #[test]
+    fn test_provider_section_parses_from_toml() {
+        let file: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+            [provider]
+            kind = "openai"
+            base_url = "http://10.0.0.5:8000/v1/"
+            api_key_env = "VLLM_API_KEY"
+            request_timeout_secs = 120
+            "#,
+        )
+        .unwrap();
+        let config = file.to_partial().finalize();
+
+        assert_eq!(config.provider_kind, ProviderKind::OpenAi);
+        assert_eq!(config.provider_base_url, "http://10.0.0.5:8000/v1/");
+        assert_eq!(config.provider_api_key_env.as_deref(), Some("VLLM_API_KEY"));
+        assert_eq!(config.provider_timeout_secs, 120);
+    }
+
+    #[test]
+    fn test_context_limit_source() {
+        let config = PartialConfig::default().finalize();
+        assert_eq!(config.context_limit_source, ContextLimitSource::Default);
+
+        let config = PartialConfig {
+            context_limit: Some(8192),
+            ..Default::default()
+        }
+        .finalize();
+        assert_eq!(config.context_limit_source, ContextLimitSource::Config);
+        assert_eq!(config.num_ctx, None, "Unset num_ctx follows context_limit at request time");
+    }
+
+    #[test]
+    fn test_model_options_parse_and_merge() {
+        let file: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+            [model_options]
+            temperature = 0.3
+            seed = 42
+            stop = ["</answer>"]
+            num_ctx = 16384
+            "#,
+        )
+        .unwrap();
+        let cli = PartialConfig {
+            temperature: Some(0.9),
+            ..Default::default()
+        };
+        let config = cli.with_fallback(file.to_partial()).finalize();
+
+        assert_eq!(config.temperature, Some(0.9), "Higher layer should win");
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.top_p, None);
+        assert_eq!(config.max_tokens, None);
+        assert_eq!(config.stop_sequences, vec!["</answer>"]);
+        assert_eq!(config.num_ctx, Some(16384));
+    }
+
+    #[test]
+    fn test_model_chain_parse_and_merge() {
+        let global: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+            [general]
+            model = ["qwen2.5:32b", "qwen2.5:14b", "qwen2.5:7b"]
+            "#,
+        )
+        .unwrap();
+        let config = global.to_partial().finalize();
+        assert_eq!(config.model, "qwen2.5:32b");
+        assert_eq!(config.fallback_models, vec!["qwen2.5:14b", "qwen2.5:7b"]);
+
+        // A single model in a higher layer replaces the whole chain.
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[general]\nmodel = [\"a\", \"b\"]").unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[general]\nmodel = \"c\"").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert_eq!(config.model, "c");
+        assert!(config.fallback_models.is_empty());
+
+        // The CLI --model only replaces the primary.
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[general]\nmodel = [\"a\", \"b\"]").unwrap();
+        let cli = PartialConfig {
+            model: Some("x".to_string()),
+            ..Default::default()
+        };
+        let config = cli.with_fallback(global.to_partial()).finalize();
+        assert_eq!(config.model, "x");
+        assert_eq!(config.fallback_models, vec!["b"]);
+    }
+
+    #[test]
+    fn test_context_strategy_parse_and_defaults() {
+        use super::super::schema::ContextStrategy;
+
+        let config = PartialConfig::default().finalize();
+        assert_eq!(config.context_strategy, ContextStrategy::Mask);
+        assert_eq!(config.summarize_turns, 10);
+        assert_eq!(config.summary_model, None);
+
+        let file: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+            [context]
+            strategy = "hybrid"
+            summarize_turns = 4
+            summary_model = "qwen2.5:3b"
+            "#,
+        )
+        .unwrap();
+        let config = file.to_partial().finalize();
+        assert_eq!(config.context_strategy, ContextStrategy::Hybrid);
+        assert_eq!(config.summarize_turns, 4);
+        assert_eq!(config.summary_model.as_deref(), Some("qwen2.5:3b"));
+
+        let bad: Result<super::super::schema::ConfigFile, _> =
+            toml::from_str("[context]\nstrategy = \"truncate\"");
+        assert!(bad.is_err(), "Unknown strategies are rejected");
+    }
+
+    #[test]
+    fn test_mask_policies_parse_and_replace() {
+        assert!(PartialConfig::default().finalize().mask_policies.is_empty());
+
+        let global: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+            [context.mask_policies.file_read]
+            never = true
+
+            [context.mask_policies.shell_exec]
+            after_turns = 3
+            keep_tail_lines = 20
+            max_retained_bytes = 2000
+            "#,
+        )
+        .unwrap();
+        let config = global.to_partial().finalize();
+        assert!(config.mask_policies["file_read"].never);
+        let shell = &config.mask_policies["shell_exec"];
+        assert_eq!(shell.after_turns, Some(3));
+        assert_eq!(shell.keep_head_lines, 0);
+        assert_eq!(shell.keep_tail_lines, 20);
+        assert_eq!(shell.max_retained_bytes, Some(2000));
+
+        // A higher layer's policy table replaces the lower one entirely.
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[context.mask_policies.file_read]\nnever = true").unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[context.mask_policies.web_fetch]\nafter_turns = 1").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert_eq!(config.mask_policies.len(), 1);
+        assert!(config.mask_policies.contains_key("web_fetch"));
+    }
+
+    #[test]
+    fn test_carryover_mode_parse_and_defaults() {
+        use super::super::schema::CarryoverMode;
+
+        let defaults = PartialConfig::default().finalize();
+        assert_eq!(defaults.carryover_mode, CarryoverMode::Turns);
+        assert_eq!(defaults.digest_tokens, 1000);
+
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[context]\ncarryover = \"both\"\ndigest_tokens = 0").unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[context]\ncarryover = \"handoff\"").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert_eq!(config.carryover_mode, CarryoverMode::Handoff);
+        assert_eq!(config.digest_tokens, 0);
+
+        let only_global: super::super::schema::ConfigFile =
+            toml::from_str("[context]\ncarryover = \"both\"").unwrap();
+        assert_eq!(only_global.to_partial().finalize().carryover_mode, CarryoverMode::Both);
+
+        let bad: Result<super::super::schema::ConfigFile, _> =
+            toml::from_str("[context]\ncarryover = \"summary\"");
+        assert!(bad.is_err(), "Unknown carryover modes are rejected");
+    }
+
+    #[test]
+    fn test_protected_paths_extend_defaults() {
+        let defaults = PartialConfig::default().finalize();
+        assert_eq!(defaults.protected_paths, vec!["ouro.toml", "security.log"]);
+
+        let global = || -> super::super::schema::ConfigFile {
+            toml::from_str("[safety]\nprotected_paths = [\"ouro.toml\", \"archive/**\"]").unwrap()
+        };
+        assert_eq!(
+            global().to_partial().finalize().protected_paths,
+            vec!["ouro.toml", "security.log", "archive/**"]
+        );
+
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[safety]\nprotected_paths = []").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global().to_partial())
+            .finalize();
+        assert_eq!(
+            config.protected_paths,
+            vec!["ouro.toml", "security.log"],
+            "An empty list replaces the fallback but keeps the defaults"
+        );
+
+        let nested_log = PartialConfig {
+            security_log_path: Some(PathBuf::from("./workspace/logs/security.log")),
+            ..Default::default()
+        };
+        assert_eq!(
+            nested_log.finalize().protected_paths,
+            vec!["ouro.toml", "logs/security.log"]
+        );
+
+        let outside_log = PartialConfig {
+            security_log_path: Some(PathBuf::from("/var/log/ouro.log")),
+            ..Default::default()
+        };
+        assert_eq!(outside_log.finalize().protected_paths, vec!["ouro.toml"]);
+    }
+
+    #[test]
+    fn test_allowlist_mode_parse_and_defaults() {
+        use super::super::schema::CommandFilterMode;
+
+        let defaults = PartialConfig::default().finalize();
+        assert_eq!(defaults.command_filter_mode, CommandFilterMode::Blocklist);
+        assert!(defaults.allowed_commands.is_empty());
+
+        let file: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+[safety]
+mode = "allowlist"
+
+[[safety.allowed_commands]]
+program = "ls"
+
+[[safety.allowed_commands]]
+program = "python3"
+args = '[\w./-]+\.py( .*)?'
+"#,
+        )
+        .unwrap();
+        let config = file.to_partial().finalize();
+        assert_eq!(config.command_filter_mode, CommandFilterMode::Allowlist);
+        assert_eq!(
+            config.allowed_commands,
+            vec![
+                ("ls".to_string(), None),
+                ("python3".to_string(), Some(r"[\w./-]+\.py( .*)?".to_string())),
+            ]
+        );
+
+        let bad: Result<super::super::schema::ConfigFile, _> =
+            toml::from_str("[safety]\nmode = \"denylist\"");
+        assert!(bad.is_err(), "Unknown filter modes are rejected");
+    }
+
+    #[test]
+    fn test_blocklist_rules_parse() {
+        use super::super::schema::BlocklistEntry;
+
+        let file: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+[[safety.blocked_patterns]]
+pattern = 'curl.*\|\s*sh'
+reason = "Piping downloads to a shell not allowed"
+
+[[safety.blocked_patterns]]
+program = "git"
+args = '^--force$'
+reason = "Force pushes not allowed"
+
+[[safety.blocked_patterns]]
+redirect = '^/dev/sd'
+reason = "Direct device writes not allowed"
+"#,
+        )
+        .unwrap();
+        let config = file.to_partial().finalize();
+        assert_eq!(
+            config.blocked_patterns,
+            vec![
+                BlocklistEntry::pattern(r"curl.*\|\s*sh", "Piping downloads to a shell not allowed"),
+                BlocklistEntry::program("git", "Force pushes not allowed").with_args("^--force$"),
+                BlocklistEntry::redirect("^/dev/sd", "Direct device writes not allowed"),
+            ]
+        );
+
+        let typo: super::super::schema::ConfigFile =
+            toml::from_str("[[safety.blocked_patterns]]\nprogramme = \"git\"\nreason = \"x\"").unwrap();
+        let rules = typo.to_partial().finalize().blocked_patterns;
+        assert!(
+            crate::safety::command_filter::CommandFilter::from_rules(&rules).is_err(),
+            "a rule left without anything to match is rejected"
+        );
+    }
+
+    #[test]
+    fn test_limits_parse_merge_and_zero_lifts() {
+        let defaults = PartialConfig::default().finalize();
+        assert_eq!(defaults.limit_memory_mb, None);
+        assert_eq!(defaults.limit_cpu_secs, None);
+
+        let global: super::super::schema::ConfigFile = toml::from_str(
+            "[limits]\nmemory_mb = 2048\ncpu_secs = 300\nfile_size_mb = 100\nmax_processes = 256",
+        )
+        .unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[limits]\nmemory_mb = 0\nmax_open_files = 1024").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert_eq!(config.limit_memory_mb, None, "0 lifts the global limit");
+        assert_eq!(config.limit_cpu_secs, Some(300));
+        assert_eq!(config.limit_file_size_mb, Some(100));
+        assert_eq!(config.limit_max_processes, Some(256));
+        assert_eq!(config.limit_max_open_files, Some(1024));
+    }
+
+    #[test]
+    fn test_sandbox_parse_and_defaults() {
+        let defaults = PartialConfig::default().finalize();
+        assert!(!defaults.sandbox_enabled);
+        assert!(!defaults.sandbox_network);
+
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[sandbox]\nenabled = true").unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[sandbox]\nnetwork = true").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert!(config.sandbox_enabled);
+        assert!(config.sandbox_network);
+    }
+
+    #[test]
+    fn test_output_limits_parse_and_defaults() {
+        let defaults = PartialConfig::default().finalize();
+        assert_eq!(defaults.output_shell_exec_max_bytes, 20_000);
+        assert_eq!(defaults.output_file_read_max_bytes, 50_000);
+
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[output]\nshell_exec_max_bytes = 4000\nfile_read_max_bytes = 8000").unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[output]\nfile_read_max_bytes = 0").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert_eq!(config.output_shell_exec_max_bytes, 4000);
+        assert_eq!(config.output_file_read_max_bytes, 0);
+    }
+
+    #[test]
+    fn test_jobs_parse_and_defaults() {
+        assert_eq!(PartialConfig::default().finalize().jobs_max_concurrent, 4);
+
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[jobs]\nmax_concurrent = 1").unwrap();
+        assert_eq!(global.to_partial().finalize().jobs_max_concurrent, 1);
+    }
+
+    #[test]
+    fn test_snapshots_parse_and_defaults() {
+        assert!(!PartialConfig::default().finalize().snapshots_enabled);
+
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[snapshots]\nenabled = true").unwrap();
+        assert!(global.to_partial().finalize().snapshots_enabled);
+
+        let global: super::super::schema::ConfigFile =
+            toml::from_str("[snapshots]\nenabled = true").unwrap();
+        let workspace: super::super::schema::ConfigFile =
+            toml::from_str("[snapshots]\nenabled = false").unwrap();
+        let config = workspace
+            .to_partial()
+            .with_fallback(global.to_partial())
+            .finalize();
+        assert!(!config.snapshots_enabled);
+    }
+
+    #[test]
+    fn test_retry_parse_and_defaults() {
+        let config = PartialConfig::default().finalize();
+        assert_eq!(config.retry_max_attempts, 5);
+        assert_eq!(config.retry_base_delay_ms, 1000);
+        assert_eq!(config.retry_max_delay_ms, 30_000);
+        assert_eq!(config.retry_jitter, 0.25);
+
+        let file: super::super::schema::ConfigFile = toml::from_str(
+            r#"
+            [retry]
+            max_attempts = 0
+            base_delay_ms = 250
+            jitter = 3.0
+            "#,
+        )
+        .unwrap();
+        let config = file.to_partial().finalize();
+        assert_eq!(config.retry_max_attempts, 1, "At least one attempt is always made");
+        assert_eq!(config.retry_base_delay_ms, 250);
+        assert_eq!(config.retry_max_delay_ms, 30_000);
+        assert_eq!(config.retry_jitter, 1.0, "Jitter is clamped to a fraction");
+    }
 }
@@ -96,7 +96,9 @@ fn global_config_path() -> Option<std::path::PathBuf> {
 fn cli_workspace(cli: &Cli) -> Option<std::path::PathBuf> {
     match &cli.command {
         Commands::Run { workspace, .. } => workspace.clone(),
-        Commands::Resume { workspace } => workspace.clone(),
+        Commands::Resume { workspace, .. } => workspace.clone(),
+        Commands::Replay { workspace, .. } => workspace.clone(),
+        Commands::Snapshots { workspace, .. } => workspace.clone(),
     }
 }
 
@@ -115,9 +117,30 @@ fn cli_to_partial(cli: &Cli) -> PartialConfig {
             shell_timeout_secs: *timeout,
             ..Default::default()
         },
-        Commands::Resume { workspace } => PartialConfig {
+        Commands::Resume { workspace, .. }
+        | Commands::Replay { workspace, .. }
+        | Commands::Snapshots { workspace, .. } => PartialConfig {
             workspace: workspace.clone(),
             ..Default::default()
         },
     }
 }
+
+/// A config for tests: the given workspace, no protected paths, and a
+/// single model request attempt without backoff. Set anything else with
+/// struct update syntax: `AppConfig { shell_timeout_secs: 5, ..test_config(ws) }`.
+#[cfg(test)]
+pub fn test_config(workspace: &Path) -> AppConfig {
+    AppConfig {
+        protected_paths: Vec::new(),
+        ..PartialConfig {
+            model: Some("test-model".to_string()),
+            workspace: Some(workspace.to_path_buf()),
+            retry_max_attempts: Some(1),
+            retry_base_delay_ms: Some(0),
+            retry_max_delay_ms: Some(0),
+            ..Default::default()
+        }
+        .finalize()
+    }
+}
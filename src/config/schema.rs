@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 /// The TOML file structure for ouro.toml.
@@ -7,14 +8,32 @@ pub struct ConfigFile {
     pub general: Option<GeneralConfig>,
     pub safety: Option<SafetyConfig>,
     pub context: Option<ContextConfig>,
+    pub web: Option<WebConfig>,
+    pub provider: Option<ProviderConfig>,
+    pub model_options: Option<ModelOptionsConfig>,
+    pub retry: Option<RetryConfig>,
+    pub snapshots: Option<SnapshotsConfig>,
+    pub limits: Option<LimitsConfig>,
+    pub sandbox: Option<SandboxConfig>,
+    pub output: Option<OutputConfig>,
+    pub jobs: Option<JobsConfig>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GeneralConfig {
-    pub model: Option<String>,
+    pub model: Option<ModelChain>,
     pub workspace: Option<String>,
 }
 
+/// `model` is either a single model name or an ordered fallback chain.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ModelChain {
+    Single(String),
+    /// Primary model first, then fallbacks in the order they are tried.
+    Chain(Vec<String>),
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SafetyConfig {
     pub shell_timeout_secs: Option<u64>,
@@ -22,14 +41,103 @@ pub struct SafetyConfig {
     /// If specified, fully replaces the default blocklist.
     pub blocked_patterns: Option<Vec<BlocklistEntry>>,
     pub security_log: Option<String>,
+    /// Workspace globs the agent may read but not modify, in addition to
+    /// `ouro.toml` and the security log, which are always protected.
+    pub protected_paths: Option<Vec<String>>,
+    /// Whether commands need only avoid the blocklist, or must also be
+    /// built from `allowed_commands`.
+    pub mode: Option<CommandFilterMode>,
+    pub allowed_commands: Option<Vec<AllowedCommandEntry>>,
+}
+
+/// How shell commands are filtered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandFilterMode {
+    /// Anything not matching `blocked_patterns` may run.
+    #[default]
+    Blocklist,
+    /// Every program a command runs must be in `allowed_commands` (and the
+    /// command must still pass the blocklist).
+    Allowlist,
 }
 
+/// A program permitted in allowlist mode, e.g.
+/// `{ program = "python3", args = '[\w./-]+\.py( .*)?' }`.
 #[derive(Debug, Clone, Deserialize)]
+pub struct AllowedCommandEntry {
+    pub program: String,
+    /// Regex the program's arguments (joined with spaces) must match in
+    /// full; it is anchored at both ends. Unset allows any arguments.
+    pub args: Option<String>,
+}
+
+/// A blocklist rule. Either a `pattern` matched against the raw command
+/// string, or any combination of `program`, `args`, and `redirect`, which are
+/// matched against each simple command the shell would run -- including
+/// those inside pipelines, substitutions, `sh -c`, `eval`, and wrappers such
+/// as `env` or `xargs`. All fields set on such a rule must match the same
+/// command, e.g. `{ program = "rm", args = '^/$', reason = "..." }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
 pub struct BlocklistEntry {
-    pub pattern: String,
+    /// Regex matched anywhere in the raw command string.
+    pub pattern: Option<String>,
+    /// Regex the program name must match in full, case-insensitively.
+    /// Paths are reduced to their last component: `/usr/bin/sudo` is `sudo`.
+    pub program: Option<String>,
+    /// Regex at least one argument must match.
+    pub args: Option<String>,
+    /// Regex at least one output redirection target (`>`, `>>`, `>|`, `<>`)
+    /// must match.
+    pub redirect: Option<String>,
     pub reason: String,
 }
 
+impl BlocklistEntry {
+    /// A rule matching the raw command string against `pattern`.
+    pub fn pattern(pattern: &str, reason: &str) -> Self {
+        Self {
+            pattern: Some(pattern.into()),
+            reason: reason.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A rule matching commands that run `program`.
+    pub fn program(program: &str, reason: &str) -> Self {
+        Self {
+            program: Some(program.into()),
+            reason: reason.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A rule matching commands with an argument matching `args`, whatever
+    /// the program.
+    pub fn args(args: &str, reason: &str) -> Self {
+        Self {
+            args: Some(args.into()),
+            reason: reason.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A rule matching commands that write to a file matching `redirect`.
+    pub fn redirect(redirect: &str, reason: &str) -> Self {
+        Self {
+            redirect: Some(redirect.into()),
+            reason: reason.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Also require an argument matching `args`.
+    pub fn with_args(mut self, args: &str) -> Self {
+        self.args = Some(args.into());
+        self
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ContextConfig {
     pub soft_threshold_pct: Option<f64>,
@@ -37,22 +145,265 @@ pub struct ContextConfig {
     pub carryover_turns: Option<usize>,
     pub max_restarts: Option<u32>,
     pub auto_restart: Option<bool>,
+    /// How to reclaim context at the soft threshold.
+    pub strategy: Option<ContextStrategy>,
+    /// Number of oldest turns folded into one summary per summarization round.
+    pub summarize_turns: Option<usize>,
+    /// Model that writes summaries. Defaults to the session's active model.
+    pub summary_model: Option<String>,
+    /// Masking rules keyed by tool name, e.g. `[context.mask_policies.file_read]`.
+    pub mask_policies: Option<HashMap<String, MaskPolicy>>,
+    /// What a restarted session inherits from the previous one.
+    pub carryover: Option<CarryoverMode>,
+    /// Token budget of the previous-session digest in a restarted session's
+    /// system prompt. 0 disables the digest.
+    pub digest_tokens: Option<usize>,
+}
+
+/// What a restarted session inherits from the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CarryoverMode {
+    /// The last `carryover_turns` turns, verbatim.
+    #[default]
+    Turns,
+    /// A structured handoff written at wind-down (HANDOFF.md), injected into
+    /// the next system prompt. Falls back to turns if no handoff was written.
+    Handoff,
+    /// Both the handoff and the last turns.
+    Both,
+}
+
+/// How observations from one tool are masked. Tools without a policy are
+/// masked whole, in priority order.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MaskPolicy {
+    /// Never mask this tool's output.
+    #[serde(default)]
+    pub never: bool,
+    /// Only mask output at least this many turns old.
+    pub after_turns: Option<u64>,
+    /// Lines kept from the start of the output when it is masked.
+    #[serde(default)]
+    pub keep_head_lines: usize,
+    /// Lines kept from the end of the output when it is masked.
+    #[serde(default)]
+    pub keep_tail_lines: usize,
+    /// Cap on the output kept when masked. Without head/tail lines, the
+    /// first `max_retained_bytes` bytes are kept.
+    pub max_retained_bytes: Option<usize>,
+}
+
+/// Context compression strategy applied at the soft threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextStrategy {
+    /// Replace old tool outputs with short placeholders.
+    #[default]
+    Mask,
+    /// Have a model summarize the oldest turns into one system message.
+    Summarize,
+    /// Mask first; summarize once there is nothing left to mask.
+    Hybrid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebConfig {
+    /// Maximum bytes of extracted text returned by `web_fetch`.
+    pub max_bytes: Option<usize>,
+    pub max_redirects: Option<usize>,
+    pub timeout_secs: Option<u64>,
+    /// If non-empty, only these domains (and their subdomains) may be fetched.
+    pub allowed_domains: Option<Vec<String>>,
+    /// Domains (and their subdomains) that may never be fetched.
+    pub blocked_domains: Option<Vec<String>>,
+}
+
+/// Which API the LLM server speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ProviderKind {
+    /// Ollama's native API.
+    #[serde(rename = "ollama")]
+    Ollama,
+    /// Any OpenAI-compatible server (llama.cpp, vLLM, LM Studio, ...).
+    #[serde(rename = "openai", alias = "openai_compatible")]
+    OpenAi,
+}
+
+impl ProviderKind {
+    /// Base URL used when `base_url` is not configured.
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            ProviderKind::Ollama => "http://localhost:11434/",
+            ProviderKind::OpenAi => "http://localhost:8080/v1/",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: Option<ProviderKind>,
+    pub base_url: Option<String>,
+    /// Name of the environment variable holding the API key (never the key itself).
+    pub api_key_env: Option<String>,
+    pub request_timeout_secs: Option<u64>,
+}
+
+/// Sampling options sent with every chat request. Unset fields use the
+/// server's defaults.
+#[derive(Debug, Deserialize)]
+pub struct ModelOptionsConfig {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<u64>,
+    pub stop: Option<Vec<String>>,
+    /// Ollama context window. Defaults to `context_limit`; 0 keeps the
+    /// server's own default.
+    pub num_ctx: Option<u32>,
+}
+
+/// Retry policy for failed LLM requests. Only transient failures (connection
+/// errors, 5xx, model still loading, truncated streams) are retried.
+#[derive(Debug, Deserialize)]
+pub struct RetryConfig {
+    /// Total attempts per request, including the first. 1 disables retries.
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry; doubles on each further retry.
+    pub base_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+    /// Random spread applied to each delay, as a fraction (0.0 - 1.0).
+    pub jitter: Option<f64>,
+}
+
+/// Git snapshots of the workspace, taken after turns that may have changed it.
+#[derive(Debug, Deserialize)]
+pub struct SnapshotsConfig {
+    pub enabled: Option<bool>,
+}
+
+/// Linux namespace sandbox for shell commands: read-only filesystem except
+/// the workspace, private /tmp, and no network unless `network` is set.
+#[derive(Debug, Deserialize)]
+pub struct SandboxConfig {
+    pub enabled: Option<bool>,
+    /// Keep network access inside the sandbox.
+    pub network: Option<bool>,
+}
+
+/// Resource limits for shell commands. Unset or 0 means no limit.
+#[derive(Debug, Deserialize)]
+pub struct LimitsConfig {
+    /// Address space per process.
+    pub memory_mb: Option<u64>,
+    /// CPU time per process.
+    pub cpu_secs: Option<u64>,
+    /// Largest file a command may write.
+    pub file_size_mb: Option<u64>,
+    /// Processes the user may have, counting the harness's own.
+    pub max_processes: Option<u64>,
+    /// Open file descriptors per process.
+    pub max_open_files: Option<u64>,
+}
+
+/// Largest tool results kept whole in context. Longer output keeps its head
+/// and tail, and the full output is saved under `.ouro-outputs/` in the
+/// workspace. 0 disables truncation for that tool.
+#[derive(Debug, Deserialize)]
+pub struct OutputConfig {
+    /// Per stream (stdout and stderr are truncated separately).
+    pub shell_exec_max_bytes: Option<usize>,
+    pub file_read_max_bytes: Option<usize>,
+}
+
+/// Background jobs started with `job_start`.
+#[derive(Debug, Deserialize)]
+pub struct JobsConfig {
+    /// Jobs that may run at once. 0 disables `job_start`.
+    pub max_concurrent: Option<usize>,
+}
+
+/// Where the effective `context_limit` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextLimitSource {
+    /// Built-in default; no layer set `context_limit`.
+    Default,
+    /// Set explicitly in a config file.
+    Config,
+    /// Detected from the model's metadata (lower than the default or the
+    /// configured value).
+    Model,
+}
+
+impl ContextLimitSource {
+    /// Name recorded in the `session_start` log entry.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContextLimitSource::Default => "default",
+            ContextLimitSource::Config => "config",
+            ContextLimitSource::Model => "model",
+        }
+    }
 }
 
 /// Fully-resolved runtime configuration. All fields have values.
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub model: String,
+    /// Models tried in order when `model` is unavailable or keeps failing.
+    pub fallback_models: Vec<String>,
     pub workspace: PathBuf,
     pub shell_timeout_secs: u64,
     pub context_limit: usize,
-    pub blocked_patterns: Vec<(String, String)>,
+    pub context_limit_source: ContextLimitSource,
+    pub blocked_patterns: Vec<BlocklistEntry>,
     pub security_log_path: PathBuf,
+    pub protected_paths: Vec<String>,
+    pub command_filter_mode: CommandFilterMode,
+    pub allowed_commands: Vec<(String, Option<String>)>,
     pub soft_threshold_pct: f64,
     pub hard_threshold_pct: f64,
     pub carryover_turns: usize,
     pub max_restarts: Option<u32>,
     pub auto_restart: bool,
+    pub context_strategy: ContextStrategy,
+    pub summarize_turns: usize,
+    pub summary_model: Option<String>,
+    pub mask_policies: HashMap<String, MaskPolicy>,
+    pub carryover_mode: CarryoverMode,
+    pub digest_tokens: usize,
+    pub web_max_bytes: usize,
+    pub web_max_redirects: usize,
+    pub web_timeout_secs: u64,
+    pub web_allowed_domains: Vec<String>,
+    pub web_blocked_domains: Vec<String>,
+    pub provider_kind: ProviderKind,
+    pub provider_base_url: String,
+    pub provider_api_key_env: Option<String>,
+    pub provider_timeout_secs: u64,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<u64>,
+    pub stop_sequences: Vec<String>,
+    /// Context window requested from Ollama: `None` follows `context_limit`,
+    /// `Some(0)` keeps the server's default.
+    pub num_ctx: Option<u32>,
+    pub retry_max_attempts: u32,
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    pub retry_jitter: f64,
+    pub snapshots_enabled: bool,
+    pub limit_memory_mb: Option<u64>,
+    pub limit_cpu_secs: Option<u64>,
+    pub limit_file_size_mb: Option<u64>,
+    pub limit_max_processes: Option<u64>,
+    pub limit_max_open_files: Option<u64>,
+    pub sandbox_enabled: bool,
+    pub sandbox_network: bool,
+    pub output_shell_exec_max_bytes: usize,
+    pub output_file_read_max_bytes: usize,
+    pub jobs_max_concurrent: usize,
 }
 
 /// Partial config used during merge. All fields are Option so that
@@ -60,16 +411,56 @@ pub struct AppConfig {
 #[derive(Debug, Clone, Default)]
 pub struct PartialConfig {
     pub model: Option<String>,
+    pub fallback_models: Option<Vec<String>>,
     pub workspace: Option<PathBuf>,
     pub shell_timeout_secs: Option<u64>,
     pub context_limit: Option<usize>,
-    pub blocked_patterns: Option<Vec<(String, String)>>,
+    pub blocked_patterns: Option<Vec<BlocklistEntry>>,
     pub security_log_path: Option<PathBuf>,
+    pub protected_paths: Option<Vec<String>>,
+    pub command_filter_mode: Option<CommandFilterMode>,
+    pub allowed_commands: Option<Vec<(String, Option<String>)>>,
     pub soft_threshold_pct: Option<f64>,
     pub hard_threshold_pct: Option<f64>,
     pub carryover_turns: Option<usize>,
     pub max_restarts: Option<Option<u32>>,
     pub auto_restart: Option<bool>,
+    pub context_strategy: Option<ContextStrategy>,
+    pub summarize_turns: Option<usize>,
+    pub summary_model: Option<String>,
+    pub mask_policies: Option<HashMap<String, MaskPolicy>>,
+    pub carryover_mode: Option<CarryoverMode>,
+    pub digest_tokens: Option<usize>,
+    pub web_max_bytes: Option<usize>,
+    pub web_max_redirects: Option<usize>,
+    pub web_timeout_secs: Option<u64>,
+    pub web_allowed_domains: Option<Vec<String>>,
+    pub web_blocked_domains: Option<Vec<String>>,
+    pub provider_kind: Option<ProviderKind>,
+    pub provider_base_url: Option<String>,
+    pub provider_api_key_env: Option<String>,
+    pub provider_timeout_secs: Option<u64>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<u64>,
+    pub stop_sequences: Option<Vec<String>>,
+    pub num_ctx: Option<u32>,
+    pub retry_max_attempts: Option<u32>,
+    pub retry_base_delay_ms: Option<u64>,
+    pub retry_max_delay_ms: Option<u64>,
+    pub retry_jitter: Option<f64>,
+    pub snapshots_enabled: Option<bool>,
+    pub limit_memory_mb: Option<u64>,
+    pub limit_cpu_secs: Option<u64>,
+    pub limit_file_size_mb: Option<u64>,
+    pub limit_max_processes: Option<u64>,
+    pub limit_max_open_files: Option<u64>,
+    pub sandbox_enabled: Option<bool>,
+    pub sandbox_network: Option<bool>,
+    pub output_shell_exec_max_bytes: Option<usize>,
+    pub output_file_read_max_bytes: Option<usize>,
+    pub jobs_max_concurrent: Option<usize>,
 }
 
 impl ConfigFile {
@@ -78,20 +469,34 @@ impl ConfigFile {
         let mut partial = PartialConfig::default();
 
         if let Some(general) = self.general {
-            partial.model = general.model;
+            match general.model {
+                Some(ModelChain::Single(model)) => {
+                    partial.model = Some(model);
+                    partial.fallback_models = Some(Vec::new());
+                }
+                Some(ModelChain::Chain(models)) => {
+                    let mut models = models.into_iter();
+                    partial.model = models.next();
+                    partial.fallback_models = Some(models.collect());
+                }
+                None => {}
+            }
             partial.workspace = general.workspace.map(PathBuf::from);
         }
 
         if let Some(safety) = self.safety {
             partial.shell_timeout_secs = safety.shell_timeout_secs;
             partial.context_limit = safety.context_limit;
-            partial.blocked_patterns = safety.blocked_patterns.map(|entries| {
+            partial.blocked_patterns = safety.blocked_patterns;
+            partial.security_log_path = safety.security_log.map(PathBuf::from);
+            partial.protected_paths = safety.protected_paths;
+            partial.command_filter_mode = safety.mode;
+            partial.allowed_commands = safety.allowed_commands.map(|entries| {
                 entries
                     .into_iter()
-                    .map(|e| (e.pattern, e.reason))
+                    .map(|e| (e.program, e.args))
                     .collect()
             });
-            partial.security_log_path = safety.security_log.map(PathBuf::from);
         }
 
         if let Some(context) = self.context {
@@ -100,6 +505,69 @@ impl ConfigFile {
             partial.carryover_turns = context.carryover_turns;
             partial.max_restarts = context.max_restarts.map(Some);
             partial.auto_restart = context.auto_restart;
+            partial.context_strategy = context.strategy;
+            partial.summarize_turns = context.summarize_turns;
+            partial.summary_model = context.summary_model;
+            partial.mask_policies = context.mask_policies;
+            partial.carryover_mode = context.carryover;
+            partial.digest_tokens = context.digest_tokens;
+        }
+
+        if let Some(web) = self.web {
+            partial.web_max_bytes = web.max_bytes;
+            partial.web_max_redirects = web.max_redirects;
+            partial.web_timeout_secs = web.timeout_secs;
+            partial.web_allowed_domains = web.allowed_domains;
+            partial.web_blocked_domains = web.blocked_domains;
+        }
+
+        if let Some(provider) = self.provider {
+            partial.provider_kind = provider.kind;
+            partial.provider_base_url = provider.base_url;
+            partial.provider_api_key_env = provider.api_key_env;
+            partial.provider_timeout_secs = provider.request_timeout_secs;
+        }
+
+        if let Some(options) = self.model_options {
+            partial.temperature = options.temperature;
+            partial.top_p = options.top_p;
+            partial.max_tokens = options.max_tokens;
+            partial.seed = options.seed;
+            partial.stop_sequences = options.stop;
+            partial.num_ctx = options.num_ctx;
+        }
+
+        if let Some(retry) = self.retry {
+            partial.retry_max_attempts = retry.max_attempts;
+            partial.retry_base_delay_ms = retry.base_delay_ms;
+            partial.retry_max_delay_ms = retry.max_delay_ms;
+            partial.retry_jitter = retry.jitter;
+        }
+
+        if let Some(snapshots) = self.snapshots {
+            partial.snapshots_enabled = snapshots.enabled;
+        }
+
+        if let Some(limits) = self.limits {
+            partial.limit_memory_mb = limits.memory_mb;
+            partial.limit_cpu_secs = limits.cpu_secs;
+            partial.limit_file_size_mb = limits.file_size_mb;
+            partial.limit_max_processes = limits.max_processes;
+            partial.limit_max_open_files = limits.max_open_files;
+        }
+
+        if let Some(sandbox) = self.sandbox {
+            partial.sandbox_enabled = sandbox.enabled;
+            partial.sandbox_network = sandbox.network;
+        }
+
+        if let Some(output) = self.output {
+            partial.output_shell_exec_max_bytes = output.shell_exec_max_bytes;
+            partial.output_file_read_max_bytes = output.file_read_max_bytes;
+        }
+
+        if let Some(jobs) = self.jobs {
+            partial.jobs_max_concurrent = jobs.max_concurrent;
         }
 
         partial
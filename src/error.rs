@@ -42,10 +42,10 @@ pub enum ExecError {
 /// Errors related to the agent loop and its subsystems.
 #[derive(Debug, thiserror::Error)]
 pub enum AgentError {
-    #[error("Ollama not reachable at {url}: {message}")]
-    OllamaUnavailable { url: String, message: String },
+    #[error("LLM provider not reachable at {url}: {message}")]
+    ProviderUnavailable { url: String, message: String },
 
-    #[error("Model '{model}' not available in Ollama: {message}")]
+    #[error("Model '{model}' not available from provider: {message}")]
     ModelNotAvailable { model: String, message: String },
 
     #[error("System prompt not found at {path}")]
@@ -0,0 +1,342 @@
+//! Background jobs: shell commands that outlive a single tool call.
+//!
+//! `shell_exec` commands must finish within `shell_timeout_secs`, which rules
+//! out training runs and local servers. A job is started the same way -- `sh
+//! -c` in its own process group, with the configured resource limits and
+//! sandbox (see [`spawn_shell`]) -- but has no timeout. Its combined stdout
+//! and stderr are kept in a bounded buffer the agent can poll.
+//!
+//! A [`JobRegistry`] belongs to one session. It caps how many jobs run at
+//! once, and dropping it kills every job's process group, so no job outlives
+//! the session that started it.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::sys::signal::{kill, killpg};
+use nix::unistd::Pid;
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::watch;
+
+use super::sandbox::Sandbox;
+use super::shell::{kill_process_group, killed_by, spawn_shell, ResourceLimits};
+
+/// Output kept per job: at least the last this many bytes of combined stdout
+/// and stderr. Older output is dropped.
+pub const OUTPUT_TAIL_BYTES: usize = 64 * 1024;
+
+/// How long [`JobRegistry::kill`] waits for a killed job to be reaped.
+const KILL_WAIT: Duration = Duration::from_secs(5);
+
+/// Runs once, on a blocking thread, when a job's shell exits. A returned
+/// note is appended to the job's output.
+pub type ExitHook = Box<dyn FnOnce() -> Option<String> + Send>;
+
+/// A job as reported to the agent.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub command: String,
+    /// "running", "exited", or "killed".
+    pub state: &'static str,
+    /// Exit code once the job has exited normally.
+    pub exit_code: Option<i32>,
+    /// What stopped the job: "job_kill", or the resource limit it hit.
+    pub killed_by: Option<String>,
+    pub runtime_secs: u64,
+}
+
+/// The background jobs of one session.
+pub struct JobRegistry {
+    inner: Mutex<Registry>,
+    max_running: usize,
+}
+
+#[derive(Default)]
+struct Registry {
+    jobs: Vec<Job>,
+    next_id: u32,
+}
+
+struct Job {
+    id: String,
+    command: String,
+    /// PID of the shell, which leads the job's process group.
+    pid: u32,
+    started: Instant,
+    state: Arc<Mutex<JobState>>,
+    /// Becomes true once the shell has been reaped.
+    done: watch::Receiver<bool>,
+}
+
+/// Everything the background tasks of a job update.
+#[derive(Default)]
+struct JobState {
+    output: String,
+    kill_requested: bool,
+    exit: Option<JobExit>,
+}
+
+struct JobExit {
+    exit_code: Option<i32>,
+    killed_by: Option<String>,
+    runtime: Duration,
+}
+
+impl JobRegistry {
+    /// An empty registry allowing `max_running` jobs at once.
+    pub fn new(max_running: usize) -> Self {
+        Self {
+            inner: Mutex::new(Registry::default()),
+            max_running,
+        }
+    }
+
+    /// Start `command` as a background job in `working_dir`, with `limits`
+    /// applied and inside `sandbox` if given. `on_exit` runs once the shell
+    /// exits. Must be called from within a Tokio runtime.
+    ///
+    /// Fails if `max_running` jobs are already running or the shell cannot
+    /// be spawned.
+    pub fn start(
+        &self,
+        command: &str,
+        working_dir: &Path,
+        limits: &ResourceLimits,
+        sandbox: Option<&Sandbox>,
+        on_exit: Option<ExitHook>,
+    ) -> anyhow::Result<JobStatus> {
+        let mut registry = self.inner.lock().unwrap();
+        let running = registry.jobs.iter().filter(|job| job.is_running()).count();
+        if running >= self.max_running {
+            anyhow::bail!(
+                "{running} jobs are already running (the limit is {}); kill or wait for one first",
+                self.max_running
+            );
+        }
+
+        let mut child = spawn_shell(command, working_dir, limits, sandbox)?;
+        let pid = child
+            .id()
+            .ok_or_else(|| anyhow::anyhow!("Child process has no PID"))?;
+        let stdout = child.stdout.take().expect("stdout piped");
+        let stderr = child.stderr.take().expect("stderr piped");
+
+        let state = Arc::new(Mutex::new(JobState::default()));
+        let stdout_task = tokio::spawn(collect_output(stdout, state.clone()));
+        let stderr_task = tokio::spawn(collect_output(stderr, state.clone()));
+
+        let (done_tx, done) = watch::channel(false);
+        let started = Instant::now();
+        let limits = *limits;
+        let monitor_state = state.clone();
+        tokio::spawn(async move {
+            let status = child.wait().await;
+            // The readers finish once the pipes close, unless the command
+            // left a process running in the background that still holds them.
+            let _ = tokio::time::timeout(Duration::from_millis(500), async {
+                let _ = stdout_task.await;
+                let _ = stderr_task.await;
+            })
+            .await;
+            // The hook may read and rewrite files.
+            let note = match on_exit {
+                Some(hook) => tokio::task::spawn_blocking(hook).await.unwrap_or_else(|e| {
+                    tracing::warn!("Exit hook of background job {pid} failed: {e}");
+                    None
+                }),
+                None => None,
+            };
+
+            let mut state = monitor_state.lock().unwrap();
+            if let Some(note) = note {
+                append_output(&mut state.output, &format!("{note}\n"));
+            }
+            let (exit_code, killed_by) = match status {
+                Ok(_) if state.kill_requested => (None, Some("job_kill".to_string())),
+                Ok(status) => (
+                    status.code(),
+                    killed_by(status, &state.output, &limits).map(String::from),
+                ),
+                Err(e) => {
+                    tracing::warn!("Failed to wait for background job {pid}: {e}");
+                    (None, None)
+                }
+            };
+            state.exit = Some(JobExit {
+                exit_code,
+                killed_by,
+                runtime: started.elapsed(),
+            });
+            drop(state);
+            let _ = done_tx.send(true);
+        });
+
+        registry.next_id += 1;
+        let job = Job {
+            id: format!("job-{}", registry.next_id),
+            command: command.to_string(),
+            pid,
+            started,
+            state,
+            done,
+        };
+        let status = job.status();
+        registry.jobs.push(job);
+        Ok(status)
+    }
+
+    /// Status of `job_id` and the last `tail_lines` lines of its output, or
+    /// `None` if there is no such job.
+    pub fn status(&self, job_id: &str, tail_lines: usize) -> Option<(JobStatus, String)> {
+        let registry = self.inner.lock().unwrap();
+        let job = registry.jobs.iter().find(|job| job.id == job_id)?;
+        let output = last_lines(&job.state.lock().unwrap().output, tail_lines).to_string();
+        Some((job.status(), output))
+    }
+
+    /// Status of every job started in this session, oldest first.
+    pub fn list(&self) -> Vec<JobStatus> {
+        let registry = self.inner.lock().unwrap();
+        registry.jobs.iter().map(Job::status).collect()
+    }
+
+    /// Kill the process group of a running job and wait (briefly) until it
+    /// has been reaped. Fails if there is no such job or it already ended.
+    pub async fn kill(&self, job_id: &str) -> anyhow::Result<JobStatus> {
+        let mut done = {
+            let registry = self.inner.lock().unwrap();
+            let job = registry
+                .jobs
+                .iter()
+                .find(|job| job.id == job_id)
+                .ok_or_else(|| anyhow::anyhow!("no job '{job_id}'"))?;
+            if !job.is_running() {
+                anyhow::bail!("job '{job_id}' has already ended");
+            }
+            job.state.lock().unwrap().kill_requested = true;
+            kill_process_group(job.pid);
+            job.done.clone()
+        };
+        let _ = tokio::time::timeout(KILL_WAIT, done.wait_for(|done| *done)).await;
+
+        let registry = self.inner.lock().unwrap();
+        let job = registry.jobs.iter().find(|job| job.id == job_id).expect("jobs are never removed");
+        Ok(job.status())
+    }
+
+    /// Kill the process group of every job. Returns how many were still
+    /// running.
+    pub fn kill_all(&self) -> usize {
+        let registry = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut running = 0;
+        for job in &registry.jobs {
+            if job.is_running() {
+                running += 1;
+                job.state.lock().unwrap_or_else(|e| e.into_inner()).kill_requested = true;
+                kill_process_group(job.pid);
+            } else if group_outlives_shell(job.pid) {
+                // Processes an exited shell left in the background are
+                // still in its group.
+                kill_process_group(job.pid);
+            }
+        }
+        running
+    }
+}
+
+impl Drop for JobRegistry {
+    fn drop(&mut self) {
+        let running = self.kill_all();
+        if running > 0 {
+            tracing::info!("Killed {running} background jobs at session end");
+        }
+    }
+}
+
+impl Job {
+    fn is_running(&self) -> bool {
+        self.state.lock().unwrap().exit.is_none()
+    }
+
+    fn status(&self) -> JobStatus {
+        let state = self.state.lock().unwrap();
+        let (state_name, exit_code, killed_by, runtime) = match &state.exit {
+            None => ("running", None, None, self.started.elapsed()),
+            Some(exit) => {
+                let name = if exit.killed_by.is_some() || exit.exit_code.is_none() {
+                    "killed"
+                } else {
+                    "exited"
+                };
+                (name, exit.exit_code, exit.killed_by.clone(), exit.runtime)
+            }
+        };
+        JobStatus {
+            job_id: self.id.clone(),
+            command: self.command.clone(),
+            state: state_name,
+            exit_code,
+            killed_by,
+            runtime_secs: runtime.as_secs(),
+        }
+    }
+}
+
+/// Whether the process group led by the reaped shell `pid` still exists.
+///
+/// The kernel does not hand out a PID while a group still uses it as its ID,
+/// so a live process with that PID means the group is gone and the PID has
+/// been reused, possibly for an unrelated group that must not be killed.
+fn group_outlives_shell(pid: u32) -> bool {
+    let pid = Pid::from_raw(pid as i32);
+    kill(pid, None) == Err(Errno::ESRCH) && killpg(pid, None).is_ok()
+}
+
+/// Append everything read from `reader` to the job's output, a line at a
+/// time so stdout and stderr lines do not interleave mid-line.
+async fn collect_output<R: AsyncRead + Unpin>(reader: R, state: Arc<Mutex<JobState>>) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                append_output(&mut state.lock().unwrap().output, &text);
+            }
+        }
+    }
+}
+
+/// Append `text`, dropping the oldest whole lines once the output is twice
+/// [`OUTPUT_TAIL_BYTES`] (trimming only then keeps appends cheap).
+fn append_output(output: &mut String, text: &str) {
+    output.push_str(text);
+    if output.len() > 2 * OUTPUT_TAIL_BYTES {
+        let mut cut = output.len() - OUTPUT_TAIL_BYTES;
+        while !output.is_char_boundary(cut) {
+            cut += 1;
+        }
+        if let Some(newline) = output[cut..].find('\n') {
+            cut += newline + 1;
+        }
+        output.drain(..cut);
+    }
+}
+
+/// The last `n` lines of `text`.
+fn last_lines(text: &str, n: usize) -> &str {
+    if n == 0 {
+        return "";
+    }
+    let body = text.strip_suffix('\n').unwrap_or(text);
+    match body.rmatch_indices('\n').nth(n - 1) {
+        Some((idx, _)) => &text[idx + 1..],
+        None => text,
+    }
+}
@@ -1,3 +1,9 @@
+pub mod jobs;
+pub mod sandbox;
 pub mod shell;
+pub mod web;
 
-pub use shell::{execute_shell, ExecResult};
+pub use jobs::{ExitHook, JobRegistry, JobStatus};
+pub use sandbox::Sandbox;
+pub use shell::{execute_shell, ExecResult, OutputSink, ResourceLimits};
+pub use web::{fetch_url, FetchResult, WebLimits};
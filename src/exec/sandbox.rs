@@ -0,0 +1,237 @@
+//! Linux namespace sandbox for shell commands.
+//!
+//! The command filter is not a security boundary; this is the closest thing
+//! the harness has to one. A sandboxed command runs in fresh user and mount
+//! namespaces (and, unless networking is allowed, a network namespace with
+//! only a loopback device that is down), where:
+//!
+//! - the whole filesystem is read-only, recursively,
+//! - the workspace is mounted read-write at its usual path,
+//! - `/tmp` is a private, empty tmpfs.
+//!
+//! Everything is set up in the forked child before `exec`, so it needs no
+//! helper binary and no privileges beyond unprivileged user namespaces. The
+//! mount API calls (`open_tree`, `move_mount`, `mount_setattr`) need Linux
+//! 5.12 or later. [`Sandbox::probe`] tells callers whether it works on this
+//! host so they can fall back to running commands unsandboxed.
+
+use std::ffi::CString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Namespace sandbox settings for one workspace. Build once with
+/// [`Sandbox::new`], check with [`Sandbox::probe`], then call
+/// [`Sandbox::enter`] from a `pre_exec` hook.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    /// Canonical workspace path.
+    workspace_path: PathBuf,
+    workspace: CString,
+    /// The workspace and its ancestors inside `/tmp`, outermost first. They
+    /// are recreated in the private `/tmp` so the workspace can be mounted
+    /// there.
+    tmp_dirs: Vec<CString>,
+    /// Contents for `/proc/self/uid_map` and `gid_map`: the caller's IDs map
+    /// to themselves, so workspace files keep their owner.
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+    network: bool,
+}
+
+impl Sandbox {
+    /// Prepare a sandbox for `workspace`, which must be canonical. With
+    /// `network` false, commands get no network access.
+    pub fn new(workspace: &Path, network: bool) -> Self {
+        let mut tmp_dirs: Vec<CString> = workspace
+            .ancestors()
+            .filter(|dir| dir.starts_with("/tmp") && *dir != Path::new("/tmp"))
+            .map(c_path)
+            .collect();
+        tmp_dirs.reverse();
+        Self {
+            workspace_path: workspace.to_path_buf(),
+            workspace: c_path(workspace),
+            tmp_dirs,
+            uid_map: id_map(current_ids().0),
+            gid_map: id_map(current_ids().1),
+            network,
+        }
+    }
+
+    /// Run a trivial command in the sandbox to check that this host allows
+    /// it (unprivileged user namespaces enabled, new enough kernel).
+    pub fn probe(&self) -> Result<(), String> {
+        use std::os::unix::process::CommandExt;
+
+        let sandbox = self.clone();
+        let mut probe = std::process::Command::new("sh");
+        probe
+            .arg("-c")
+            .arg("test -w .")
+            .current_dir(&self.workspace_path)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        // SAFETY: see `Sandbox::enter`; the hook only makes system calls.
+        unsafe {
+            probe.pre_exec(move || sandbox.enter());
+        }
+        match probe.status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("sandboxed test command failed ({status})")),
+            Err(e) => Err(format!("could not create namespaces: {e}")),
+        }
+    }
+
+    /// Move the current process into the sandbox. Meant to run in a forked
+    /// child between `fork` and `exec`: it only makes system calls, and does
+    /// not allocate.
+    pub fn enter(&self) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            linux::enter(self)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+    }
+}
+
+fn c_path(path: &Path) -> CString {
+    use std::os::unix::ffi::OsStrExt;
+    CString::new(path.as_os_str().as_bytes()).expect("paths contain no NUL bytes")
+}
+
+fn current_ids() -> (u32, u32) {
+    (nix::unistd::getuid().as_raw(), nix::unistd::getgid().as_raw())
+}
+
+fn id_map(id: u32) -> Vec<u8> {
+    format!("{id} {id} 1").into_bytes()
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CStr;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use nix::errno::Errno;
+    use nix::fcntl::{open, OFlag};
+    use nix::libc;
+    use nix::mount::{mount, MsFlags};
+    use nix::sched::{unshare, CloneFlags};
+    use nix::sys::stat::Mode;
+    use nix::unistd::{chdir, mkdir, write};
+
+    use super::Sandbox;
+
+    // From linux/mount.h; not exported by libc for every target.
+    const OPEN_TREE_CLONE: libc::c_uint = 0x1;
+    const MOVE_MOUNT_F_EMPTY_PATH: libc::c_uint = 0x4;
+    const MOUNT_ATTR_RDONLY: u64 = 0x1;
+
+    pub fn enter(sandbox: &Sandbox) -> io::Result<()> {
+        let mut namespaces = CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_NEWNS;
+        if !sandbox.network {
+            namespaces |= CloneFlags::CLONE_NEWNET;
+        }
+        unshare(namespaces)?;
+
+        // setgroups must be denied before an unprivileged process may write
+        // its gid_map.
+        write_file(c"/proc/self/setgroups", b"deny")?;
+        write_file(c"/proc/self/uid_map", &sandbox.uid_map)?;
+        write_file(c"/proc/self/gid_map", &sandbox.gid_map)?;
+
+        // Keep mount changes from propagating back to the host.
+        mount(
+            None::<&CStr>,
+            c"/",
+            None::<&CStr>,
+            MsFlags::MS_REC | MsFlags::MS_PRIVATE,
+            None::<&CStr>,
+        )?;
+
+        // Take a detached, still-writable copy of the workspace mount before
+        // everything else becomes read-only.
+        let workspace = open_tree(&sandbox.workspace)?;
+        set_read_only(c"/")?;
+
+        mount(
+            Some(c"tmpfs"),
+            c"/tmp",
+            Some(c"tmpfs"),
+            MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
+            Some(c"mode=1777"),
+        )?;
+        for dir in &sandbox.tmp_dirs {
+            match mkdir(dir.as_c_str(), Mode::from_bits_truncate(0o755)) {
+                Ok(()) | Err(Errno::EEXIST) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        move_mount(&workspace, &sandbox.workspace)?;
+        // The working directory still points into the read-only mount the
+        // workspace was covered by.
+        chdir(sandbox.workspace.as_c_str())?;
+        Ok(())
+    }
+
+    fn write_file(path: &CStr, contents: &[u8]) -> io::Result<()> {
+        let fd = open(path, OFlag::O_WRONLY | OFlag::O_CLOEXEC, Mode::empty())?;
+        // SAFETY: `open` just returned this descriptor and nothing else owns it.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        write(&fd, contents)?;
+        Ok(())
+    }
+
+    fn open_tree(path: &CStr) -> io::Result<OwnedFd> {
+        let flags = OPEN_TREE_CLONE | libc::O_CLOEXEC as libc::c_uint | libc::AT_RECURSIVE as libc::c_uint;
+        // SAFETY: `path` is a valid NUL-terminated string for the call.
+        let fd = unsafe { libc::syscall(libc::SYS_open_tree, libc::AT_FDCWD, path.as_ptr(), flags) };
+        let fd = Errno::result(fd)?;
+        // SAFETY: the kernel just returned this descriptor.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+    }
+
+    fn move_mount(tree: &OwnedFd, target: &CStr) -> io::Result<()> {
+        // SAFETY: both strings are valid and NUL-terminated for the call.
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_move_mount,
+                tree.as_raw_fd(),
+                c"".as_ptr(),
+                libc::AT_FDCWD,
+                target.as_ptr(),
+                MOVE_MOUNT_F_EMPTY_PATH,
+            )
+        };
+        Errno::result(res)?;
+        Ok(())
+    }
+
+    /// Make `path` and every mount under it read-only.
+    fn set_read_only(path: &CStr) -> io::Result<()> {
+        let attr = libc::mount_attr {
+            attr_set: MOUNT_ATTR_RDONLY,
+            attr_clr: 0,
+            propagation: 0,
+            userns_fd: 0,
+        };
+        // SAFETY: `path` and `attr` outlive the call, and the size matches.
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_mount_setattr,
+                libc::AT_FDCWD,
+                path.as_ptr(),
+                libc::AT_RECURSIVE as libc::c_uint,
+                &attr as *const libc::mount_attr,
+                std::mem::size_of::<libc::mount_attr>(),
+            )
+        };
+        Errno::result(res)?;
+        Ok(())
+    }
+}
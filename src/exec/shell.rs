@@ -1,8 +1,14 @@
+use std::os::unix::process::ExitStatusExt;
 use std::path::Path;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 
-use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use nix::sys::resource::{getrlimit, setrlimit, Resource};
+use nix::sys::signal::Signal;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+
+use super::sandbox::Sandbox;
 
 /// Result of a shell command execution.
 #[derive(Debug, Clone, serde::Serialize)]
@@ -11,6 +17,66 @@ pub struct ExecResult {
     pub stderr: String,
     pub exit_code: Option<i32>,
     pub timed_out: bool,
+    /// The limit that evidently stopped the command: "timeout", "cpu",
+    /// "file_size", or "memory".
+    pub killed_by: Option<String>,
+}
+
+/// Which stream a line of command output came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Receives a command's output line by line while it runs.
+pub type OutputSink = tokio::sync::mpsc::UnboundedSender<(OutputStream, String)>;
+
+/// Resource limits applied to each shell command, from the `[limits]` config
+/// section. `None` leaves a resource as the harness itself has it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Address space per process, in bytes (`RLIMIT_AS`).
+    pub memory_bytes: Option<u64>,
+    /// CPU time per process, in seconds (`RLIMIT_CPU`).
+    pub cpu_secs: Option<u64>,
+    /// Largest file a process may write, in bytes (`RLIMIT_FSIZE`).
+    pub file_size_bytes: Option<u64>,
+    /// Processes the user may have (`RLIMIT_NPROC`). The kernel counts every
+    /// process of the user running ouro, not just the command's.
+    pub processes: Option<u64>,
+    /// Open file descriptors per process (`RLIMIT_NOFILE`).
+    pub open_files: Option<u64>,
+}
+
+impl ResourceLimits {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Set the limits on the current process. Runs in the forked child
+    /// before exec, so it only makes system calls.
+    ///
+    /// Limits above the harness's own hard limit are clamped to it. The CPU
+    /// hard limit is one second above the soft one, so the process gets
+    /// SIGXCPU (which [`killed_by`] recognizes) before SIGKILL.
+    fn apply(&self) -> std::io::Result<()> {
+        let limits = [
+            (Resource::RLIMIT_AS, self.memory_bytes, 0),
+            (Resource::RLIMIT_CPU, self.cpu_secs, 1),
+            (Resource::RLIMIT_FSIZE, self.file_size_bytes, 0),
+            (Resource::RLIMIT_NPROC, self.processes, 0),
+            (Resource::RLIMIT_NOFILE, self.open_files, 0),
+        ];
+        for (resource, limit, grace) in limits {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let (_, hard) = getrlimit(resource)?;
+            setrlimit(resource, limit.min(hard), limit.saturating_add(grace).min(hard))?;
+        }
+        Ok(())
+    }
 }
 
 /// Execute a shell command asynchronously with timeout and process-group management.
@@ -18,7 +84,24 @@ pub struct ExecResult {
 /// Spawns `sh -c <command>` in its own process group so that the entire group
 /// can be killed on timeout (not just the parent shell). Stdout and stderr are
 /// read concurrently in separate tasks so that partial output is captured even
-/// if the process is killed mid-execution.
+/// if the process is killed mid-execution. With an `output` sink, each line is
+/// also sent there as soon as it is read; the returned [`ExecResult`] is the
+/// same either way.
+///
+/// # Resource limits
+///
+/// `limits` are set on the shell before it execs and are inherited by
+/// everything it runs. The result's `killed_by` names the limit that stopped
+/// the command, judged from the terminating signal (or the shell's `128 + n`
+/// exit code for a signal-killed child) and, for memory, from allocation
+/// failures reported on stderr.
+///
+/// # Sandbox
+///
+/// With a `sandbox`, the shell is moved into its namespaces before it execs
+/// (see [`Sandbox`]). Callers are expected to have checked
+/// [`Sandbox::probe`]; if entering the sandbox fails anyway, the command
+/// does not run and an error is returned.
 ///
 /// # Timeout behavior
 ///
@@ -29,27 +112,18 @@ pub struct ExecResult {
 /// # Important
 ///
 /// - Does **not** use `kill_on_drop` (causes zombie processes).
-/// - Uses `nix` crate for `killpg` (safe wrapper, no `unsafe` blocks).
+/// - Uses `nix` crate for `killpg` and `setrlimit`; the only `unsafe` is
+///   registering the `pre_exec` hooks that set resource limits and enter
+///   the sandbox.
 pub async fn execute_shell(
     command: &str,
     working_dir: &Path,
     timeout_secs: u64,
+    limits: &ResourceLimits,
+    sandbox: Option<&Sandbox>,
+    output: Option<OutputSink>,
 ) -> anyhow::Result<ExecResult> {
-    let mut child = {
-        // process_group(0) requires the CommandExt trait in scope.
-        #[allow(unused_imports)]
-        use std::os::unix::process::CommandExt;
-
-        Command::new("sh")
-            .arg("-c")
-            .arg(command)
-            .current_dir(working_dir)
-            .process_group(0) // new process group for clean kill
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| anyhow::anyhow!("Failed to spawn shell process: {}", e))?
-    };
+    let mut child = spawn_shell(command, working_dir, limits, sandbox)?;
 
     let child_pid = child
         .id()
@@ -59,20 +133,9 @@ pub async fn execute_shell(
     let stdout_handle = child.stdout.take().expect("stdout piped");
     let stderr_handle = child.stderr.take().expect("stderr piped");
 
-    // Spawn concurrent readers that buffer output as it arrives.
-    let stdout_task = tokio::spawn(async move {
-        let mut buf = String::new();
-        let mut handle = stdout_handle;
-        let _ = handle.read_to_string(&mut buf).await;
-        buf
-    });
-
-    let stderr_task = tokio::spawn(async move {
-        let mut buf = String::new();
-        let mut handle = stderr_handle;
-        let _ = handle.read_to_string(&mut buf).await;
-        buf
-    });
+    // Spawn concurrent readers that buffer (and stream) output as it arrives.
+    let stdout_task = tokio::spawn(read_output(stdout_handle, OutputStream::Stdout, output.clone()));
+    let stderr_task = tokio::spawn(read_output(stderr_handle, OutputStream::Stderr, output));
 
     let timeout_duration = std::time::Duration::from_secs(timeout_secs);
 
@@ -84,11 +147,13 @@ pub async fn execute_shell(
         Ok(Ok(status)) => {
             let stdout = stdout_task.await.unwrap_or_default();
             let stderr = stderr_task.await.unwrap_or_default();
+            let killed_by = killed_by(status, &stderr, limits).map(String::from);
             Ok(ExecResult {
                 stdout,
                 stderr,
                 exit_code: status.code(),
                 timed_out: false,
+                killed_by,
             })
         }
         // Process wait errored (not a timeout).
@@ -96,8 +161,7 @@ pub async fn execute_shell(
         // Timeout expired -- kill process group.
         Err(_elapsed) => {
             // Kill the entire process group via SIGKILL.
-            let pgid = nix::unistd::Pid::from_raw(child_pid as i32);
-            let _ = nix::sys::signal::killpg(pgid, nix::sys::signal::Signal::SIGKILL);
+            kill_process_group(child_pid);
 
             // Reap the child to prevent zombie processes.
             let _ = child.wait().await;
@@ -131,7 +195,116 @@ pub async fn execute_shell(
                 stderr: partial_stderr,
                 exit_code: None,
                 timed_out: true,
+                killed_by: Some("timeout".into()),
             })
         }
     }
 }
+
+/// Spawn `sh -c <command>` in `working_dir` as the leader of a new process
+/// group, with stdout and stderr piped, `limits` applied, and inside
+/// `sandbox` if given. Shared by [`execute_shell`] and background jobs
+/// ([`super::jobs::JobRegistry`]).
+pub fn spawn_shell(
+    command: &str,
+    working_dir: &Path,
+    limits: &ResourceLimits,
+    sandbox: Option<&Sandbox>,
+) -> anyhow::Result<Child> {
+    // process_group(0) requires the CommandExt trait in scope.
+    #[allow(unused_imports)]
+    use std::os::unix::process::CommandExt;
+
+    let mut shell = Command::new("sh");
+    shell
+        .arg("-c")
+        .arg(command)
+        .current_dir(working_dir)
+        .process_group(0) // new process group for clean kill
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if !limits.is_empty() {
+        let limits = *limits;
+        // SAFETY: the hook runs between fork and exec and only calls
+        // getrlimit/setrlimit, which are async-signal-safe; it does not
+        // allocate or take locks.
+        unsafe {
+            shell.pre_exec(move || limits.apply());
+        }
+    }
+    if let Some(sandbox) = sandbox {
+        let sandbox = sandbox.clone();
+        // SAFETY: `Sandbox::enter` only makes system calls and does not
+        // allocate.
+        unsafe {
+            shell.pre_exec(move || sandbox.enter());
+        }
+    }
+    shell
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to spawn shell process: {}", e))
+}
+
+/// Send SIGKILL to the process group led by `pid` (a shell started by
+/// [`spawn_shell`]), taking everything the command started with it.
+pub fn kill_process_group(pid: u32) {
+    let pgid = nix::unistd::Pid::from_raw(pid as i32);
+    let _ = nix::sys::signal::killpg(pgid, Signal::SIGKILL);
+}
+
+/// Read `reader` to the end, sending each complete line (without its line
+/// ending) to `output` as it arrives. Returns everything read, with invalid
+/// UTF-8 replaced.
+async fn read_output<R: AsyncRead + Unpin>(
+    reader: R,
+    stream: OutputStream,
+    output: Option<OutputSink>,
+) -> String {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        let start = buf.len();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if let Some(output) = &output {
+                    let line = String::from_utf8_lossy(&buf[start..]);
+                    let _ = output.send((stream, line.trim_end_matches(['\n', '\r']).to_string()));
+                }
+            }
+        }
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Messages allocation failures print on stderr: glibc/strerror, Python,
+/// and the C++ runtime.
+const OUT_OF_MEMORY_MESSAGES: &[&str] = &["Cannot allocate memory", "MemoryError", "std::bad_alloc", "out of memory"];
+
+/// Which of the configured `limits` stopped a command that exited with
+/// `status`, if any evidently did.
+///
+/// CPU and file-size limits are enforced with dedicated signals. A memory
+/// limit only makes allocations fail, so the process is judged to have hit
+/// it only if it failed -- often by crashing with SIGSEGV, SIGBUS, or
+/// SIGABRT -- with an out-of-memory message on stderr. A crash without one
+/// is an ordinary crash.
+pub fn killed_by(status: ExitStatus, stderr: &str, limits: &ResourceLimits) -> Option<&'static str> {
+    // A signal-killed child of the shell shows up as exit code 128 + n.
+    let signal = status
+        .signal()
+        .or_else(|| status.code().filter(|code| (129..160).contains(code)).map(|code| code - 128))
+        .and_then(|signal| Signal::try_from(signal).ok());
+    match signal {
+        Some(Signal::SIGXCPU) if limits.cpu_secs.is_some() => return Some("cpu"),
+        Some(Signal::SIGXFSZ) if limits.file_size_bytes.is_some() => return Some("file_size"),
+        _ => {}
+    }
+    if limits.memory_bytes.is_some()
+        && !status.success()
+        && OUT_OF_MEMORY_MESSAGES.iter().any(|m| stderr.contains(m))
+    {
+        return Some("memory");
+    }
+    None
+}
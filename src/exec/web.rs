@@ -0,0 +1,513 @@
+use std::time::Duration;
+
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::Url;
+
+use crate::safety::domain_filter::DomainFilter;
+
+/// Hard cap on raw bytes downloaded, regardless of the text budget.
+///
+/// HTML is usually several times larger than the text extracted from it, so
+/// the download limit is generous; it only guards against unbounded bodies.
+pub const MAX_DOWNLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// Limits applied to a single fetch, from the `[web]` config section.
+#[derive(Debug, Clone)]
+pub struct WebLimits {
+    /// Maximum bytes of extracted text returned.
+    pub max_bytes: usize,
+    pub max_redirects: usize,
+    pub timeout_secs: u64,
+}
+
+/// Result of a web fetch.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FetchResult {
+    /// Final URL after redirects.
+    pub url: String,
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Response body; HTML is converted to markdown-ish text.
+    pub content: String,
+    /// True if `content` was cut to the byte budget.
+    pub truncated: bool,
+}
+
+/// Fetch a URL and return its body as readable text.
+///
+/// Redirects are followed up to `limits.max_redirects`, and every hop is
+/// re-checked against `filter` so a redirect cannot escape the domain lists.
+/// HTML bodies are converted with [`html_to_text`]; other textual types are
+/// returned as-is. Binary content types are rejected. Non-2xx responses are
+/// not errors: the status and body are returned for the caller to inspect.
+pub async fn fetch_url(
+    url: &str,
+    filter: &DomainFilter,
+    limits: &WebLimits,
+) -> anyhow::Result<FetchResult> {
+    let url = Url::parse(url).map_err(|e| anyhow::anyhow!("invalid URL '{url}': {e}"))?;
+    if let Some(reason) = filter.check(&url) {
+        anyhow::bail!("blocked: {reason}");
+    }
+
+    let redirect_filter = filter.clone();
+    let max_redirects = limits.max_redirects;
+    let policy = Policy::custom(move |attempt: Attempt| {
+        if attempt.previous().len() > max_redirects {
+            let message = format!("too many redirects (limit {max_redirects})");
+            return attempt.error(message);
+        }
+        match redirect_filter.check(attempt.url()) {
+            Some(reason) => {
+                let message = format!("redirect blocked: {reason}");
+                attempt.error(message)
+            }
+            None => attempt.follow(),
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .redirect(policy)
+        .timeout(Duration::from_secs(limits.timeout_secs))
+        .user_agent(concat!("ouro/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| anyhow::Error::from(e).context("request failed"))?;
+
+    let final_url = response.url().to_string();
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    let mime = content_type.as_deref().unwrap_or("").to_ascii_lowercase();
+    let is_html = mime.contains("html");
+    let is_text = mime.is_empty()
+        || mime.starts_with("text/")
+        || mime.contains("json")
+        || mime.contains("xml")
+        || mime.contains("javascript");
+    if !is_html && !is_text {
+        anyhow::bail!("unsupported content type '{mime}'");
+    }
+
+    // Read the body incrementally so a huge response cannot exhaust memory.
+    let mut body = Vec::new();
+    let mut download_truncated = false;
+    while let Some(chunk) = response.chunk().await? {
+        let room = MAX_DOWNLOAD_BYTES - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            download_truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8_lossy(&body);
+
+    let text = if is_html {
+        html_to_text(&body)
+    } else {
+        body.into_owned()
+    };
+    let (content, truncated) = truncate_to_bytes(text, limits.max_bytes);
+
+    Ok(FetchResult {
+        url: final_url,
+        status,
+        content_type,
+        content,
+        truncated: truncated || download_truncated,
+    })
+}
+
+/// Cut `text` to at most `max_bytes`, on a char boundary.
+fn truncate_to_bytes(mut text: String, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    (text, true)
+}
+
+// ---------------------------------------------------------------------------
+// HTML to text
+// ---------------------------------------------------------------------------
+
+/// Elements whose content is never shown.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "svg", "template", "iframe"];
+
+/// Elements that start and end on their own line.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "section", "article", "header", "footer", "main", "nav", "aside", "ul", "ol",
+    "table", "tr", "blockquote", "form", "figure", "figcaption", "dl", "dt", "dd", "title",
+    "details", "summary",
+];
+
+/// Convert an HTML document to markdown-ish plain text.
+///
+/// Not a full HTML parser: it walks tags in order and maps the common ones to
+/// markdown (headings, lists, links, code, rules), drops scripts, styles and
+/// comments, decodes entities, and collapses whitespace outside `<pre>`.
+pub fn html_to_text(html: &str) -> String {
+    let mut w = TextWriter::default();
+    let mut links: Vec<Option<String>> = Vec::new();
+    let mut skip: Option<String> = None;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            if skip.is_none() {
+                w.text(&decode_entities(rest));
+            }
+            break;
+        };
+        if lt > 0 && skip.is_none() {
+            w.text(&decode_entities(&rest[..lt]));
+        }
+        rest = &rest[lt..];
+
+        // A '<' not followed by a tag name is literal text ("a < b").
+        let starts_tag = rest[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'));
+        if !starts_tag {
+            if skip.is_none() {
+                w.text("<");
+            }
+            rest = &rest[1..];
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            // Unterminated tag: treat the remainder as text.
+            if skip.is_none() {
+                w.text(&decode_entities(rest));
+            }
+            break;
+        };
+        let tag = Tag::parse(&rest[1..gt]);
+        rest = &rest[gt + 1..];
+        let Some(tag) = tag else { continue };
+
+        if let Some(skipped) = &skip {
+            if tag.closing && tag.name == *skipped {
+                skip = None;
+            }
+            continue;
+        }
+        if SKIPPED_ELEMENTS.contains(&tag.name.as_str()) {
+            if !tag.closing && !tag.self_closing {
+                skip = Some(tag.name);
+            }
+            continue;
+        }
+
+        match (tag.name.as_str(), tag.closing) {
+            (h, false) if is_heading(h) => {
+                w.blank_line();
+                let level = h[1..].parse::<usize>().unwrap_or(1);
+                w.raw(&format!("{} ", "#".repeat(level)));
+            }
+            (h, true) if is_heading(h) => w.blank_line(),
+            ("br", _) => w.newline(),
+            ("hr", _) => {
+                w.blank_line();
+                w.raw("---");
+                w.blank_line();
+            }
+            ("li", false) => {
+                w.newline();
+                w.raw("- ");
+            }
+            ("li", true) => w.newline(),
+            ("td" | "th", false) if !w.at_line_start() => w.raw(" | "),
+            ("pre", false) => {
+                w.blank_line();
+                w.raw("```\n");
+                w.pre_depth += 1;
+            }
+            ("pre", true) => {
+                w.pre_depth = w.pre_depth.saturating_sub(1);
+                w.newline();
+                w.raw("```");
+                w.blank_line();
+            }
+            ("code", _) if w.pre_depth == 0 => w.raw("`"),
+            ("img", _) => {
+                if let Some(alt) = tag.attr("alt").filter(|a| !a.trim().is_empty()) {
+                    w.text(&format!("[image: {}]", alt.trim()));
+                }
+            }
+            ("a", false) => {
+                let href = tag
+                    .attr("href")
+                    .filter(|h| !h.is_empty() && !h.starts_with('#') && !h.starts_with("javascript:"));
+                if href.is_some() {
+                    w.raw("[");
+                }
+                links.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = links.pop() {
+                    w.raw(&format!("]({href})"));
+                }
+            }
+            (name, _) if BLOCK_ELEMENTS.contains(&name) => w.blank_line(),
+            _ => {}
+        }
+    }
+
+    w.finish()
+}
+
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// A parsed start or end tag.
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag {
+    /// Parse the inside of `<...>`. Returns `None` for doctypes and
+    /// processing instructions.
+    fn parse(inner: &str) -> Option<Self> {
+        let inner = inner.trim();
+        if inner.starts_with('!') || inner.starts_with('?') {
+            return None;
+        }
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, inner),
+        };
+        let (self_closing, inner) = match inner.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, inner),
+        };
+        let name_end = inner
+            .find(|c: char| c.is_whitespace())
+            .unwrap_or(inner.len());
+        let name = inner[..name_end].to_ascii_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name,
+            closing,
+            self_closing,
+            attrs: parse_attrs(&inner[name_end..]),
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<String> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| decode_entities(v))
+    }
+}
+
+/// Parse `key="value" key='value' key=value key` attribute lists.
+fn parse_attrs(mut s: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    loop {
+        s = s.trim_start();
+        if s.is_empty() {
+            return attrs;
+        }
+        let key_end = s
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(s.len());
+        let key = s[..key_end].to_ascii_lowercase();
+        s = s[key_end..].trim_start();
+        let Some(after_eq) = s.strip_prefix('=') else {
+            attrs.push((key, String::new()));
+            continue;
+        };
+        let after_eq = after_eq.trim_start();
+        let (value, remaining) = match after_eq.chars().next() {
+            Some(q @ ('"' | '\'')) => {
+                let body = &after_eq[1..];
+                let end = body.find(q).unwrap_or(body.len());
+                (&body[..end], body.get(end + 1..).unwrap_or(""))
+            }
+            _ => {
+                let end = after_eq
+                    .find(char::is_whitespace)
+                    .unwrap_or(after_eq.len());
+                (&after_eq[..end], &after_eq[end..])
+            }
+        };
+        attrs.push((key, value.to_string()));
+        s = remaining;
+    }
+}
+
+/// Decode named and numeric character references.
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&semi| semi <= 10)
+            .and_then(|semi| decode_entity(&rest[1..semi + 1]).map(|c| (c, semi + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "mdash" => '\u{2014}',
+        "ndash" => '\u{2013}',
+        "hellip" => '\u{2026}',
+        "copy" => '\u{a9}',
+        "reg" => '\u{ae}',
+        "trade" => '\u{2122}',
+        "laquo" => '\u{ab}',
+        "raquo" => '\u{bb}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "middot" => '\u{b7}',
+        _ => return None,
+    })
+}
+
+/// Accumulates output text, collapsing whitespace outside `<pre>`.
+#[derive(Default)]
+struct TextWriter {
+    out: String,
+    pre_depth: usize,
+    pending_space: bool,
+}
+
+impl TextWriter {
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    /// True if a pending space should be written before the next output.
+    fn needs_space(&self) -> bool {
+        self.pending_space && !self.at_line_start() && !self.out.ends_with(' ')
+    }
+
+    /// Append document text.
+    fn text(&mut self, s: &str) {
+        if self.pre_depth > 0 {
+            self.out.push_str(s);
+            return;
+        }
+        if s.starts_with(char::is_whitespace) {
+            self.pending_space = true;
+        }
+        for word in s.split_whitespace() {
+            if self.needs_space() {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+            self.pending_space = true;
+        }
+        if !s.trim().is_empty() && !s.ends_with(char::is_whitespace) {
+            self.pending_space = false;
+        }
+    }
+
+    /// Append markup text (list bullets, link brackets) verbatim.
+    fn raw(&mut self, s: &str) {
+        // Closing a link keeps any pending space for the text that follows.
+        if !s.starts_with(']') {
+            if self.needs_space() {
+                self.out.push(' ');
+            }
+            self.pending_space = false;
+        }
+        self.out.push_str(s);
+    }
+
+    fn newline(&mut self) {
+        self.pending_space = false;
+        while self.out.ends_with(' ') {
+            self.out.pop();
+        }
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut result = String::with_capacity(self.out.len());
+        let mut blank_run = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_run += 1;
+                if blank_run > 1 {
+                    continue;
+                }
+            } else {
+                blank_run = 0;
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+        result.trim().to_string()
+    }
+}
@@ -20,7 +20,11 @@ async fn main() -> anyhow::Result<()> {
     let cli = cli::Cli::parse();
 
     // Determine if we're in TUI mode (TUI owns the terminal, so suppress stderr tracing).
-    let is_tui_mode = matches!(&cli.command, cli::Commands::Run { headless, .. } if !headless);
+    let is_tui_mode = match &cli.command {
+        cli::Commands::Run { headless, .. } | cli::Commands::Resume { headless, .. } => !headless,
+        cli::Commands::Replay { .. } => true,
+        cli::Commands::Snapshots { .. } => false,
+    };
 
     // Initialize tracing -- suppress stderr in TUI mode to avoid corrupting the terminal.
     if is_tui_mode {
@@ -45,116 +49,158 @@ async fn main() -> anyhow::Result<()> {
     let config = config::load_config(&cli)?;
     tracing::info!(model = %config.model, workspace = %config.workspace.display(), "Config loaded");
 
-    match cli.command {
-        cli::Commands::Run { headless, .. } => {
-            let safety = SafetyLayer::new(&config)?;
-
+    // -- Determine the starting session: fresh, or rehydrated from the latest log.
+    let (headless, session_number, carryover_messages) = match cli.command {
+        cli::Commands::Run { headless, .. } => (headless, 1, Vec::new()),
+        cli::Commands::Resume { headless, .. } => {
+            let state = agent::resume::load_resume_state(&config)?;
             tracing::info!(
-                model = %config.model,
-                workspace = %safety.workspace_root().display(),
-                timeout_secs = config.shell_timeout_secs,
-                blocklist_patterns = config.blocked_patterns.len(),
-                "Safety layer initialized"
+                log = %state.log_path.display(),
+                session_number = state.session_number,
+                turns = state.turns,
+                messages = state.carryover_messages.len(),
+                "Resuming from session log"
+            );
+            if headless {
+                eprintln!(
+                    "Resuming session #{} from {} ({} turns, {} messages restored)",
+                    state.session_number,
+                    state.log_path.display(),
+                    state.turns,
+                    state.carryover_messages.len()
+                );
+            }
+            (headless, state.session_number, state.carryover_messages)
+        }
+        cli::Commands::Replay {
+            log, speed, step, ..
+        } => {
+            // Replay only reads a log; no agent, safety layer, or signal handling.
+            let log_path = match log {
+                Some(path) => path,
+                None => agent::resume::find_latest_session_log(&config.workspace)?
+                    .ok_or_else(|| anyhow::anyhow!("No session logs found to replay"))?,
+            };
+            return tui::replay::run_replay(&log_path, speed, step).await;
+        }
+        cli::Commands::Snapshots { action, .. } => {
+            return agent::snapshots::run_snapshots_command(&config, action).await;
+        }
+    };
+
+    let safety = SafetyLayer::new(&config)?;
+
+    tracing::info!(
+        model = %config.model,
+        workspace = %safety.workspace_root().display(),
+        timeout_secs = config.shell_timeout_secs,
+        blocklist_patterns = config.blocked_patterns.len(),
+        sandboxed = safety.is_sandboxed(),
+        "Safety layer initialized"
+    );
+
+    // -- Set up two-phase Ctrl+C shutdown (once, shared across sessions)
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_clone = shutdown.clone();
+
+    tokio::spawn(async move {
+        // First Ctrl+C: set graceful shutdown flag.
+        tokio::signal::ctrl_c().await.ok();
+        shutdown_clone.store(true, Ordering::SeqCst);
+        // In headless mode, print message. In TUI mode, the TUI
+        // handles its own quit flow via the 'q' key.
+        if headless {
+            eprintln!(
+                "\nShutting down after current turn... (Ctrl+C again to force quit)"
             );
+        }
+
+        // Second Ctrl+C: force exit.
+        tokio::signal::ctrl_c().await.ok();
+        std::process::exit(1);
+    });
+
+    if headless {
+        // ---- Headless mode: original behavior (no TUI) ----
+        run_headless(&config, &safety, shutdown, session_number, carryover_messages).await?;
+    } else {
+        // ---- TUI mode (default): full dashboard ----
+        tui::runner::run_tui(&config, &safety, shutdown, session_number, carryover_messages)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Headless restart loop: run sessions until shutdown, error, or max restarts.
+///
+/// Starts at `session_number` with `carryover_messages` (empty for a fresh
+/// run, rehydrated history for `ouro resume`).
+async fn run_headless(
+    config: &config::AppConfig,
+    safety: &SafetyLayer,
+    shutdown: Arc<AtomicBool>,
+    mut session_number: u32,
+    mut carryover_messages: Vec<ChatMessage>,
+) -> anyhow::Result<()> {
+    loop {
+        let result = agent::agent_loop::run_agent_session(
+            config,
+            safety,
+            session_number,
+            &carryover_messages,
+            shutdown.clone(),
+            None, // event_tx: no TUI in headless mode
+            None, // pause_flag: no pause in headless mode
+        )
+        .await?;
+
+        match result.shutdown_reason {
+            ShutdownReason::ContextFull {
+                carryover_messages: carry,
+            } => {
+                // Check max_restarts
+                if let Some(max) = config.max_restarts {
+                    if session_number >= max {
+                        eprintln!("Max restarts ({max}) reached. Exiting.");
+                        break;
+                    }
+                }
 
-            // -- Set up two-phase Ctrl+C shutdown (once, shared across sessions)
-            let shutdown = Arc::new(AtomicBool::new(false));
-            let shutdown_clone = shutdown.clone();
-
-            tokio::spawn(async move {
-                // First Ctrl+C: set graceful shutdown flag.
-                tokio::signal::ctrl_c().await.ok();
-                shutdown_clone.store(true, Ordering::SeqCst);
-                // In headless mode, print message. In TUI mode, the TUI
-                // handles its own quit flow via the 'q' key.
-                if headless {
+                // Check auto_restart
+                if !config.auto_restart {
                     eprintln!(
-                        "\nShutting down after current turn... (Ctrl+C again to force quit)"
+                        "Session context full. Auto-restart disabled. \
+                         Press Enter to continue or Ctrl+C to exit."
                     );
+                    let mut input = String::new();
+                    std::io::stdin().read_line(&mut input)?;
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
                 }
 
-                // Second Ctrl+C: force exit.
-                tokio::signal::ctrl_c().await.ok();
-                std::process::exit(1);
-            });
-
-            if headless {
-                // ---- Headless mode: original behavior (no TUI) ----
-                let mut session_number: u32 = 1;
-                let mut carryover_messages: Vec<ChatMessage> = Vec::new();
-
-                loop {
-                    let result = agent::agent_loop::run_agent_session(
-                        &config,
-                        &safety,
-                        session_number,
-                        &carryover_messages,
-                        shutdown.clone(),
-                        None, // event_tx: no TUI in headless mode
-                        None, // pause_flag: no pause in headless mode
-                    )
-                    .await?;
-
-                    match result.shutdown_reason {
-                        ShutdownReason::ContextFull {
-                            carryover_messages: carry,
-                        } => {
-                            // Check max_restarts
-                            if let Some(max) = config.max_restarts {
-                                if session_number >= max {
-                                    eprintln!(
-                                        "Max restarts ({max}) reached. Exiting."
-                                    );
-                                    break;
-                                }
-                            }
-
-                            // Check auto_restart
-                            if !config.auto_restart {
-                                eprintln!(
-                                    "Session context full. Auto-restart disabled. \
-                                     Press Enter to continue or Ctrl+C to exit."
-                                );
-                                let mut input = String::new();
-                                std::io::stdin().read_line(&mut input)?;
-                                if shutdown.load(Ordering::SeqCst) {
-                                    break;
-                                }
-                            }
-
-                            // Check if user triggered shutdown during the session
-                            if shutdown.load(Ordering::SeqCst) {
-                                break;
-                            }
-
-                            session_number += 1;
-                            carryover_messages = carry;
-                            eprintln!(
-                                "\n--- Starting session #{session_number} ---\n"
-                            );
-                        }
-                        ShutdownReason::UserShutdown => {
-                            eprintln!(
-                                "User shutdown. {session_number} session(s) completed."
-                            );
-                            break;
-                        }
-                        ShutdownReason::MaxTurnsOrError(msg) => {
-                            eprintln!("Session ended: {msg}");
-                            break;
-                        }
-                    }
+                // Check if user triggered shutdown during the session
+                if shutdown.load(Ordering::SeqCst) {
+                    break;
                 }
-            } else {
-                // ---- TUI mode (default): full dashboard ----
-                tui::runner::run_tui(&config, &safety, shutdown).await?;
+
+                session_number += 1;
+                carryover_messages = carry;
+                eprintln!("\n--- Starting session #{session_number} ---\n");
+            }
+            ShutdownReason::UserShutdown => {
+                eprintln!("User shutdown. {session_number} session(s) completed.");
+                break;
+            }
+            ShutdownReason::MaxTurnsOrError(msg) => {
+                eprintln!("Session ended: {msg}");
+                break;
+            }
+            ShutdownReason::Completed { .. } => {
+                eprintln!("Session ended: completed.");
+                break;
             }
-        }
-        cli::Commands::Resume { .. } => {
-            eprintln!(
-                "Resume not yet implemented. \
-                 The agent auto-restarts sessions when context fills."
-            );
         }
     }
 
@@ -1,14 +1,51 @@
-use regex::RegexSet;
+use regex::{Regex, RegexSet};
 
 use super::defaults::default_blocklist;
+use super::shell_parser::{invoked_commands, parse_commands, program_name, SimpleCommand};
+use crate::config::BlocklistEntry;
 
 /// Checks commands against a set of blocked patterns.
 ///
-/// Patterns are compiled into a [`RegexSet`] once at construction time,
-/// enabling efficient single-pass matching against all patterns simultaneously.
+/// Raw-string patterns are compiled into a [`RegexSet`] once at construction
+/// time, enabling efficient single-pass matching against all patterns
+/// simultaneously.
+///
+/// Rules targeting programs, arguments, or redirections (see
+/// [`CommandFilter::from_rules`]) are checked against every command the line
+/// would run, found with [`invoked_commands`], so quoting (`s\udo`), paths
+/// (`/usr/bin/sudo`), and wrappers (`env sudo`, `sh -c 'sudo ...'`) do not
+/// hide a program, and a blocked name inside a quoted string
+/// (`echo "don't reboot"`) does not block a harmless command.
+///
+/// In allowlist mode (see [`CommandFilter::with_allowlist`]) a command must
+/// also pass the allowlist: every program it runs -- across pipelines, lists,
+/// subshells, command substitutions, and wrappers -- must be allowed.
 pub struct CommandFilter {
     patterns: RegexSet,
     pattern_reasons: Vec<String>,
+    rules: Vec<CommandRule>,
+    allowlist: Option<Vec<AllowedProgram>>,
+}
+
+/// A blocklist rule matched against each simple command.
+struct CommandRule {
+    /// Anchored, case-insensitive regex on the program name.
+    program: Option<Regex>,
+    /// Regex at least one argument must match.
+    args: Option<Regex>,
+    /// Regex at least one output redirection target must match.
+    redirect: Option<Regex>,
+    reason: String,
+}
+
+/// A program permitted in allowlist mode.
+struct AllowedProgram {
+    /// Program name exactly as written in the command (`python3`, not
+    /// `/usr/bin/python3`, unless that is what is allowed).
+    program: String,
+    /// If set, the program's arguments, joined with single spaces, must
+    /// match this regex.
+    args: Option<Regex>,
 }
 
 /// Information about a blocked command.
@@ -34,16 +71,88 @@ impl CommandFilter {
         Ok(Self {
             patterns: RegexSet::new(&regexes)?,
             pattern_reasons: reasons,
+            rules: Vec::new(),
+            allowlist: None,
         })
     }
 
+    /// Create a filter from configured blocklist rules.
+    ///
+    /// Returns an error if a regex is invalid, a rule sets `pattern` together
+    /// with `program`, `args`, or `redirect`, or a rule sets none of them.
+    pub fn from_rules(entries: &[BlocklistEntry]) -> Result<Self, String> {
+        let mut patterns = Vec::new();
+        let mut rules = Vec::new();
+        for entry in entries {
+            let targeted = entry.program.is_some() || entry.args.is_some() || entry.redirect.is_some();
+            match &entry.pattern {
+                Some(_) if targeted => {
+                    return Err(format!(
+                        "blocklist rule '{}' sets `pattern` together with `program`, `args`, or `redirect`",
+                        entry.reason
+                    ));
+                }
+                Some(pattern) => patterns.push((pattern.clone(), entry.reason.clone())),
+                None if !targeted => {
+                    return Err(format!(
+                        "blocklist rule '{}' needs a `pattern`, `program`, `args`, or `redirect`",
+                        entry.reason
+                    ));
+                }
+                None => {
+                    let compile = |re: &Option<String>| {
+                        re.as_deref()
+                            .map(Regex::new)
+                            .transpose()
+                            .map_err(|e| format!("blocklist rule '{}': {e}", entry.reason))
+                    };
+                    let program = entry.program.as_ref().map(|p| format!("^(?i:{p})$"));
+                    rules.push(CommandRule {
+                        program: compile(&program)?,
+                        args: compile(&entry.args)?,
+                        redirect: compile(&entry.redirect)?,
+                        reason: entry.reason.clone(),
+                    });
+                }
+            }
+        }
+        let mut filter = Self::new(&patterns).map_err(|e| e.to_string())?;
+        filter.rules = rules;
+        Ok(filter)
+    }
+
+    /// Switch to allowlist mode: only the given (program, argument regex)
+    /// pairs may run. The blocklist still applies.
+    ///
+    /// An argument regex must match the whole argument string, as if it
+    /// were wrapped in `^(?:...)$`, so an allowed prefix cannot be followed
+    /// by anything else. A program may be listed more than once; its
+    /// arguments then need to match any one of its regexes. Returns an error
+    /// if a regex is invalid.
+    pub fn with_allowlist(mut self, allowed: &[(String, Option<String>)]) -> Result<Self, regex::Error> {
+        let allowlist = allowed
+            .iter()
+            .map(|(program, args)| {
+                Ok(AllowedProgram {
+                    program: program.clone(),
+                    args: args
+                        .as_deref()
+                        .map(|re| Regex::new(&format!("^(?:{re})$")))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>, regex::Error>>()?;
+        self.allowlist = Some(allowlist);
+        Ok(self)
+    }
+
     /// Create a filter using the default blocklist from [`default_blocklist()`].
     ///
     /// This is a convenience constructor covering privilege escalation,
     /// destructive root operations, system directory writes, disk operations,
     /// fork bombs, system control, and root permission changes.
-    pub fn from_defaults() -> Result<Self, regex::Error> {
-        Self::new(&default_blocklist())
+    pub fn from_defaults() -> Result<Self, String> {
+        Self::from_rules(&default_blocklist())
     }
 
     /// Check if a command is blocked.
@@ -51,17 +160,149 @@ impl CommandFilter {
     /// Returns `Some(BlockedCommand)` with the reason if the command matches
     /// any blocked pattern, or `None` if the command is allowed.
     ///
-    /// Uses [`RegexSet::matches`] for single-pass matching against all patterns.
+    /// Uses [`RegexSet::matches`] for single-pass matching against all
+    /// raw-string patterns, then parses the command once for the rules and
+    /// the allowlist.
     pub fn check(&self, command: &str) -> Option<BlockedCommand> {
-        self.patterns
+        let reason = self
+            .patterns
             .matches(command)
             .into_iter()
             .next()
-            .map(|idx| BlockedCommand {
-                blocked: true,
-                reason: self.pattern_reasons[idx].clone(),
-                command: command.to_string(),
-            })
+            .map(|idx| self.pattern_reasons[idx].clone())
+            .or_else(|| {
+                if self.rules.is_empty() && self.allowlist.is_none() {
+                    return None;
+                }
+                let parsed = parse_commands(command).and_then(invoked_commands);
+                self.check_rules(command, &parsed)
+                    .or_else(|| self.check_allowlist(&parsed))
+            })?;
+        Some(BlockedCommand {
+            blocked: true,
+            reason,
+            command: command.to_string(),
+        })
+    }
+
+    /// The first rule blocking a command the line runs, if any.
+    ///
+    /// If any rule targets programs, a program whose name is only known at
+    /// run time (`$CC`, `$(echo sudo)`, `eval "$x"`, `sh -c "$x"`) is
+    /// blocked, since it could be anything (in allowlist mode, the allowlist
+    /// refuses it instead). A line that cannot be parsed is split on
+    /// whitespace instead and each word checked against the program-only
+    /// rules.
+    fn check_rules(&self, command: &str, parsed: &Result<Vec<SimpleCommand>, String>) -> Option<String> {
+        let checks_programs =
+            self.allowlist.is_none() && self.rules.iter().any(|rule| rule.program.is_some());
+        let commands = match parsed {
+            Ok(commands) => commands,
+            Err(_) => {
+                return command
+                    .split(|c: char| c.is_whitespace() || ";&|()`'\"".contains(c))
+                    .filter(|word| !word.is_empty())
+                    .find_map(|word| {
+                        self.rules
+                            .iter()
+                            .filter(|rule| rule.args.is_none() && rule.redirect.is_none())
+                            .find(|rule| rule.matches_program(word))
+                            .map(|rule| rule.reason.clone())
+                    });
+            }
+        };
+        commands.iter().find_map(|simple| {
+            if checks_programs
+                && let Some(program) = &simple.program
+                && program.has_expansion
+            {
+                return Some(format!(
+                    "Program name '{program}' is only known at run time and cannot be checked against the blocklist"
+                ));
+            }
+            self.rules
+                .iter()
+                .find(|rule| rule.matches(simple))
+                .map(|rule| rule.reason.clone())
+        })
+    }
+
+    /// In allowlist mode, the reason the parsed command may not run, if any.
+    ///
+    /// Commands that cannot be parsed are refused, as are programs whose
+    /// name is only known at run time (`$CMD`, `$(which rm)`).
+    fn check_allowlist(&self, parsed: &Result<Vec<SimpleCommand>, String>) -> Option<String> {
+        let allowlist = self.allowlist.as_ref()?;
+        let commands = match parsed {
+            Ok(commands) => commands,
+            Err(e) => return Some(format!("Command could not be parsed for the allowlist: {e}")),
+        };
+        commands
+            .iter()
+            .find_map(|simple| allowlist_violation(allowlist, simple))
+    }
+}
+
+impl CommandRule {
+    /// Whether every field this rule sets matches `command`.
+    fn matches(&self, command: &SimpleCommand) -> bool {
+        if self.program.is_some() {
+            match &command.program {
+                Some(program) if self.matches_program(&program.text) => {}
+                _ => return false,
+            }
+        }
+        if let Some(args) = &self.args
+            && !command.args.iter().any(|arg| args.is_match(&arg.text))
+        {
+            return false;
+        }
+        if let Some(redirect) = &self.redirect
+            && !command
+                .redirects
+                .iter()
+                .any(|r| (r.op.starts_with('>') || r.op == "<>") && redirect.is_match(&r.target.text))
+        {
+            return false;
+        }
+        true
+    }
+
+    fn matches_program(&self, program: &str) -> bool {
+        self.program
+            .as_ref()
+            .is_some_and(|re| re.is_match(program_name(program)))
+    }
+}
+
+/// Why a simple command is not allowed, if it is not.
+fn allowlist_violation(allowlist: &[AllowedProgram], command: &SimpleCommand) -> Option<String> {
+    let program = command.program.as_ref()?;
+    if program.has_expansion {
+        return Some(format!(
+            "Program name '{program}' is not a literal word (not allowed in allowlist mode)"
+        ));
+    }
+    let entries: Vec<&AllowedProgram> = allowlist
+        .iter()
+        .filter(|entry| entry.program == program.text)
+        .collect();
+    if entries.is_empty() {
+        return Some(format!("Program '{program}' is not in the allowlist"));
+    }
+    let args = command
+        .args
+        .iter()
+        .map(|arg| arg.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let allowed = entries
+        .iter()
+        .any(|entry| entry.args.as_ref().is_none_or(|re| re.is_match(&args)));
+    if allowed {
+        None
+    } else {
+        Some(format!("Arguments not allowed for '{program}': {args}"))
     }
 }
 
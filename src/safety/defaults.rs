@@ -1,33 +1,57 @@
-/// Returns the default blocklist of (pattern, reason) tuples.
-/// This list catches obvious dangerous patterns but is not a security boundary.
+use crate::config::BlocklistEntry;
+use std::path::{Component, Path};
+
+/// Returns the default blocklist rules.
+/// This list catches obvious dangerous commands but is not a security boundary.
 /// The workspace guard (canonical path checking on writes) is the primary defense.
-pub fn default_blocklist() -> Vec<(String, String)> {
+pub fn default_blocklist() -> Vec<BlocklistEntry> {
     vec![
-        // Privilege escalation
-        (r"(?i)\bsudo\b".into(), "Privilege escalation (sudo) not allowed".into()),
-        (r"(?i)\bsu\b\s".into(), "Privilege escalation (su) not allowed".into()),
-        (r"(?i)\bdoas\b".into(), "Privilege escalation (doas) not allowed".into()),
+        // Privilege escalation, including as an argument to a wrapper the
+        // filter does not know (`chroot / sudo ...`)
+        BlocklistEntry::program("sudo", "Privilege escalation (sudo) not allowed"),
+        BlocklistEntry::program("su", "Privilege escalation (su) not allowed"),
+        BlocklistEntry::program("doas", "Privilege escalation (doas) not allowed"),
+        BlocklistEntry::args(r"(?i)^(sudo|su|doas)$", "Privilege escalation (sudo/su/doas) not allowed"),
         // Destructive filesystem operations at root
-        (r"rm\s+(-[^\s]*)?(\s+-[^\s]*)?\s+/($|\s)".into(), "Recursive deletion at root not allowed".into()),
-        (r"rm\s+(-[^\s]*)?(\s+-[^\s]*)?\s+/\*".into(), "Recursive deletion at root not allowed".into()),
+        BlocklistEntry::program("rm", "Recursive deletion at root not allowed").with_args(r"^/\*?$"),
         // System directory writes
-        (r">\s*/etc/".into(), "Write to /etc not allowed".into()),
-        (r">\s*/usr/".into(), "Write to /usr not allowed".into()),
-        (r">\s*/boot/".into(), "Write to /boot not allowed".into()),
-        (r">\s*/sys/".into(), "Write to /sys not allowed".into()),
-        (r">\s*/proc/".into(), "Write to /proc not allowed".into()),
+        BlocklistEntry::redirect(r"^/etc(/|$)", "Write to /etc not allowed"),
+        BlocklistEntry::redirect(r"^/usr(/|$)", "Write to /usr not allowed"),
+        BlocklistEntry::redirect(r"^/boot(/|$)", "Write to /boot not allowed"),
+        BlocklistEntry::redirect(r"^/sys(/|$)", "Write to /sys not allowed"),
+        BlocklistEntry::redirect(r"^/proc(/|$)", "Write to /proc not allowed"),
         // Disk-level destructive operations
-        (r"(?i)\bmkfs\b".into(), "Filesystem formatting not allowed".into()),
-        (r"(?i)\bdd\b\s.*of=/dev/".into(), "Direct device writes not allowed".into()),
+        BlocklistEntry::program(r"mkfs(\..+)?", "Filesystem formatting not allowed"),
+        BlocklistEntry::program("dd", "Direct device writes not allowed").with_args(r"^of=/dev/"),
         // Fork bomb patterns
-        (r":\(\)\s*\{.*\}".into(), "Fork bomb pattern detected".into()),
+        BlocklistEntry::pattern(r":\(\)\s*\{.*\}", "Fork bomb pattern detected"),
         // System shutdown/reboot
-        (r"(?i)\bshutdown\b".into(), "System shutdown not allowed".into()),
-        (r"(?i)\breboot\b".into(), "System reboot not allowed".into()),
-        (r"(?i)\bhalt\b".into(), "System halt not allowed".into()),
-        (r"(?i)\bpoweroff\b".into(), "System poweroff not allowed".into()),
+        BlocklistEntry::program("shutdown", "System shutdown not allowed"),
+        BlocklistEntry::program("reboot", "System reboot not allowed"),
+        BlocklistEntry::program("halt", "System halt not allowed"),
+        BlocklistEntry::program("poweroff", "System poweroff not allowed"),
         // Permission changes at system level
-        (r"chmod\s.*\s/($|\s|[a-z])".into(), "Permission changes at root level not allowed".into()),
-        (r"chown\s.*\s/($|\s|[a-z])".into(), "Ownership changes at root level not allowed".into()),
+        BlocklistEntry::program("chmod", "Permission changes at root level not allowed").with_args(r"^/([a-z]|$)"),
+        BlocklistEntry::program("chown", "Ownership changes at root level not allowed").with_args(r"^/([a-z]|$)"),
     ]
 }
+
+/// Returns the protected paths that are always in force: the workspace config
+/// (which could otherwise widen its own blocklist) and the security log, when
+/// it lives inside the workspace.
+pub fn default_protected_paths(workspace: &Path, security_log_path: &Path) -> Vec<String> {
+    let mut paths = vec!["ouro.toml".to_string()];
+    if let Ok(relative) = security_log_path.strip_prefix(workspace) {
+        let parts: Option<Vec<&str>> = relative
+            .components()
+            .map(|c| match c {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+        if let Some(parts) = parts.filter(|parts| !parts.is_empty()) {
+            paths.push(parts.join("/"));
+        }
+    }
+    paths
+}
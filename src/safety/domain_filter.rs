@@ -0,0 +1,65 @@
+/// Checks URLs against the `[web]` domain allow and deny lists.
+///
+/// A domain entry matches the host itself and any of its subdomains, so
+/// `example.com` covers `docs.example.com` but not `badexample.com`. The deny
+/// list always wins; an empty allow list permits every domain not denied.
+#[derive(Debug, Clone)]
+pub struct DomainFilter {
+    allowed: Vec<String>,
+    blocked: Vec<String>,
+}
+
+impl DomainFilter {
+    /// Create a filter from the configured allow and deny lists.
+    ///
+    /// Entries are normalized to lowercase without a leading `*.` or `.`.
+    pub fn new(allowed: &[String], blocked: &[String]) -> Self {
+        Self {
+            allowed: normalize(allowed),
+            blocked: normalize(blocked),
+        }
+    }
+
+    /// Check if a URL may be fetched.
+    ///
+    /// Returns `Some(reason)` if the URL is rejected, `None` if it is allowed.
+    /// Only `http` and `https` URLs with a host are ever allowed.
+    pub fn check(&self, url: &reqwest::Url) -> Option<String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Some(format!("unsupported URL scheme '{}'", url.scheme()));
+        }
+        let Some(host) = url.host_str() else {
+            return Some("URL has no host".to_string());
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+        if let Some(domain) = self.blocked.iter().find(|d| domain_matches(&host, d)) {
+            return Some(format!("domain '{host}' is blocked by web.blocked_domains ({domain})"));
+        }
+        if !self.allowed.is_empty() && !self.allowed.iter().any(|d| domain_matches(&host, d)) {
+            return Some(format!("domain '{host}' is not in web.allowed_domains"));
+        }
+        None
+    }
+}
+
+fn normalize(domains: &[String]) -> Vec<String> {
+    domains
+        .iter()
+        .map(|d| {
+            d.trim()
+                .trim_start_matches("*.")
+                .trim_matches('.')
+                .to_ascii_lowercase()
+        })
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+/// True if `host` is `domain` or a subdomain of it.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
@@ -1,49 +1,167 @@
 pub mod command_filter;
 pub mod defaults;
+pub mod domain_filter;
+pub mod protected;
+pub mod shell_parser;
 pub mod workspace;
 
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
 use command_filter::{BlockedCommand, CommandFilter};
+use domain_filter::DomainFilter;
+use protected::{ProtectedChange, ProtectedPaths, ProtectedSnapshot};
 use workspace::WorkspaceGuard;
 
-use crate::config::AppConfig;
-use crate::exec::{execute_shell, ExecResult};
+use crate::config::{AppConfig, CommandFilterMode};
+use crate::exec::{
+    execute_shell, fetch_url, ExecResult, ExitHook, FetchResult, JobRegistry, JobStatus, OutputSink,
+    ResourceLimits, Sandbox, WebLimits,
+};
 
 /// Combined safety layer: checks commands against the blocklist, enforces
-/// workspace boundaries, and delegates allowed commands to the shell executor
-/// with timeout enforcement.
+/// workspace boundaries and protected paths, and delegates allowed commands to
+/// the shell executor with timeout and resource-limit enforcement (inside the
+/// namespace sandbox, if enabled and available). Web fetches are likewise checked against the
+/// domain allow/deny lists before being delegated to [`fetch_url`].
 ///
 /// This is the single entry point for all command execution. No code should
-/// call [`execute_shell`] directly -- always go through `SafetyLayer::execute`.
+/// call [`execute_shell`], [`JobRegistry::start`], or [`fetch_url`] directly
+/// -- always go through `SafetyLayer::execute`, `SafetyLayer::start_job`, or
+/// `SafetyLayer::fetch`.
 pub struct SafetyLayer {
     command_filter: CommandFilter,
     workspace_guard: WorkspaceGuard,
+    domain_filter: DomainFilter,
+    web_limits: WebLimits,
     timeout_secs: u64,
+    resource_limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+    /// Why the configured sandbox is not in use, if it is not.
+    sandbox_warning: Option<String>,
+    protected: Arc<ProtectedState>,
+}
+
+/// The protected files as of the last check, shared with the exit hooks of
+/// background jobs, and the security log that records changes to them.
+///
+/// Commands and jobs are checked against this baseline rather than against
+/// a snapshot of their own, so a job exiting hours after it started is not
+/// blamed for (and does not undo) changes checked in the meantime. The
+/// harness's own appends to the security log are recorded in the baseline
+/// as they happen, so they are never taken for tampering.
+///
+/// While no job runs, the baseline is retaken before each command, so edits
+/// made between commands (by the operator, say) are kept. While a job runs,
+/// any change is assumed to be the job's and is undone at the next check.
+struct ProtectedState {
+    paths: ProtectedPaths,
+    root: PathBuf,
     security_log_path: PathBuf,
+    baseline: Mutex<ProtectedSnapshot>,
+    /// Background jobs that have not exited yet.
+    running_jobs: AtomicUsize,
 }
 
 impl SafetyLayer {
     /// Build a SafetyLayer from the resolved application configuration.
     ///
-    /// Constructs the [`CommandFilter`] from `config.blocked_patterns` and the
-    /// [`WorkspaceGuard`] from `config.workspace`. Stores timeout and security
-    /// log path for runtime use.
+    /// Constructs the [`CommandFilter`] from `config.blocked_patterns` (plus
+    /// `config.allowed_commands` in allowlist mode), the
+    /// [`WorkspaceGuard`] from `config.workspace` and `config.protected_paths`,
+    /// and the [`DomainFilter`] and
+    /// fetch limits from the `web_*` settings. Stores timeout, resource limits
+    /// (`limit_*`), and security log path for runtime use.
+    ///
+    /// With `sandbox_enabled`, the [`Sandbox`] is probed once here. If this
+    /// host cannot create the namespaces, commands run unsandboxed and
+    /// [`SafetyLayer::sandbox_warning`] says so.
     pub fn new(config: &AppConfig) -> anyhow::Result<Self> {
-        let command_filter = CommandFilter::new(&config.blocked_patterns)
-            .map_err(|e| anyhow::anyhow!("Failed to compile command filter patterns: {}", e))?;
+        Self::build(config, None)
+    }
+
+    /// Build the SafetyLayer of a sub-agent working in `config.workspace`,
+    /// a directory inside this layer's workspace.
+    ///
+    /// Protected paths stay those of this layer, relative to its workspace
+    /// root, and the two layers share one baseline of the protected files.
+    /// A sub-agent's shell command that changes a protected file anywhere
+    /// in the top-level workspace (`../ouro.toml`, say) is undone like the
+    /// parent's own would be.
+    pub fn for_subdirectory(&self, config: &AppConfig) -> anyhow::Result<Self> {
+        Self::build(config, Some(self.protected.clone()))
+    }
+
+    fn build(config: &AppConfig, shared: Option<Arc<ProtectedState>>) -> anyhow::Result<Self> {
+        let mut command_filter = CommandFilter::from_rules(&config.blocked_patterns)
+            .map_err(|e| anyhow::anyhow!("Failed to compile command filter rules: {}", e))?;
+        if config.command_filter_mode == CommandFilterMode::Allowlist {
+            command_filter = command_filter
+                .with_allowlist(&config.allowed_commands)
+                .map_err(|e| anyhow::anyhow!("Failed to compile allowed command arguments: {}", e))?;
+        }
 
         let workspace_guard = WorkspaceGuard::new(&config.workspace)
             .map_err(|e| anyhow::anyhow!("Failed to initialize workspace guard: {}", e))?;
+        let protected = match shared {
+            Some(protected) => protected,
+            None => Arc::new(ProtectedState {
+                paths: ProtectedPaths::new(&config.protected_paths)
+                    .map_err(|e| anyhow::anyhow!("Failed to compile protected paths: {}", e))?,
+                root: workspace_guard.canonical_root().to_path_buf(),
+                security_log_path: config.security_log_path.clone(),
+                baseline: Mutex::new(ProtectedSnapshot::default()),
+                running_jobs: AtomicUsize::new(0),
+            }),
+        };
+        let workspace_guard = workspace_guard
+            .with_protected(protected.paths.clone())
+            .with_protected_root(&protected.root);
+
+        let domain_filter =
+            DomainFilter::new(&config.web_allowed_domains, &config.web_blocked_domains);
+
+        let (sandbox, sandbox_warning) = if config.sandbox_enabled {
+            let sandbox = Sandbox::new(workspace_guard.canonical_root(), config.sandbox_network);
+            match sandbox.probe() {
+                Ok(()) => (Some(sandbox), None),
+                Err(e) => {
+                    let warning = format!(
+                        "Shell sandbox is enabled but unavailable on this host ({e}). \
+                         Commands will run UNSANDBOXED, with full filesystem and network access."
+                    );
+                    tracing::warn!("{warning}");
+                    (None, Some(warning))
+                }
+            }
+        } else {
+            (None, None)
+        };
 
         Ok(Self {
             command_filter,
             workspace_guard,
+            domain_filter,
+            web_limits: WebLimits {
+                max_bytes: config.web_max_bytes,
+                max_redirects: config.web_max_redirects,
+                timeout_secs: config.web_timeout_secs,
+            },
             timeout_secs: config.shell_timeout_secs,
-            security_log_path: config.security_log_path.clone(),
+            resource_limits: ResourceLimits {
+                memory_bytes: config.limit_memory_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+                cpu_secs: config.limit_cpu_secs,
+                file_size_bytes: config.limit_file_size_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+                processes: config.limit_max_processes,
+                open_files: config.limit_max_open_files,
+            },
+            sandbox,
+            sandbox_warning,
+            protected,
         })
     }
 
@@ -52,8 +170,18 @@ impl SafetyLayer {
     /// 1. Check command against the blocklist.
     /// 2. If blocked: log to security file, return an [`ExecResult`] with the
     ///    blocked JSON in `stderr` and `exit_code` 126 ("cannot execute").
-    /// 3. If allowed: delegate to [`execute_shell`] with workspace root and timeout.
-    pub async fn execute(&self, command: &str) -> anyhow::Result<ExecResult> {
+    /// 3. If allowed: delegate to [`execute_shell`] with workspace root, timeout,
+    ///    resource limits, and sandbox.
+    /// 4. Restore any protected file the command modified, deleted, or created,
+    ///    log it to the security file, and note it in the result's `stderr`.
+    ///
+    /// If `output` is given, the command's output is also sent to it line by
+    /// line while the command runs. Blocked commands send nothing.
+    pub async fn execute(
+        &self,
+        command: &str,
+        output: Option<OutputSink>,
+    ) -> anyhow::Result<ExecResult> {
         // Step 1: Check against blocklist.
         if let Some(blocked) = self.command_filter.check(command) {
             // Log the blocked command to the security log.
@@ -65,11 +193,116 @@ impl SafetyLayer {
                 stderr: blocked.to_json(),
                 exit_code: Some(126), // standard "cannot execute" code
                 timed_out: false,
+                killed_by: None,
+            });
+        }
+
+        // Step 2: Execute allowed command in workspace with timeout, with
+        // protected files checked beforehand.
+        let root = self.workspace_guard.canonical_root();
+        let background_note = self.on_protected(ProtectedState::prepare).await?.map(|summary| {
+            format!("[harness] A background job changed protected files; restored: {summary}")
+        });
+        let mut result = execute_shell(
+            command,
+            root,
+            self.timeout_secs,
+            &self.resource_limits,
+            self.sandbox.as_ref(),
+            output,
+        )
+        .await?;
+
+        // Step 3: Undo changes to protected files.
+        let command = command.to_string();
+        let note = self
+            .on_protected(move |protected| protected.restore(&command))
+            .await?
+            .map(|summary| {
+                format!("[harness] Protected files may not be modified; restored: {summary}")
+            });
+        for note in background_note.into_iter().chain(note) {
+            if !result.stderr.is_empty() && !result.stderr.ends_with('\n') {
+                result.stderr.push('\n');
+            }
+            result.stderr.push_str(&note);
+            result.stderr.push('\n');
+        }
+        Ok(result)
+    }
+
+    /// Start a background job through the safety pipeline.
+    ///
+    /// The command is checked like [`SafetyLayer::execute`]; a blocked one is
+    /// logged and returned as an error. An allowed one is started in `jobs`
+    /// with the same resource limits and sandbox but no timeout. Changes to
+    /// protected files since the last check are undone (and noted in the
+    /// job's output) when the job's shell exits.
+    pub async fn start_job(&self, jobs: &JobRegistry, command: &str) -> anyhow::Result<JobStatus> {
+        if let Some(blocked) = self.command_filter.check(command) {
+            self.log_blocked_command(&blocked);
+            anyhow::bail!("blocked: {}", blocked.reason);
+        }
+
+        // Anything a running job changed is logged here; the new job has no
+        // output to note it in yet.
+        self.on_protected(ProtectedState::prepare).await?;
+        let on_exit: Option<ExitHook> = (!self.protected.paths.is_empty()).then(|| {
+            self.protected.running_jobs.fetch_add(1, Ordering::SeqCst);
+            let protected = self.protected.clone();
+            let command = command.to_string();
+            Box::new(move || {
+                let summary = protected.restore(&command);
+                protected.running_jobs.fetch_sub(1, Ordering::SeqCst);
+                summary.map(|summary| {
+                    format!("[harness] Protected files may not be modified; restored: {summary}")
+                })
+            }) as ExitHook
+        });
+        let has_hook = on_exit.is_some();
+        let root = self.workspace_guard.canonical_root();
+        let started = jobs.start(command, root, &self.resource_limits, self.sandbox.as_ref(), on_exit);
+        if started.is_err() && has_hook {
+            self.protected.running_jobs.fetch_sub(1, Ordering::SeqCst);
+        }
+        started
+    }
+
+    /// Whether shell commands run inside the namespace sandbox.
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandbox.is_some()
+    }
+
+    /// Set when the sandbox is enabled in the config but could not be used,
+    /// so commands run unsandboxed. Meant to be shown prominently.
+    pub fn sandbox_warning(&self) -> Option<&str> {
+        self.sandbox_warning.as_deref()
+    }
+
+    /// The protected-path pattern covering `path`, if writes to it are refused.
+    pub fn protected_pattern(&self, path: &Path) -> Option<&str> {
+        self.workspace_guard.protected_pattern(path)
+    }
+
+    /// Fetch a URL through the safety pipeline.
+    ///
+    /// 1. Check the URL against the domain allow/deny lists.
+    /// 2. If rejected: log to security file and return an error.
+    /// 3. If allowed: delegate to [`fetch_url`], which re-checks every redirect.
+    pub async fn fetch(&self, url: &str) -> anyhow::Result<FetchResult> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| anyhow::anyhow!("invalid URL '{}': {}", url, e))?;
+
+        if let Some(reason) = self.domain_filter.check(&parsed) {
+            self.log_blocked_command(&BlockedCommand {
+                blocked: true,
+                reason: reason.clone(),
+                command: format!("web_fetch {url}"),
             });
+            anyhow::bail!("blocked: {}", reason);
         }
 
-        // Step 2: Execute allowed command in workspace with timeout.
-        execute_shell(command, self.workspace_guard.canonical_root(), self.timeout_secs).await
+        fetch_url(url, &self.domain_filter, &self.web_limits).await
     }
 
     /// Get the canonical workspace root path.
@@ -77,46 +310,130 @@ impl SafetyLayer {
         self.workspace_guard.canonical_root()
     }
 
+    /// Run `f` on the protected-file state on a blocking thread, since
+    /// checking and restoring protected files reads and rewrites them.
+    async fn on_protected<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&ProtectedState) -> T + Send + 'static,
+    ) -> anyhow::Result<T> {
+        let protected = self.protected.clone();
+        Ok(tokio::task::spawn_blocking(move || f(&protected)).await?)
+    }
+
     /// Append a JSON line to the security log for a blocked command.
-    ///
-    /// Each entry is a single JSON line with timestamp, blocked flag, reason, and command.
-    /// Uses [`std::time::SystemTime`] for timestamps (no chrono dependency).
-    /// If the log file cannot be written, a warning is logged via tracing but the
-    /// command check is not affected.
     fn log_blocked_command(&self, blocked: &BlockedCommand) {
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
-        let log_entry = format!(
-            "{{\"timestamp\":{},\"blocked\":true,\"reason\":{},\"command\":{}}}\n",
-            timestamp,
-            serde_json::to_string(&blocked.reason).unwrap_or_else(|_| "\"unknown\"".into()),
-            serde_json::to_string(&blocked.command).unwrap_or_else(|_| "\"unknown\"".into()),
+        self.protected.log(blocked);
+    }
+}
+
+impl ProtectedState {
+    /// Bring the baseline up to date before a command or job starts. While
+    /// a job runs, changes since the last check are undone as the job's,
+    /// and a summary of them is returned.
+    fn prepare(&self) -> Option<String> {
+        if self.paths.is_empty() {
+            return None;
+        }
+        if self.running_jobs.load(Ordering::SeqCst) > 0 {
+            return self.restore("(background job)");
+        }
+        let mut baseline = self.baseline.lock().unwrap();
+        *baseline = self.paths.snapshot(&self.root, &baseline);
+        None
+    }
+
+    /// Undo any change to protected files since the last check, logging it
+    /// to the security log against `command`. Returns a summary of the
+    /// changes, if there were any.
+    fn restore(&self, command: &str) -> Option<String> {
+        if self.paths.is_empty() {
+            return None;
+        }
+        let mut baseline = self.baseline.lock().unwrap();
+        let changes = baseline.restore_changes(&self.paths, &self.root);
+        if changes.is_empty() {
+            return None;
+        }
+        let summary = summarize_changes(&changes);
+        append_security_log(
+            &self.security_log_path,
+            &BlockedCommand {
+                blocked: true,
+                reason: format!("Changed protected files, restored: {summary}"),
+                command: command.to_string(),
+            },
         );
+        // Record the restored files and the log entry just written.
+        *baseline = self.paths.snapshot(&self.root, &baseline);
+        Some(summary)
+    }
 
-        match OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.security_log_path)
-        {
-            Ok(mut file) => {
-                if let Err(e) = file.write_all(log_entry.as_bytes()) {
-                    tracing::warn!(
-                        "Failed to write to security log at {}: {}",
-                        self.security_log_path.display(),
-                        e
-                    );
-                }
+    /// Append a JSON line to the security log, recording the log's new
+    /// contents in the baseline if it is protected.
+    fn log(&self, blocked: &BlockedCommand) {
+        let mut baseline = self.baseline.lock().unwrap();
+        append_security_log(&self.security_log_path, blocked);
+        if !self.paths.is_empty() {
+            baseline.update_file(&self.paths, &self.root, &self.security_log_path);
+        }
+    }
+}
+
+/// One `path (change)` entry per changed protected file.
+fn summarize_changes(changes: &[ProtectedChange]) -> String {
+    changes
+        .iter()
+        .map(|c| {
+            if c.restored {
+                format!("{} ({})", c.path, c.change)
+            } else {
+                format!("{} ({}; too large to restore)", c.path, c.change)
             }
-            Err(e) => {
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Append a JSON line to the security log at `security_log_path` for a
+/// blocked command.
+///
+/// Each entry is a single JSON line with timestamp, blocked flag, reason, and command.
+/// Uses [`std::time::SystemTime`] for timestamps (no chrono dependency).
+/// If the log file cannot be written, a warning is logged via tracing but the
+/// command check is not affected.
+fn append_security_log(security_log_path: &Path, blocked: &BlockedCommand) {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let log_entry = format!(
+        "{{\"timestamp\":{},\"blocked\":true,\"reason\":{},\"command\":{}}}\n",
+        timestamp,
+        serde_json::to_string(&blocked.reason).unwrap_or_else(|_| "\"unknown\"".into()),
+        serde_json::to_string(&blocked.command).unwrap_or_else(|_| "\"unknown\"".into()),
+    );
+
+    match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(security_log_path)
+    {
+        Ok(mut file) => {
+            if let Err(e) = file.write_all(log_entry.as_bytes()) {
                 tracing::warn!(
-                    "Failed to open security log at {}: {}",
-                    self.security_log_path.display(),
+                    "Failed to write to security log at {}: {}",
+                    security_log_path.display(),
                     e
                 );
             }
         }
+        Err(e) => {
+            tracing::warn!(
+                "Failed to open security log at {}: {}",
+                security_log_path.display(),
+                e
+            );
+        }
     }
 }
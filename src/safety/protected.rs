@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use regex::RegexSet;
+
+/// Workspace files the agent may read but never overwrite or delete.
+///
+/// Patterns are globs relative to the workspace root, matched against
+/// `/`-separated relative paths:
+///
+/// - `*` matches within one path component, `?` matches one character
+/// - `**` matches any number of components, so `archive/**` covers everything
+///   under `archive/` (and `archive` itself) and `**/*.lock` matches at any depth
+/// - A pattern without wildcards names a single path (`ouro.toml` is only the
+///   workspace's top-level `ouro.toml`)
+///
+/// `file_write` refuses protected paths outright. Shell commands can write
+/// anywhere, so [`ProtectedPaths::snapshot`] records protected files before a
+/// command runs and [`ProtectedSnapshot::restore_changes`] puts them back
+/// afterwards.
+///
+/// Changes are detected from file metadata, so checking unchanged files is
+/// cheap. Contents are kept only for files up to [`RESTORE_MAX_BYTES`]; a
+/// change to a larger file is reported and logged but cannot be undone.
+#[derive(Debug, Clone)]
+pub struct ProtectedPaths {
+    patterns: Vec<String>,
+    set: RegexSet,
+}
+
+/// Largest protected file whose contents are kept so a change can be undone.
+pub const RESTORE_MAX_BYTES: u64 = 1024 * 1024;
+
+/// A protected file a shell command changed, and what the harness did about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectedChange {
+    /// Path relative to the workspace root.
+    pub path: String,
+    /// "modified", "deleted", or "created".
+    pub change: &'static str,
+    /// False if the file was larger than [`RESTORE_MAX_BYTES`] and so could
+    /// not be put back.
+    pub restored: bool,
+}
+
+/// Every protected file at one point in time.
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedSnapshot {
+    files: BTreeMap<String, ProtectedFile>,
+}
+
+#[derive(Debug, Clone)]
+struct ProtectedFile {
+    fingerprint: Fingerprint,
+    /// `None` for files over [`RESTORE_MAX_BYTES`].
+    contents: Option<Arc<[u8]>>,
+}
+
+/// Metadata that changes whenever a file is written or replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    len: u64,
+    inode: u64,
+    mtime: (i64, i64),
+    ctime: (i64, i64),
+}
+
+impl Fingerprint {
+    fn of(meta: &std::fs::Metadata) -> Self {
+        Self {
+            len: meta.len(),
+            inode: meta.ino(),
+            mtime: (meta.mtime(), meta.mtime_nsec()),
+            ctime: (meta.ctime(), meta.ctime_nsec()),
+        }
+    }
+}
+
+impl ProtectedPaths {
+    /// Compile the glob patterns. Returns an error if a pattern is empty or
+    /// not relative to the workspace.
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut regexes = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            if pattern.trim().is_empty() || pattern.starts_with('/') || pattern.contains("..") {
+                return Err(format!(
+                    "protected path '{pattern}' must be a non-empty glob relative to the workspace"
+                ));
+            }
+            regexes.push(glob_to_regex(pattern));
+        }
+        let set = RegexSet::new(&regexes).map_err(|e| e.to_string())?;
+        Ok(Self {
+            patterns: patterns.to_vec(),
+            set,
+        })
+    }
+
+    /// No protected paths.
+    pub fn none() -> Self {
+        Self::new(&[]).expect("empty pattern list compiles")
+    }
+
+    /// Whether any patterns are configured.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The first pattern matching `relative` (a `/`-separated path relative
+    /// to the workspace root), if any.
+    pub fn matching_pattern(&self, relative: &str) -> Option<&str> {
+        self.set
+            .matches(relative)
+            .into_iter()
+            .next()
+            .map(|idx| self.patterns[idx].as_str())
+    }
+
+    /// The pattern protecting `path`, if any. `path` must be canonical (or
+    /// have a canonical parent); paths outside `root` are never protected.
+    pub fn protecting(&self, root: &Path, path: &Path) -> Option<&str> {
+        let relative = path.strip_prefix(root).ok()?;
+        self.matching_pattern(&slash_path(relative))
+    }
+
+    /// Record every protected file under `root`, reading the contents of
+    /// those up to [`RESTORE_MAX_BYTES`]. Contents are taken from `previous`
+    /// for files that have not changed since, so only new and changed files
+    /// are read.
+    pub fn snapshot(&self, root: &Path, previous: &ProtectedSnapshot) -> ProtectedSnapshot {
+        let files = self
+            .scan(root)
+            .into_iter()
+            .map(|(path, fingerprint)| {
+                let file = match previous.files.get(&path) {
+                    Some(old) if old.fingerprint == fingerprint => old.clone(),
+                    _ => ProtectedFile::read(&root.join(&path), fingerprint),
+                };
+                (path, file)
+            })
+            .collect();
+        ProtectedSnapshot { files }
+    }
+
+    /// Fingerprint every protected file under `root`.
+    ///
+    /// Only the directories a pattern can reach are walked (`archive/**`
+    /// walks `archive/`, `ouro.toml` looks at one file), and symlinked
+    /// directories are not followed.
+    fn scan(&self, root: &Path) -> BTreeMap<String, Fingerprint> {
+        let mut files = BTreeMap::new();
+        let mut visited: Vec<PathBuf> = Vec::new();
+        for pattern in &self.patterns {
+            let base = root.join(literal_prefix(pattern));
+            if visited.iter().any(|dir| base.starts_with(dir)) {
+                continue;
+            }
+            self.collect(root, &base, &mut files);
+            visited.push(base);
+        }
+        files
+    }
+
+    fn collect(&self, root: &Path, path: &Path, files: &mut BTreeMap<String, Fingerprint>) {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        if meta.is_dir() {
+            let Ok(entries) = std::fs::read_dir(path) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                self.collect(root, &entry.path(), files);
+            }
+        } else if meta.is_file()
+            && let Ok(relative) = path.strip_prefix(root)
+        {
+            let relative = slash_path(relative);
+            if self.matching_pattern(&relative).is_some() {
+                files.insert(relative, Fingerprint::of(&meta));
+            }
+        }
+    }
+}
+
+impl ProtectedFile {
+    fn read(path: &Path, fingerprint: Fingerprint) -> Self {
+        let contents = if fingerprint.len > RESTORE_MAX_BYTES {
+            None
+        } else {
+            std::fs::read(path).ok().map(Arc::from)
+        };
+        Self { fingerprint, contents }
+    }
+}
+
+impl ProtectedSnapshot {
+    /// Re-record the file at `path` after the harness itself wrote to it.
+    /// Does nothing unless `path` is a protected file under `root`.
+    pub fn update_file(&mut self, protected: &ProtectedPaths, root: &Path, path: &Path) {
+        let Some(relative) = std::fs::canonicalize(path)
+            .ok()
+            .and_then(|path| path.strip_prefix(root).ok().map(slash_path))
+        else {
+            return;
+        };
+        if protected.matching_pattern(&relative).is_none() {
+            return;
+        }
+        let target = root.join(&relative);
+        match std::fs::symlink_metadata(&target) {
+            Ok(meta) if meta.is_file() => {
+                let file = ProtectedFile::read(&target, Fingerprint::of(&meta));
+                self.files.insert(relative, file);
+            }
+            _ => {
+                self.files.remove(&relative);
+            }
+        }
+    }
+
+    /// Compare the protected files under `root` with this snapshot and undo
+    /// any change: modified and deleted files are rewritten, new files are
+    /// removed. Files whose contents were too large to keep are left as they
+    /// are. Returns what was changed.
+    pub fn restore_changes(&self, protected: &ProtectedPaths, root: &Path) -> Vec<ProtectedChange> {
+        let current = protected.scan(root);
+        let mut changes = Vec::new();
+        for (path, file) in &self.files {
+            let target = root.join(path);
+            let change = match current.get(path) {
+                Some(now) if *now == file.fingerprint => continue,
+                // Touched but rewritten with the same contents.
+                Some(_) if file
+                    .contents
+                    .as_deref()
+                    .is_some_and(|contents| std::fs::read(&target).is_ok_and(|now| now == contents)) =>
+                {
+                    continue;
+                }
+                Some(_) => "modified",
+                None => "deleted",
+            };
+            let Some(contents) = &file.contents else {
+                tracing::warn!(
+                    "Protected file {} was {} but is over {} bytes and cannot be restored",
+                    path,
+                    change,
+                    RESTORE_MAX_BYTES
+                );
+                changes.push(ProtectedChange {
+                    path: path.clone(),
+                    change,
+                    restored: false,
+                });
+                continue;
+            };
+            if let Some(parent) = target.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            // Replace rather than write through, in case the command swapped
+            // the file for a symlink.
+            let _ = std::fs::remove_file(&target);
+            if let Err(e) = std::fs::write(&target, contents) {
+                tracing::warn!("Failed to restore protected file {}: {}", target.display(), e);
+            }
+            changes.push(ProtectedChange {
+                path: path.clone(),
+                change,
+                restored: true,
+            });
+        }
+        for path in current.keys().filter(|p| !self.files.contains_key(*p)) {
+            if let Err(e) = std::fs::remove_file(root.join(path)) {
+                tracing::warn!("Failed to remove new protected file {}: {}", path, e);
+            }
+            changes.push(ProtectedChange {
+                path: path.clone(),
+                change: "created",
+                restored: true,
+            });
+        }
+        changes
+    }
+}
+
+/// Translate a workspace glob into an anchored regex.
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let at_start = i == 0 || chars[i - 1] == '/';
+                let at_end = i + 2 == chars.len();
+                if at_start && chars.get(i + 2) == Some(&'/') {
+                    // `**/` -- zero or more leading directories.
+                    regex.push_str("(?:.*/)?");
+                    i += 3;
+                    continue;
+                }
+                if at_end && i > 0 && chars[i - 1] == '/' {
+                    // `dir/**` -- the directory and everything under it.
+                    regex.pop(); // the `/` already pushed
+                    regex.push_str("(?:/.*)?");
+                } else {
+                    regex.push_str(".*");
+                }
+                i += 2;
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    regex.push('$');
+    regex
+}
+
+/// Leading path components of a glob that contain no wildcards.
+fn literal_prefix(pattern: &str) -> PathBuf {
+    pattern
+        .split('/')
+        .take_while(|part| !part.contains(['*', '?']))
+        .collect()
+}
+
+/// Render a relative path with `/` separators.
+fn slash_path(relative: &Path) -> String {
+    relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
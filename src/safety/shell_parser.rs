@@ -0,0 +1,704 @@
+//! A POSIX sh parser that finds every simple command in a command line.
+//!
+//! `sh -c` runs far more than the first word of a string: pipelines, lists
+//! (`&&`, `||`, `;`, `&`), subshells, command substitutions (`$(...)` and
+//! backticks), process substitutions, and expansions in here-documents all
+//! run programs of their own. [`parse_commands`] walks the whole line and
+//! returns each simple command with its program, arguments, and redirections,
+//! so the command filter can judge every program that would run.
+//! [`invoked_commands`] goes one step further and adds the commands those
+//! programs run in turn: `env sudo ...`, `xargs rm`, `sh -c '...'`, `eval`.
+//!
+//! It is a parser for checking, not for execution: words are unquoted but not
+//! expanded. A word containing a parameter, command, or arithmetic expansion
+//! is flagged so callers can treat its value as unknown.
+
+use std::fmt;
+
+/// One word of a command, with quotes and escapes removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// The word after quote removal. Expansions are kept as written.
+    pub text: String,
+    /// Whether any part of the word was quoted or escaped.
+    pub quoted: bool,
+    /// Whether the word contains `$`-expansions or backticks, so its value
+    /// is only known at run time.
+    pub has_expansion: bool,
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A redirection such as `> out.txt`, `2>&1`, or `<<EOF`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// Explicit file descriptor (`2` in `2>`), if given.
+    pub fd: Option<u32>,
+    /// The operator: `<`, `>`, `>>`, `>|`, `<>`, `<&`, `>&`, `<<`, or `<<-`.
+    pub op: String,
+    /// The target file, descriptor, or here-document delimiter.
+    pub target: Word,
+}
+
+/// A simple command: assignments, a program, its arguments, and redirections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// Leading `NAME=value` words.
+    pub assignments: Vec<Word>,
+    /// The program to run; `None` for a bare assignment or redirection.
+    pub program: Option<Word>,
+    pub args: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+impl SimpleCommand {
+    fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+            && self.program.is_none()
+            && self.args.is_empty()
+            && self.redirects.is_empty()
+    }
+}
+
+/// Words that start or continue a compound command instead of naming a
+/// program when they appear unquoted in command position.
+const RESERVED_WORDS: &[&str] = &[
+    "!", "{", "}", "if", "then", "else", "elif", "fi", "while", "until", "do", "done", "time",
+];
+
+/// Parse a command line into the simple commands it would run, outermost
+/// first within each list, nested substitutions included.
+///
+/// Returns an error for unterminated quotes or substitutions, unbalanced
+/// parentheses, and constructs this parser does not model (`case`, function
+/// definitions).
+pub fn parse_commands(input: &str) -> Result<Vec<SimpleCommand>, String> {
+    let mut parser = Parser::new(input);
+    parser.parse_list(None)?;
+    Ok(parser.commands)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    commands: Vec<SimpleCommand>,
+    /// Here-documents whose bodies start after the next newline:
+    /// (delimiter, strip leading tabs, body expands).
+    pending_heredocs: Vec<(String, bool, bool)>,
+}
+
+/// What a word in command position turned out to be.
+enum CommandWord {
+    Assignment,
+    Reserved,
+    Program,
+}
+
+impl Parser {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            pos: 0,
+            commands: Vec::new(),
+            pending_heredocs: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn finish(&mut self, current: &mut SimpleCommand) {
+        let command = std::mem::take(current);
+        if !command.is_empty() {
+            self.commands.push(command);
+        }
+    }
+
+    /// Parse a command list until end of input or, inside a subshell or
+    /// `$(...)`, the closing parenthesis (which is consumed).
+    fn parse_list(&mut self, closing: Option<char>) -> Result<(), String> {
+        let mut current = SimpleCommand::default();
+        // Inside `for NAME in WORDS`, words up to the next separator are data.
+        let mut skipping_clause = false;
+
+        loop {
+            let Some(c) = self.peek() else {
+                self.finish(&mut current);
+                return match closing {
+                    Some(close) => Err(format!("unterminated subshell: missing '{close}'")),
+                    None => Ok(()),
+                };
+            };
+            match c {
+                ' ' | '\t' => self.pos += 1,
+                '\\' if self.peek_at(1) == Some('\n') => self.pos += 2,
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                '\n' => {
+                    self.pos += 1;
+                    self.finish(&mut current);
+                    skipping_clause = false;
+                    self.read_heredoc_bodies()?;
+                }
+                ';' | '&' | '|' => {
+                    self.pos += 1;
+                    if c == ';' && self.peek() == Some(';') {
+                        return Err("unexpected ';;' outside a case statement".into());
+                    }
+                    // `&&`, `||`, `|&`
+                    if matches!(self.peek(), Some('&' | '|')) && c != ';' {
+                        self.pos += 1;
+                    }
+                    self.finish(&mut current);
+                    skipping_clause = false;
+                }
+                '(' => {
+                    if current.program.is_some() {
+                        return Err("function definitions are not supported".into());
+                    }
+                    self.pos += 1;
+                    self.parse_list(Some(')'))?;
+                }
+                ')' => {
+                    if closing == Some(')') {
+                        self.pos += 1;
+                        self.finish(&mut current);
+                        return Ok(());
+                    }
+                    return Err("unexpected ')'".into());
+                }
+                '<' | '>' if self.peek_at(1) == Some('(') => {
+                    // Process substitution: runs a command, used as a file name.
+                    self.pos += 2;
+                    self.parse_list(Some(')'))?;
+                    let word = Word {
+                        text: format!("{c}(...)"),
+                        quoted: false,
+                        has_expansion: true,
+                    };
+                    if !skipping_clause {
+                        self.add_word(&mut current, word);
+                    }
+                }
+                '<' | '>' => {
+                    let redirect = self.read_redirect(None)?;
+                    current.redirects.push(redirect);
+                }
+                _ => {
+                    if let Some(fd) = self.io_number() {
+                        let redirect = self.read_redirect(Some(fd))?;
+                        current.redirects.push(redirect);
+                        continue;
+                    }
+                    let word = self.read_word()?;
+                    if skipping_clause {
+                        continue;
+                    }
+                    if current.program.is_none() && !word.quoted {
+                        match word.text.as_str() {
+                            "case" => return Err("case statements are not supported".into()),
+                            "for" | "select" => {
+                                skipping_clause = true;
+                                continue;
+                            }
+                            _ => {}
+                        }
+                    }
+                    self.add_word(&mut current, word);
+                }
+            }
+        }
+    }
+
+    /// Place a word in the command being built.
+    fn add_word(&mut self, current: &mut SimpleCommand, word: Word) {
+        if current.program.is_some() {
+            current.args.push(word);
+            return;
+        }
+        match classify(&word) {
+            CommandWord::Assignment => current.assignments.push(word),
+            // A reserved word ends any pending assignments-only command.
+            CommandWord::Reserved => self.finish(current),
+            CommandWord::Program => current.program = Some(word),
+        }
+    }
+
+    /// A run of digits immediately followed by `<` or `>` is a file
+    /// descriptor number (`2>`); returns it and leaves `pos` on the operator.
+    fn io_number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let mut end = start;
+        while self.chars.get(end).is_some_and(|c| c.is_ascii_digit()) {
+            end += 1;
+        }
+        if end == start || !matches!(self.chars.get(end), Some('<' | '>')) {
+            return None;
+        }
+        let fd = self.chars[start..end].iter().collect::<String>().parse().ok()?;
+        self.pos = end;
+        Some(fd)
+    }
+
+    /// Read a redirection operator and its target word.
+    fn read_redirect(&mut self, fd: Option<u32>) -> Result<Redirect, String> {
+        let first = self.peek().unwrap_or_default();
+        self.pos += 1;
+        let mut op = first.to_string();
+        match (first, self.peek()) {
+            ('>', Some(c @ ('>' | '|' | '&'))) | ('<', Some(c @ ('>' | '&'))) => {
+                op.push(c);
+                self.pos += 1;
+            }
+            ('<', Some('<')) => {
+                op.push('<');
+                self.pos += 1;
+                if self.peek() == Some('-') {
+                    op.push('-');
+                    self.pos += 1;
+                }
+            }
+            _ => {}
+        }
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+        if self.peek().is_none_or(is_metachar) {
+            return Err(format!("missing target after '{op}'"));
+        }
+        let target = self.read_word()?;
+        if op.starts_with("<<") {
+            self.pending_heredocs
+                .push((target.text.clone(), op == "<<-", !target.quoted));
+        }
+        Ok(Redirect { fd, op, target })
+    }
+
+    /// Consume the bodies of pending here-documents, which start at the
+    /// current position (just after a newline).
+    fn read_heredoc_bodies(&mut self) -> Result<(), String> {
+        for (delimiter, strip_tabs, expands) in std::mem::take(&mut self.pending_heredocs) {
+            let mut body = String::new();
+            loop {
+                if self.peek().is_none() {
+                    return Err(format!("unterminated here-document (missing '{delimiter}')"));
+                }
+                let mut line = String::new();
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    if c == '\n' {
+                        break;
+                    }
+                    line.push(c);
+                }
+                let line = if strip_tabs { line.trim_start_matches('\t') } else { &line };
+                if line == delimiter {
+                    break;
+                }
+                body.push_str(line);
+                body.push('\n');
+            }
+            if expands {
+                // An unquoted delimiter means the body undergoes command
+                // substitution, like a double-quoted string.
+                let mut sub = Parser::new(&body);
+                sub.read_double_quoted_until(None)?;
+                self.commands.append(&mut sub.commands);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read one word, handling quotes, escapes, and expansions.
+    fn read_word(&mut self) -> Result<Word, String> {
+        let mut word = Word {
+            text: String::new(),
+            quoted: false,
+            has_expansion: false,
+        };
+        while let Some(c) = self.peek() {
+            if is_metachar(c) {
+                break;
+            }
+            match c {
+                '\'' => {
+                    word.quoted = true;
+                    self.pos += 1;
+                    loop {
+                        match self.peek() {
+                            None => return Err("unterminated single quote".into()),
+                            Some('\'') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some(c) => {
+                                word.text.push(c);
+                                self.pos += 1;
+                            }
+                        }
+                    }
+                }
+                '"' => {
+                    word.quoted = true;
+                    self.pos += 1;
+                    let (text, expands) = self.read_double_quoted_until(Some('"'))?;
+                    word.text.push_str(&text);
+                    word.has_expansion |= expands;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(c) => {
+                            word.quoted = true;
+                            word.text.push(c);
+                            self.pos += 1;
+                        }
+                        None => {}
+                    }
+                }
+                '$' | '`' => {
+                    let text = self.read_expansion()?;
+                    word.text.push_str(&text);
+                    word.has_expansion |= text != "$";
+                }
+                _ => {
+                    word.text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        Ok(word)
+    }
+
+    /// Read double-quoted text up to `end` (consumed) or end of input.
+    /// Returns the text and whether it contains expansions.
+    fn read_double_quoted_until(&mut self, end: Option<char>) -> Result<(String, bool), String> {
+        let mut text = String::new();
+        let mut expands = false;
+        loop {
+            let Some(c) = self.peek() else {
+                return match end {
+                    Some(_) => Err("unterminated double quote".into()),
+                    None => Ok((text, expands)),
+                };
+            };
+            if Some(c) == end {
+                self.pos += 1;
+                return Ok((text, expands));
+            }
+            match c {
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(c @ ('$' | '`' | '"' | '\\')) => {
+                            text.push(c);
+                            self.pos += 1;
+                        }
+                        Some(c) => {
+                            text.push('\\');
+                            text.push(c);
+                            self.pos += 1;
+                        }
+                        None => text.push('\\'),
+                    }
+                }
+                '$' | '`' => {
+                    let expansion = self.read_expansion()?;
+                    expands |= expansion != "$";
+                    text.push_str(&expansion);
+                }
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Read an expansion starting at `$` or a backtick and return it as
+    /// written. Command substitutions are parsed for their commands.
+    fn read_expansion(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        if self.peek() == Some('`') {
+            self.pos += 1;
+            let mut inner = String::new();
+            loop {
+                match self.peek() {
+                    None => return Err("unterminated backquote".into()),
+                    Some('`') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some('\\') if matches!(self.peek_at(1), Some('`' | '\\' | '$')) => {
+                        inner.push(self.chars[self.pos + 1]);
+                        self.pos += 2;
+                    }
+                    Some(c) => {
+                        inner.push(c);
+                        self.pos += 1;
+                    }
+                }
+            }
+            let mut sub = Parser::new(&inner);
+            sub.parse_list(None)?;
+            self.commands.append(&mut sub.commands);
+            return Ok(self.chars[start..self.pos].iter().collect());
+        }
+
+        // `$`
+        self.pos += 1;
+        match self.peek() {
+            Some('(') if self.peek_at(1) == Some('(') => {
+                // Arithmetic expansion: skip to the matching `))`.
+                self.pos += 2;
+                self.skip_balanced('(', ')', 2, "unterminated arithmetic expansion")?;
+            }
+            Some('(') => {
+                self.pos += 1;
+                self.parse_list(Some(')'))?;
+            }
+            Some('{') => {
+                self.pos += 1;
+                self.skip_balanced('{', '}', 1, "unterminated parameter expansion")?;
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+            }
+            Some(c) if c.is_ascii_digit() || "@*#?-$!".contains(c) => self.pos += 1,
+            // A lone `$` is literal.
+            _ => {}
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Advance past `depth` unmatched closing delimiters. Quotes are
+    /// skipped and nested substitutions parsed, since `${x:-$(cmd)}` and
+    /// `$(( $(cmd) + 1 ))` run `cmd` too.
+    fn skip_balanced(&mut self, open: char, close: char, depth: usize, err: &str) -> Result<(), String> {
+        let mut depth = depth;
+        while depth > 0 {
+            match self.peek() {
+                None => return Err(err.into()),
+                Some('$' | '`') => {
+                    self.read_expansion()?;
+                    continue;
+                }
+                Some('\\') => self.pos += 1,
+                Some('\'') => {
+                    self.pos += 1;
+                    while self.peek().is_some_and(|c| c != '\'') {
+                        self.pos += 1;
+                    }
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    self.read_double_quoted_until(Some('"'))?;
+                    continue;
+                }
+                Some(c) if c == open => depth += 1,
+                Some(c) if c == close => depth -= 1,
+                Some(_) => {}
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Characters that end an unquoted word.
+fn is_metachar(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | ';' | '&' | '|' | '(' | ')' | '<' | '>')
+}
+
+/// Decide what a word in command position is.
+fn classify(word: &Word) -> CommandWord {
+    if !word.quoted && RESERVED_WORDS.contains(&word.text.as_str()) {
+        return CommandWord::Reserved;
+    }
+    if let Some((name, _)) = word.text.split_once('=')
+        && !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return CommandWord::Assignment;
+    }
+    CommandWord::Program
+}
+
+/// Most wrapper and `eval` levels followed by [`invoked_commands`].
+const MAX_NESTING: usize = 8;
+
+/// Shells whose `-c` argument is a command line of its own.
+const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "ksh", "ash"];
+
+/// Programs that run the command given in their arguments: name, options
+/// that take a value, and operands that come before the command.
+const WRAPPERS: &[(&str, &[&str], usize)] = &[
+    ("command", &[], 0),
+    ("exec", &["-a"], 0),
+    ("nohup", &[], 0),
+    ("setsid", &[], 0),
+    ("time", &["-f", "-o"], 0),
+    ("nice", &["-n"], 0),
+    ("ionice", &["-c", "-n"], 0),
+    ("env", &["-u", "-C"], 0),
+    ("stdbuf", &["-i", "-o", "-e"], 0),
+    ("timeout", &["-s", "-k"], 1),
+    ("taskset", &[], 1),
+    ("xargs", &["-I", "-n", "-P", "-L", "-d", "-s", "-a", "-E"], 0),
+];
+
+/// The name a program is looked up by: `/usr/bin/sudo` runs `sudo`.
+pub fn program_name(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/// Every command `commands` would run: each one, followed by the commands it
+/// runs in turn -- through wrappers such as `env`, `nice`, `timeout`,
+/// `xargs`, and `find -exec`, and through `eval`, `watch`, and `sh -c`.
+///
+/// When the command line an `eval` or `sh -c` would run is only known at run
+/// time, it is returned as a command whose program word has
+/// `has_expansion` set.
+pub fn invoked_commands(commands: Vec<SimpleCommand>) -> Result<Vec<SimpleCommand>, String> {
+    let mut out = Vec::new();
+    for command in commands {
+        collect_invoked(command, 0, &mut out)?;
+    }
+    Ok(out)
+}
+
+fn collect_invoked(command: SimpleCommand, depth: usize, out: &mut Vec<SimpleCommand>) -> Result<(), String> {
+    if depth > MAX_NESTING {
+        return Err("commands are nested too deeply".into());
+    }
+    let inner = inner_commands(&command)?;
+    out.push(command);
+    for command in inner {
+        collect_invoked(command, depth + 1, out)?;
+    }
+    Ok(())
+}
+
+/// Commands run directly by `command`'s program.
+fn inner_commands(command: &SimpleCommand) -> Result<Vec<SimpleCommand>, String> {
+    let Some(program) = command.program.as_ref().filter(|p| !p.has_expansion) else {
+        return Ok(Vec::new());
+    };
+    let name = program_name(&program.text);
+    let args = &command.args;
+    match name {
+        "eval" => script(args),
+        "watch" => {
+            let start = skip_options(args, &["-n", "-d"]);
+            script(&args[start..])
+        }
+        _ if SHELLS.contains(&name) => {
+            let flag = args.iter().position(|arg| {
+                arg.text.starts_with('-') && !arg.text.starts_with("--") && arg.text.contains('c')
+            });
+            match flag.and_then(|i| args.get(i + 1)) {
+                Some(line) => script(std::slice::from_ref(line)),
+                // Running a script file or reading stdin.
+                None => Ok(Vec::new()),
+            }
+        }
+        "find" => {
+            let mut commands = Vec::new();
+            let mut rest = args.as_slice();
+            while let Some(start) = rest
+                .iter()
+                .position(|arg| matches!(arg.text.as_str(), "-exec" | "-execdir" | "-ok" | "-okdir"))
+            {
+                rest = &rest[start + 1..];
+                let end = rest
+                    .iter()
+                    .position(|arg| arg.text == ";" || arg.text == "+")
+                    .unwrap_or(rest.len());
+                commands.extend(command_from_words(&rest[..end]));
+                rest = &rest[end..];
+            }
+            Ok(commands)
+        }
+        _ => {
+            let Some((_, value_options, operands)) =
+                WRAPPERS.iter().find(|(wrapper, _, _)| *wrapper == name)
+            else {
+                return Ok(Vec::new());
+            };
+            let start = skip_options(args, value_options) + operands;
+            Ok(args
+                .get(start..)
+                .and_then(command_from_words)
+                .into_iter()
+                .collect())
+        }
+    }
+}
+
+/// Index of the first argument after leading options and `NAME=value`
+/// assignments (as `env` takes). `--` ends the options.
+fn skip_options(args: &[Word], value_options: &[&str]) -> usize {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        let text = arg.text.as_str();
+        if text == "--" {
+            return i + 1;
+        }
+        if text.starts_with('-') && text.len() > 1 {
+            i += if value_options.contains(&text) { 2 } else { 1 };
+        } else if matches!(classify(arg), CommandWord::Assignment) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i.min(args.len())
+}
+
+/// A command made of already-split words: the first is the program.
+fn command_from_words(words: &[Word]) -> Option<SimpleCommand> {
+    let (program, args) = words.split_first()?;
+    Some(SimpleCommand {
+        program: Some(program.clone()),
+        args: args.to_vec(),
+        ..Default::default()
+    })
+}
+
+/// Parse words that a program joins and runs as a command line. If any is
+/// only known at run time, so is the whole line.
+fn script(words: &[Word]) -> Result<Vec<SimpleCommand>, String> {
+    let line = words
+        .iter()
+        .map(|w| w.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if words.iter().any(|w| w.has_expansion) {
+        return Ok(vec![SimpleCommand {
+            program: Some(Word {
+                text: line,
+                quoted: false,
+                has_expansion: true,
+            }),
+            ..Default::default()
+        }]);
+    }
+    parse_commands(&line)
+}
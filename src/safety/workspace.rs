@@ -1,10 +1,18 @@
 use std::path::{Path, PathBuf};
 
+use super::protected::ProtectedPaths;
+
 /// Enforces workspace-scoped write access.
-/// Reads are unrestricted; writes must target paths within the workspace.
+/// Reads are unrestricted; writes must target paths within the workspace
+/// that are not protected.
 pub struct WorkspaceGuard {
     /// Canonical (absolute, symlinks resolved) workspace root.
     canonical_root: PathBuf,
+    /// Workspace files that may not be written at all.
+    protected: ProtectedPaths,
+    /// Directory the protected-path patterns are relative to: the workspace
+    /// root, or for a sub-agent, the top-level workspace containing it.
+    protected_root: PathBuf,
 }
 
 impl WorkspaceGuard {
@@ -13,12 +21,33 @@ impl WorkspaceGuard {
     pub fn new(workspace_path: &Path) -> std::io::Result<Self> {
         std::fs::create_dir_all(workspace_path)?;
         let canonical_root = std::fs::canonicalize(workspace_path)?;
-        Ok(Self { canonical_root })
+        Ok(Self {
+            protected_root: canonical_root.clone(),
+            canonical_root,
+            protected: ProtectedPaths::none(),
+        })
+    }
+
+    /// Refuse writes to the given protected paths as well.
+    pub fn with_protected(mut self, protected: ProtectedPaths) -> Self {
+        self.protected = protected;
+        self
+    }
+
+    /// Match protected paths relative to `root`, the canonical root of a
+    /// workspace containing this one, instead of relative to this one.
+    pub fn with_protected_root(mut self, root: &Path) -> Self {
+        self.protected_root = root.to_path_buf();
+        self
     }
 
     /// Check if a write to the given path is allowed.
     /// Resolves symlinks to prevent escape via symlink traversal.
     pub fn is_write_allowed(&self, target: &Path) -> Result<bool, std::io::Error> {
+        if self.protected_pattern(target).is_some() {
+            return Ok(false);
+        }
+
         let canonical = if target.exists() {
             std::fs::canonicalize(target)?
         } else {
@@ -36,6 +65,23 @@ impl WorkspaceGuard {
         Ok(canonical.starts_with(&self.canonical_root))
     }
 
+    /// The protected-path pattern covering `target`, if any.
+    ///
+    /// Both the target itself and, when it is a symlink, the file it points
+    /// to are checked, so a link cannot be used to write a protected file.
+    pub fn protected_pattern(&self, target: &Path) -> Option<&str> {
+        if self.protected.is_empty() {
+            return None;
+        }
+        let parent = std::fs::canonicalize(target.parent()?).ok()?;
+        let lexical = parent.join(target.file_name()?);
+        if let Some(pattern) = self.protected.protecting(&self.protected_root, &lexical) {
+            return Some(pattern);
+        }
+        let resolved = std::fs::canonicalize(target).ok()?;
+        self.protected.protecting(&self.protected_root, &resolved)
+    }
+
     /// Get the canonical workspace root path.
     pub fn canonical_root(&self) -> &Path {
         &self.canonical_root
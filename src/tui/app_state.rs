@@ -7,6 +7,10 @@
 
 use super::event::{AgentEvent, AgentState};
 
+/// Streamed output lines kept for a running tool call. Older lines are
+/// dropped; the complete output arrives with the tool result.
+pub const LIVE_OUTPUT_MAX_LINES: usize = 200;
+
 /// Categorizes log entries for color-coding and icon selection during rendering.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogEntryKind {
@@ -43,6 +47,27 @@ pub struct LogEntry {
     pub expanded: bool,
 }
 
+/// A sub-agent as shown in the sub-agent panel.
+#[derive(Debug, Clone)]
+pub struct SubAgentView {
+    /// Harness-assigned id (e.g., "sub-3").
+    pub id: String,
+    /// Nesting depth (0 for children of the main agent), used for indentation.
+    pub depth: usize,
+    /// Task prompt the sub-agent was given.
+    pub task: String,
+    /// Current agent state of the sub-agent.
+    pub state: AgentState,
+    /// Outcome once the sub-agent has finished; `None` while running.
+    pub status: Option<String>,
+    /// Turns completed so far.
+    pub turn: u64,
+    /// Tool calls executed so far.
+    pub tool_calls: u64,
+    /// One-line summary of the latest thought, tool call, or error.
+    pub last_activity: String,
+}
+
 /// All TUI-visible state, accumulated from agent events.
 ///
 /// The TUI render loop reads from this struct every frame. Agent events
@@ -58,8 +83,10 @@ pub struct AppState {
     pub discoveries: Vec<(String, String)>,
 
     // -- Status bar fields --
-    /// Current agent state (Thinking/Executing/Idle/Paused).
+    /// Current agent state (Thinking/Executing/Idle/Paused/Retrying).
     pub agent_state: AgentState,
+    /// Model currently serving requests; empty until the session reports it.
+    pub active_model: String,
     /// Context window usage as a fraction (0.0 to 1.0).
     pub context_usage_pct: f64,
     /// Number of prompt tokens used in the current context.
@@ -81,6 +108,10 @@ pub struct AppState {
     /// When true, new log entries auto-scroll the view to the bottom.
     pub auto_scroll: bool,
 
+    // -- Sub-agents --
+    /// Spawned sub-agents in tree order (each child follows its parent).
+    pub sub_agents: Vec<SubAgentView>,
+
     // -- Panel visibility --
     /// Whether the sub-agent tree panel is visible on the Agent tab.
     pub sub_agent_panel_visible: bool,
@@ -88,6 +119,29 @@ pub struct AppState {
     // -- Quit confirmation --
     /// True after the first 'q' press; a second 'q' confirms quit.
     pub quit_pending: bool,
+
+    // -- Replay --
+    /// Playback position when the TUI is replaying a session log.
+    /// `None` for live sessions.
+    pub replay: Option<ReplayStatus>,
+
+    // -- Live tool output --
+    /// Call ID and log entry index of the running tool call whose streamed
+    /// output is collected in that entry's `full_content`.
+    pub live_call: Option<(String, usize)>,
+}
+
+/// Playback status shown in the status bar during `ouro replay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayStatus {
+    /// Number of log events applied so far.
+    pub position: usize,
+    /// Total number of log events in the replay.
+    pub total: usize,
+    /// Playback speed multiplier relative to the original timing.
+    pub speed: f64,
+    /// Whether playback is paused (step mode).
+    pub paused: bool,
 }
 
 impl AppState {
@@ -97,6 +151,7 @@ impl AppState {
             log_entries: Vec::new(),
             discoveries: Vec::new(),
             agent_state: AgentState::Idle,
+            active_model: String::new(),
             context_usage_pct: 0.0,
             prompt_tokens: 0,
             context_limit: 0,
@@ -106,8 +161,11 @@ impl AppState {
             active_tab: 0,
             log_scroll_offset: 0,
             auto_scroll: true,
+            sub_agents: Vec::new(),
             sub_agent_panel_visible: true,
             quit_pending: false,
+            replay: None,
+            live_call: None,
         }
     }
 
@@ -135,7 +193,7 @@ impl AppState {
             AgentEvent::ToolCallStarted {
                 timestamp,
                 turn: _,
-                call_id: _,
+                call_id,
                 fn_name,
                 args_summary,
             } => {
@@ -147,17 +205,39 @@ impl AppState {
                     full_content: String::new(),
                     expanded: false,
                 });
+                self.live_call = Some((call_id, self.log_entries.len() - 1));
                 self.auto_scroll_to_bottom();
             }
 
+            AgentEvent::ToolOutputChunk { call_id, line } => {
+                if let Some((live_id, index)) = &self.live_call
+                    && *live_id == call_id
+                    && let Some(entry) = self.log_entries.get_mut(*index)
+                {
+                    append_live_line(&mut entry.full_content, &line);
+                    self.auto_scroll_to_bottom();
+                }
+            }
+
             AgentEvent::ToolCallCompleted {
                 timestamp,
                 turn: _,
-                call_id: _,
+                call_id,
                 fn_name,
                 result_summary: _,
                 full_result,
             } => {
+                // The live output was only a preview; the result entry below
+                // carries the full output.
+                if let Some((live_id, index)) = &self.live_call
+                    && *live_id == call_id
+                {
+                    let index = *index;
+                    self.live_call = None;
+                    if let Some(entry) = self.log_entries.get_mut(index) {
+                        entry.full_content.clear();
+                    }
+                }
                 let line_count = full_result.lines().count();
                 let summary = format!("{fn_name}: {line_count} lines of output");
                 self.log_entries.push(LogEntry {
@@ -211,14 +291,105 @@ impl AppState {
                 self.auto_scroll_to_bottom();
             }
 
+            AgentEvent::SystemMessage { timestamp, content } => {
+                self.log_entries.push(LogEntry {
+                    timestamp,
+                    kind: LogEntryKind::System,
+                    summary: first_line_or_truncate(&content, 120),
+                    full_content: content,
+                    expanded: true,
+                });
+                self.auto_scroll_to_bottom();
+            }
+
             AgentEvent::Discovery { timestamp, content } => {
                 self.discoveries.push((timestamp, content));
             }
 
+            AgentEvent::ModelChanged { model } => {
+                self.active_model = model;
+            }
+
             AgentEvent::CountersUpdated { turn, tool_calls } => {
                 self.turn_count = turn;
                 self.tool_call_count = tool_calls;
             }
+
+            AgentEvent::SubAgentSpawned {
+                agent_id,
+                parent_id,
+                task,
+            } => {
+                // Insert after the parent's subtree so the list stays in tree order.
+                let (index, depth) = match parent_id
+                    .as_ref()
+                    .and_then(|pid| self.sub_agents.iter().position(|a| &a.id == pid))
+                {
+                    Some(p) => {
+                        let parent_depth = self.sub_agents[p].depth;
+                        let mut i = p + 1;
+                        while i < self.sub_agents.len() && self.sub_agents[i].depth > parent_depth {
+                            i += 1;
+                        }
+                        (i, parent_depth + 1)
+                    }
+                    None => (self.sub_agents.len(), 0),
+                };
+                self.sub_agents.insert(
+                    index,
+                    SubAgentView {
+                        id: agent_id,
+                        depth,
+                        last_activity: String::new(),
+                        task,
+                        state: AgentState::Idle,
+                        status: None,
+                        turn: 0,
+                        tool_calls: 0,
+                    },
+                );
+            }
+
+            AgentEvent::SubAgent { agent_id, event } => {
+                let Some(agent) = self.sub_agents.iter_mut().find(|a| a.id == agent_id) else {
+                    return;
+                };
+                match *event {
+                    AgentEvent::StateChanged(state) => agent.state = state,
+                    AgentEvent::CountersUpdated { turn, tool_calls } => {
+                        agent.turn = turn;
+                        agent.tool_calls = tool_calls;
+                    }
+                    AgentEvent::ThoughtText { content, .. } => {
+                        agent.last_activity = first_line_or_truncate(&content, 80);
+                    }
+                    AgentEvent::ToolCallStarted {
+                        fn_name,
+                        args_summary,
+                        ..
+                    } => {
+                        agent.last_activity =
+                            first_line_or_truncate(&format!("{fn_name}({args_summary})"), 80);
+                    }
+                    AgentEvent::Error { message, .. } => {
+                        agent.last_activity =
+                            first_line_or_truncate(&format!("error: {message}"), 80);
+                    }
+                    _ => {}
+                }
+            }
+
+            AgentEvent::SubAgentFinished {
+                agent_id,
+                status,
+                turns,
+            } => {
+                if let Some(agent) = self.sub_agents.iter_mut().find(|a| a.id == agent_id) {
+                    agent.state = AgentState::Idle;
+                    agent.status = Some(status);
+                    agent.turn = turns;
+                }
+            }
         }
     }
 
@@ -268,6 +439,21 @@ impl Default for AppState {
     }
 }
 
+/// Append `line` to a running tool call's live output, keeping only the last
+/// [`LIVE_OUTPUT_MAX_LINES`] lines.
+fn append_live_line(output: &mut String, line: &str) {
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output.push_str(line);
+    let excess = output.lines().count().saturating_sub(LIVE_OUTPUT_MAX_LINES);
+    if excess > 0
+        && let Some((cut, _)) = output.match_indices('\n').nth(excess - 1)
+    {
+        output.drain(..=cut);
+    }
+}
+
 /// Extract the first line of `text`, truncating to `max_len` characters if needed.
 fn first_line_or_truncate(text: &str, max_len: usize) -> String {
     let first_line = text.lines().next().unwrap_or("");
@@ -296,6 +482,7 @@ mod tests {
         assert!(state.log_entries.is_empty());
         assert!(state.discoveries.is_empty());
         assert!(!state.quit_pending);
+        assert!(state.replay.is_none());
     }
 
     #[test]
@@ -332,6 +519,64 @@ mod tests {
         assert_eq!(entry.summary, "shell_exec(ls -la)");
     }
 
+    #[test]
+    fn tool_output_chunks_collect_under_running_call() {
+        let mut state = AppState::new();
+        state.apply_event(AgentEvent::ToolCallStarted {
+            timestamp: "14:32:08".into(),
+            turn: 1,
+            call_id: "call_001".into(),
+            fn_name: "shell_exec".into(),
+            args_summary: "make".into(),
+        });
+        for line in ["compiling a", "compiling b"] {
+            state.apply_event(AgentEvent::ToolOutputChunk {
+                call_id: "call_001".into(),
+                line: line.into(),
+            });
+        }
+        // Chunks for another call are ignored.
+        state.apply_event(AgentEvent::ToolOutputChunk {
+            call_id: "call_002".into(),
+            line: "stray".into(),
+        });
+        assert_eq!(state.log_entries[0].full_content, "compiling a\ncompiling b");
+
+        state.apply_event(AgentEvent::ToolCallCompleted {
+            timestamp: "14:32:09".into(),
+            turn: 1,
+            call_id: "call_001".into(),
+            fn_name: "shell_exec".into(),
+            result_summary: "ok".into(),
+            full_result: "compiling a\ncompiling b".into(),
+        });
+        assert!(state.log_entries[0].full_content.is_empty());
+        assert!(state.live_call.is_none());
+        assert_eq!(state.log_entries[1].full_content, "compiling a\ncompiling b");
+    }
+
+    #[test]
+    fn tool_output_chunks_keep_only_recent_lines() {
+        let mut state = AppState::new();
+        state.apply_event(AgentEvent::ToolCallStarted {
+            timestamp: "14:32:08".into(),
+            turn: 1,
+            call_id: "call_001".into(),
+            fn_name: "shell_exec".into(),
+            args_summary: "seq 1000".into(),
+        });
+        for i in 1..=LIVE_OUTPUT_MAX_LINES + 50 {
+            state.apply_event(AgentEvent::ToolOutputChunk {
+                call_id: "call_001".into(),
+                line: i.to_string(),
+            });
+        }
+        let output = &state.log_entries[0].full_content;
+        assert_eq!(output.lines().count(), LIVE_OUTPUT_MAX_LINES);
+        assert_eq!(output.lines().next(), Some("51"));
+        assert_eq!(output.lines().last(), Some((LIVE_OUTPUT_MAX_LINES + 50).to_string().as_str()));
+    }
+
     #[test]
     fn apply_tool_call_completed_counts_lines() {
         let mut state = AppState::new();
@@ -410,6 +655,20 @@ mod tests {
         assert_eq!(entry.full_content, "Connection refused");
     }
 
+    #[test]
+    fn apply_system_message_pushes_system_entry() {
+        let mut state = AppState::new();
+        state.apply_event(AgentEvent::SystemMessage {
+            timestamp: "14:33:30".into(),
+            content: "[Context window 91% full.]".into(),
+        });
+
+        assert_eq!(state.log_entries.len(), 1);
+        let entry = &state.log_entries[0];
+        assert_eq!(entry.kind, LogEntryKind::System);
+        assert!(entry.expanded);
+    }
+
     #[test]
     fn apply_discovery_appends_to_list() {
         let mut state = AppState::new();
@@ -434,6 +693,74 @@ mod tests {
         assert_eq!(state.tool_call_count, 47);
     }
 
+    #[test]
+    fn sub_agents_are_kept_in_tree_order() {
+        let mut state = AppState::new();
+        let spawn = |id: &str, parent: Option<&str>| AgentEvent::SubAgentSpawned {
+            agent_id: id.into(),
+            parent_id: parent.map(String::from),
+            task: format!("task {id}"),
+        };
+        state.apply_event(spawn("sub-1", None));
+        state.apply_event(spawn("sub-2", None));
+        state.apply_event(spawn("sub-3", Some("sub-1")));
+        state.apply_event(spawn("sub-4", Some("sub-3")));
+
+        let order: Vec<(&str, usize)> = state
+            .sub_agents
+            .iter()
+            .map(|a| (a.id.as_str(), a.depth))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("sub-1", 0), ("sub-3", 1), ("sub-4", 2), ("sub-2", 0)]
+        );
+    }
+
+    #[test]
+    fn sub_agent_events_update_their_view() {
+        let mut state = AppState::new();
+        state.apply_event(AgentEvent::SubAgentSpawned {
+            agent_id: "sub-1".into(),
+            parent_id: None,
+            task: "count files".into(),
+        });
+        let tagged = |event| AgentEvent::SubAgent {
+            agent_id: "sub-1".into(),
+            event: Box::new(event),
+        };
+        state.apply_event(tagged(AgentEvent::StateChanged(AgentState::Executing)));
+        state.apply_event(tagged(AgentEvent::ToolCallStarted {
+            timestamp: "t".into(),
+            turn: 1,
+            call_id: "c1".into(),
+            fn_name: "shell_exec".into(),
+            args_summary: "ls".into(),
+        }));
+        state.apply_event(tagged(AgentEvent::CountersUpdated {
+            turn: 1,
+            tool_calls: 1,
+        }));
+
+        let agent = &state.sub_agents[0];
+        assert_eq!(agent.state, AgentState::Executing);
+        assert_eq!(agent.last_activity, "shell_exec(ls)");
+        assert_eq!(agent.tool_calls, 1);
+        assert!(agent.status.is_none());
+        // Sub-agent activity does not go into the main log stream.
+        assert!(state.log_entries.is_empty());
+
+        state.apply_event(AgentEvent::SubAgentFinished {
+            agent_id: "sub-1".into(),
+            status: "completed".into(),
+            turns: 2,
+        });
+        let agent = &state.sub_agents[0];
+        assert_eq!(agent.status.as_deref(), Some("completed"));
+        assert_eq!(agent.turn, 2);
+        assert_eq!(agent.state, AgentState::Idle);
+    }
+
     #[test]
     fn toggle_expand_flips_state() {
         let mut state = AppState::new();
@@ -564,6 +891,11 @@ mod tests {
         assert_eq!(format!("{}", AgentState::Executing), "Executing");
         assert_eq!(format!("{}", AgentState::Idle), "Idle");
         assert_eq!(format!("{}", AgentState::Paused), "Paused");
+        let retrying = AgentState::Retrying {
+            attempt: 2,
+            max_attempts: 3,
+        };
+        assert_eq!(format!("{retrying}"), "Retrying (2/3)");
     }
 
     #[test]
@@ -29,6 +29,13 @@ pub enum AgentEvent {
         args_summary: String,
     },
 
+    /// A line of output from a running tool call (currently `shell_exec`),
+    /// sent before its `ToolCallCompleted`.
+    ToolOutputChunk {
+        call_id: String,
+        line: String,
+    },
+
     /// Tool call completed with result.
     ToolCallCompleted {
         timestamp: String,
@@ -61,20 +68,53 @@ pub enum AgentEvent {
         message: String,
     },
 
+    /// Harness-injected system message (wind-down notices, session end).
+    SystemMessage {
+        timestamp: String,
+        content: String,
+    },
+
     /// Agent flagged something noteworthy for the discoveries list.
     Discovery {
         timestamp: String,
         content: String,
     },
 
+    /// The model serving requests changed (session start or fallback switch).
+    ModelChanged {
+        model: String,
+    },
+
     /// Turn and tool-call counters updated (emitted each turn).
     CountersUpdated {
         turn: u64,
         tool_calls: u64,
     },
+
+    /// A sub-agent was spawned via the `spawn_agent` tool.
+    SubAgentSpawned {
+        agent_id: String,
+        /// Id of the spawning sub-agent, or `None` if spawned by the main agent.
+        parent_id: Option<String>,
+        task: String,
+    },
+
+    /// An event emitted by a running sub-agent, tagged with its id.
+    SubAgent {
+        agent_id: String,
+        event: Box<AgentEvent>,
+    },
+
+    /// A sub-agent session ended.
+    SubAgentFinished {
+        agent_id: String,
+        /// Outcome: "completed", "stopped", "context_full", "shutdown", or "error".
+        status: String,
+        turns: u64,
+    },
 }
 
-/// The four visible agent states shown in the status bar.
+/// The visible agent states shown in the status bar.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AgentState {
     /// Model is generating a response (streaming tokens).
@@ -85,6 +125,8 @@ pub enum AgentState {
     Idle,
     /// User has paused the agent loop.
     Paused,
+    /// Waiting to retry a failed LLM request (`attempt` of `max_attempts`).
+    Retrying { attempt: u32, max_attempts: u32 },
 }
 
 impl fmt::Display for AgentState {
@@ -94,6 +136,10 @@ impl fmt::Display for AgentState {
             AgentState::Executing => write!(f, "Executing"),
             AgentState::Idle => write!(f, "Idle"),
             AgentState::Paused => write!(f, "Paused"),
+            AgentState::Retrying {
+                attempt,
+                max_attempts,
+            } => write!(f, "Retrying ({attempt}/{max_attempts})"),
         }
     }
 }
@@ -1,6 +1,7 @@
 pub mod app_state;
 pub mod event;
 pub mod input;
+pub mod replay;
 pub mod runner;
 pub mod tabs;
 pub mod ui;
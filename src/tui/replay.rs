@@ -0,0 +1,812 @@
+//! Session log replay through the TUI.
+//!
+//! [`run_replay`] is the entry point for `ouro replay`. It parses a JSONL
+//! session log, converts each [`LogEntry`] into the [`AgentEvent`]s the live
+//! agent loop would have sent, and feeds them into [`AppState`] on a timer
+//! derived from the logged timestamps.
+//!
+//! Playback can run in real time, at an `N`x multiplier, or step-by-step
+//! (paused, advancing one log entry per key press). Seeking backward rebuilds
+//! `AppState` from the start of the log, since events are not reversible.
+
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use crossterm::event::{EventStream, KeyCode, KeyEvent, KeyEventKind};
+use futures::StreamExt;
+use tokio::time::Instant;
+
+use crate::agent::discoveries::Discovery;
+use crate::agent::logging::LogEntry;
+use crate::agent::resume::read_log_entries;
+use crate::tui::app_state::{AppState, ReplayStatus};
+use crate::tui::event::{AgentEvent, AgentState, ControlSignal};
+use crate::tui::input::handle_key_event;
+use crate::tui::ui::render_ui;
+
+/// Slowest and fastest playback multipliers reachable with `-` / `+`.
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 64.0;
+
+/// Longest wait between two events, after applying the speed multiplier.
+///
+/// Gaps this long mean the agent was paused or the model was stalled;
+/// replaying them verbatim would just leave the screen frozen.
+const MAX_GAP: Duration = Duration::from_secs(30);
+
+// ---------------------------------------------------------------------------
+// Log entry -> AgentEvent conversion
+// ---------------------------------------------------------------------------
+
+/// One log entry's worth of TUI events, with its original timestamp.
+#[derive(Debug, Clone)]
+pub struct ReplayFrame {
+    /// When the entry was logged. `None` if the timestamp failed to parse.
+    pub at: Option<DateTime<Utc>>,
+    /// Turn the entry belongs to (0 for session-level entries).
+    pub turn: u64,
+    /// Events to apply to `AppState`, in order.
+    pub events: Vec<AgentEvent>,
+}
+
+/// Convert parsed log entries into replay frames.
+///
+/// Turn and tool-call counters are not logged directly, so they are
+/// reconstructed here and emitted as `CountersUpdated` events.
+pub fn entries_to_frames(entries: &[LogEntry]) -> Vec<ReplayFrame> {
+    let mut frames = Vec::with_capacity(entries.len());
+    let mut turn: u64 = 0;
+    let mut tool_calls: u64 = 0;
+
+    for entry in entries {
+        let (timestamp, events) = match entry {
+            LogEntry::SessionStart {
+                timestamp,
+                model,
+                session_number,
+                ..
+            } => {
+                turn = 0;
+                tool_calls = 0;
+                (
+                    timestamp,
+                    vec![
+                        AgentEvent::SessionRestarted {
+                            session_number: (*session_number).max(1),
+                        },
+                        AgentEvent::ModelChanged {
+                            model: model.clone(),
+                        },
+                        AgentEvent::SystemMessage {
+                            timestamp: timestamp.clone(),
+                            content: format!("Session started with model {model}"),
+                        },
+                        AgentEvent::CountersUpdated { turn, tool_calls },
+                    ],
+                )
+            }
+            LogEntry::AssistantText {
+                timestamp,
+                turn: t,
+                content,
+            } => {
+                turn = *t;
+                (
+                    timestamp,
+                    vec![
+                        AgentEvent::ThoughtText {
+                            timestamp: timestamp.clone(),
+                            turn,
+                            content: content.clone(),
+                        },
+                        AgentEvent::CountersUpdated { turn, tool_calls },
+                    ],
+                )
+            }
+            LogEntry::ToolCall {
+                timestamp,
+                turn: t,
+                call_id,
+                fn_name,
+                fn_arguments,
+            } => {
+                turn = *t;
+                tool_calls += 1;
+                let args_summary =
+                    serde_json::to_string(fn_arguments).unwrap_or_else(|_| "{}".to_string());
+                (
+                    timestamp,
+                    vec![
+                        AgentEvent::StateChanged(AgentState::Executing),
+                        AgentEvent::ToolCallStarted {
+                            timestamp: timestamp.clone(),
+                            turn,
+                            call_id: call_id.clone(),
+                            fn_name: fn_name.clone(),
+                            args_summary,
+                        },
+                        AgentEvent::CountersUpdated { turn, tool_calls },
+                    ],
+                )
+            }
+            LogEntry::ToolResult {
+                timestamp,
+                turn: t,
+                call_id,
+                fn_name,
+                result,
+                ..
+            } => {
+                turn = *t;
+                (
+                    timestamp,
+                    vec![
+                        AgentEvent::ToolCallCompleted {
+                            timestamp: timestamp.clone(),
+                            turn,
+                            call_id: call_id.clone(),
+                            fn_name: fn_name.clone(),
+                            result_summary: String::new(),
+                            full_result: result.clone(),
+                        },
+                        AgentEvent::StateChanged(AgentState::Idle),
+                    ],
+                )
+            }
+            LogEntry::SystemMessage { timestamp, content } => (
+                timestamp,
+                vec![AgentEvent::SystemMessage {
+                    timestamp: timestamp.clone(),
+                    content: content.clone(),
+                }],
+            ),
+            LogEntry::Error {
+                timestamp,
+                turn: t,
+                message,
+            } => {
+                turn = *t;
+                (
+                    timestamp,
+                    vec![AgentEvent::Error {
+                        timestamp: timestamp.clone(),
+                        turn,
+                        message: message.clone(),
+                    }],
+                )
+            }
+            LogEntry::Retry {
+                timestamp,
+                turn: t,
+                attempt,
+                max_attempts,
+                delay_ms,
+                error,
+            } => {
+                turn = *t;
+                (
+                    timestamp,
+                    vec![
+                        AgentEvent::StateChanged(AgentState::Retrying {
+                            attempt: attempt + 1,
+                            max_attempts: *max_attempts,
+                        }),
+                        AgentEvent::SystemMessage {
+                            timestamp: timestamp.clone(),
+                            content: format!(
+                                "Retrying ({}/{max_attempts}) in {:.1}s: {error}",
+                                attempt + 1,
+                                *delay_ms as f64 / 1000.0
+                            ),
+                        },
+                    ],
+                )
+            }
+            LogEntry::ModelSwitch {
+                timestamp,
+                turn: t,
+                from,
+                to,
+                reason,
+            } => {
+                turn = *t;
+                (
+                    timestamp,
+                    vec![
+                        AgentEvent::ModelChanged { model: to.clone() },
+                        AgentEvent::SystemMessage {
+                            timestamp: timestamp.clone(),
+                            content: format!("Switched model from {from} to {to}: {reason}"),
+                        },
+                    ],
+                )
+            }
+            LogEntry::SessionEnd {
+                timestamp,
+                total_turns,
+                reason,
+            } => (
+                timestamp,
+                vec![
+                    AgentEvent::StateChanged(AgentState::Idle),
+                    AgentEvent::SystemMessage {
+                        timestamp: timestamp.clone(),
+                        content: format!("Session ended after {total_turns} turns: {reason}"),
+                    },
+                ],
+            ),
+            LogEntry::TokenUsage {
+                timestamp,
+                prompt_tokens,
+                context_used_pct,
+                ..
+            } => {
+                // The limit is not logged; recover it from the usage ratio.
+                let context_limit = if *context_used_pct > 0.0 {
+                    (*prompt_tokens as f64 / context_used_pct).round() as usize
+                } else {
+                    0
+                };
+                (
+                    timestamp,
+                    vec![AgentEvent::ContextPressure {
+                        usage_pct: *context_used_pct,
+                        prompt_tokens: *prompt_tokens,
+                        context_limit,
+                    }],
+                )
+            }
+            LogEntry::ContextMask {
+                timestamp,
+                observations_masked,
+                total_masked,
+                context_reclaimed_pct,
+            } => (
+                timestamp,
+                vec![AgentEvent::SystemMessage {
+                    timestamp: timestamp.clone(),
+                    content: format!(
+                        "Masked {observations_masked} observations ({total_masked} total), \
+                         ~{context_reclaimed_pct:.0}% reclaimed"
+                    ),
+                }],
+            ),
+            // Diagnostics only; nothing to show.
+            LogEntry::TokenEstimate { .. } => continue,
+            LogEntry::ContextSummary {
+                timestamp,
+                turns_summarized,
+                messages_replaced,
+                summary_chars,
+                model,
+                summary,
+            } => {
+                let mut content = format!(
+                    "Summarized {turns_summarized} turns ({messages_replaced} messages) \
+                     into {summary_chars} chars with {model}"
+                );
+                if !summary.is_empty() {
+                    content.push('\n');
+                    content.push_str(summary);
+                }
+                (
+                    timestamp,
+                    vec![AgentEvent::SystemMessage {
+                        timestamp: timestamp.clone(),
+                        content,
+                    }],
+                )
+            }
+            LogEntry::Handoff {
+                timestamp, path, ..
+            } => (
+                timestamp,
+                vec![AgentEvent::SystemMessage {
+                    timestamp: timestamp.clone(),
+                    content: format!("Handoff written to {path}"),
+                }],
+            ),
+            LogEntry::Discovery {
+                timestamp,
+                turn: t,
+                title,
+                body,
+                tags,
+                files,
+            } => {
+                turn = *t;
+                let discovery = Discovery {
+                    timestamp: timestamp.clone(),
+                    title: title.clone(),
+                    body: body.clone(),
+                    tags: tags.clone(),
+                    files: files.clone(),
+                };
+                (
+                    timestamp,
+                    vec![AgentEvent::Discovery {
+                        timestamp: timestamp.clone(),
+                        content: discovery.summary(),
+                    }],
+                )
+            }
+            LogEntry::SessionRestart {
+                timestamp,
+                session_number,
+                previous_turns,
+                carryover_messages,
+                ..
+            } => (
+                timestamp,
+                vec![AgentEvent::SystemMessage {
+                    timestamp: timestamp.clone(),
+                    content: format!(
+                        "Session #{session_number} restarting after {previous_turns} turns \
+                         ({carryover_messages} carryover messages)"
+                    ),
+                }],
+            ),
+        };
+
+        frames.push(ReplayFrame {
+            at: DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|t| t.with_timezone(&Utc)),
+            turn,
+            events,
+        });
+    }
+
+    frames
+}
+
+// ---------------------------------------------------------------------------
+// ReplayPlayer
+// ---------------------------------------------------------------------------
+
+/// Playback cursor over a list of replay frames.
+///
+/// `position` is the number of frames already applied to `AppState`; the
+/// next frame to play is `frames[position]`.
+pub struct ReplayPlayer {
+    frames: Vec<ReplayFrame>,
+    position: usize,
+    speed: f64,
+    paused: bool,
+}
+
+impl ReplayPlayer {
+    /// Create a player at the start of the log.
+    ///
+    /// `speed` is clamped to the supported range; `paused` starts in
+    /// step-by-step mode.
+    pub fn new(frames: Vec<ReplayFrame>, speed: f64, paused: bool) -> Self {
+        Self {
+            frames,
+            position: 0,
+            speed: speed.clamp(MIN_SPEED, MAX_SPEED),
+            paused,
+        }
+    }
+
+    /// Whether every frame has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.frames.len()
+    }
+
+    /// Toggle between playing and paused.
+    pub fn toggle_pause(&mut self, state: &mut AppState) {
+        self.paused = !self.paused;
+        self.sync_status(state);
+    }
+
+    /// Double playback speed, up to the maximum.
+    pub fn faster(&mut self, state: &mut AppState) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+        self.sync_status(state);
+    }
+
+    /// Halve playback speed, down to the minimum.
+    pub fn slower(&mut self, state: &mut AppState) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+        self.sync_status(state);
+    }
+
+    /// Wait before applying the next frame, scaled by the current speed.
+    ///
+    /// Returns `None` when paused or finished.
+    pub fn next_delay(&self) -> Option<Duration> {
+        if self.paused || self.is_finished() {
+            return None;
+        }
+        let gap = match (self.position.checked_sub(1), self.frames[self.position].at) {
+            (Some(prev), Some(next)) => match self.frames[prev].at {
+                Some(prev_at) => (next - prev_at).to_std().unwrap_or_default(),
+                None => Duration::ZERO,
+            },
+            _ => Duration::ZERO,
+        };
+        Some(gap.div_f64(self.speed).min(MAX_GAP))
+    }
+
+    /// Apply the next frame. No-op at the end of the log.
+    pub fn advance(&mut self, state: &mut AppState) {
+        if let Some(frame) = self.frames.get(self.position) {
+            for event in frame.events.iter().cloned() {
+                state.apply_event(event);
+            }
+            self.position += 1;
+        }
+        self.sync_status(state);
+    }
+
+    /// Pause and apply exactly one frame.
+    pub fn step_forward(&mut self, state: &mut AppState) {
+        self.paused = true;
+        self.advance(state);
+    }
+
+    /// Pause and un-apply the last frame.
+    pub fn step_back(&mut self, state: &mut AppState) {
+        self.paused = true;
+        self.seek(self.position.saturating_sub(1), state);
+    }
+
+    /// Seek to the first frame of the next turn.
+    pub fn next_turn(&mut self, state: &mut AppState) {
+        let current = self.current_turn();
+        let target = self.frames[self.position..]
+            .iter()
+            .position(|f| f.turn > current)
+            .map(|i| self.position + i + 1)
+            .unwrap_or(self.frames.len());
+        self.seek(target, state);
+    }
+
+    /// Seek to the first frame of the current turn, or of the previous turn
+    /// if already at the start of one.
+    pub fn prev_turn(&mut self, state: &mut AppState) {
+        let Some(last) = self.position.checked_sub(1) else {
+            return;
+        };
+        let mut first = self.turn_start(last);
+        if first == last && first > 0 {
+            first = self.turn_start(first - 1);
+        }
+        self.seek(first + 1, state);
+    }
+
+    /// Jump to an absolute position, rebuilding `AppState` when moving back.
+    pub fn seek(&mut self, target: usize, state: &mut AppState) {
+        let target = target.min(self.frames.len());
+        if target < self.position {
+            self.rebuild(state);
+        }
+        while self.position < target {
+            self.advance(state);
+        }
+        self.sync_status(state);
+    }
+
+    /// Turn of the most recently applied frame.
+    fn current_turn(&self) -> u64 {
+        self.position
+            .checked_sub(1)
+            .map_or(0, |i| self.frames[i].turn)
+    }
+
+    /// Index of the first frame in the same turn as `frames[index]`.
+    fn turn_start(&self, index: usize) -> usize {
+        let turn = self.frames[index].turn;
+        let mut start = index;
+        while start > 0 && self.frames[start - 1].turn == turn {
+            start -= 1;
+        }
+        start
+    }
+
+    /// Reset `AppState` to empty, keeping the user's view settings.
+    fn rebuild(&mut self, state: &mut AppState) {
+        let mut fresh = AppState::new();
+        fresh.active_tab = state.active_tab;
+        fresh.sub_agent_panel_visible = state.sub_agent_panel_visible;
+        fresh.auto_scroll = state.auto_scroll;
+        *state = fresh;
+        self.position = 0;
+    }
+
+    /// Mirror the playback position into `AppState` for the status bar.
+    fn sync_status(&self, state: &mut AppState) {
+        state.replay = Some(ReplayStatus {
+            position: self.position,
+            total: self.frames.len(),
+            speed: self.speed,
+            paused: self.paused,
+        });
+    }
+}
+
+/// Handle replay-specific keys. Returns `true` if the key was consumed.
+///
+/// Keys not handled here fall through to the regular TUI key handler, so
+/// scrolling, tab switching, expand, and quit behave as in a live session.
+pub fn handle_replay_key(key: KeyEvent, player: &mut ReplayPlayer, state: &mut AppState) -> bool {
+    if key.kind != KeyEventKind::Press || state.quit_pending {
+        return false;
+    }
+
+    match key.code {
+        KeyCode::Char(' ') | KeyCode::Char('p') => player.toggle_pause(state),
+        KeyCode::Right => player.step_forward(state),
+        KeyCode::Left => player.step_back(state),
+        KeyCode::Char(']') => player.next_turn(state),
+        KeyCode::Char('[') => player.prev_turn(state),
+        KeyCode::Home => player.seek(0, state),
+        KeyCode::Char('+') | KeyCode::Char('=') => player.faster(state),
+        KeyCode::Char('-') => player.slower(state),
+        _ => return false,
+    }
+    true
+}
+
+// ---------------------------------------------------------------------------
+// run_replay
+// ---------------------------------------------------------------------------
+
+/// Replay a session log in the TUI.
+///
+/// `speed` is a multiplier on the logged timing (1.0 = real time). With
+/// `step`, playback starts paused and advances one entry per key press.
+pub async fn run_replay(log_path: &Path, speed: f64, step: bool) -> anyhow::Result<()> {
+    let entries = read_log_entries(log_path)?;
+    if entries.is_empty() {
+        anyhow::bail!("No log entries found in {}", log_path.display());
+    }
+
+    let mut player = ReplayPlayer::new(entries_to_frames(&entries), speed, step);
+    let mut app_state = AppState::new();
+    player.sync_status(&mut app_state);
+
+    // The regular key handler expects a control channel and pause flag for the
+    // agent loop. There is no agent during replay, so these go nowhere.
+    let (control_tx, _control_rx) = tokio::sync::mpsc::unbounded_channel::<ControlSignal>();
+    let pause_flag = Arc::new(AtomicBool::new(false));
+
+    // -- Initialize terminal (raw mode + alternate screen + panic hook).
+    let mut terminal = ratatui::init();
+    let mut key_stream = EventStream::new();
+
+    let tick_rate = Duration::from_millis(50); // ~20fps
+    let mut tick_interval = tokio::time::interval(tick_rate);
+    let mut next_at = player.next_delay().map(|d| Instant::now() + d);
+
+    loop {
+        tokio::select! {
+            // Next log entry is due.
+            _ = tokio::time::sleep_until(next_at.unwrap_or_else(Instant::now)), if next_at.is_some() => {
+                player.advance(&mut app_state);
+                if app_state.auto_scroll {
+                    app_state.jump_to_bottom();
+                }
+                next_at = player.next_delay().map(|d| Instant::now() + d);
+            }
+
+            // Keyboard events from crossterm.
+            Some(Ok(crossterm_event)) = key_stream.next() => {
+                if let crossterm::event::Event::Key(key) = crossterm_event {
+                    if handle_replay_key(key, &mut player, &mut app_state) {
+                        if app_state.auto_scroll {
+                            app_state.jump_to_bottom();
+                        }
+                        next_at = player.next_delay().map(|d| Instant::now() + d);
+                    } else if handle_key_event(key, &mut app_state, &control_tx, &pause_flag) {
+                        break;
+                    }
+                }
+            }
+
+            // Render tick.
+            _ = tick_interval.tick() => {
+                terminal.draw(|frame| {
+                    render_ui(&app_state, frame);
+                })?;
+            }
+        }
+    }
+
+    // -- Cleanup: restore terminal state.
+    ratatui::restore();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEventState, KeyModifiers};
+
+    fn text(ts: &str, turn: u64, content: &str) -> LogEntry {
+        LogEntry::AssistantText {
+            timestamp: ts.into(),
+            turn,
+            content: content.into(),
+        }
+    }
+
+    fn call(ts: &str, turn: u64, id: &str) -> LogEntry {
+        LogEntry::ToolCall {
+            timestamp: ts.into(),
+            turn,
+            call_id: id.into(),
+            fn_name: "shell_exec".into(),
+            fn_arguments: serde_json::json!({"command": "ls"}),
+        }
+    }
+
+    fn result(ts: &str, turn: u64, id: &str) -> LogEntry {
+        LogEntry::ToolResult {
+            timestamp: ts.into(),
+            turn,
+            call_id: id.into(),
+            fn_name: "shell_exec".into(),
+            result: "a\nb".into(),
+            error: None,
+        }
+    }
+
+    /// A short session: start, two turns with a tool call each, end.
+    fn sample_frames() -> Vec<ReplayFrame> {
+        entries_to_frames(&[
+            LogEntry::SessionStart {
+                timestamp: "2026-01-01T00:00:00.000Z".into(),
+                model: "qwen2.5:7b".into(),
+                workspace: "/ws".into(),
+                session_number: 2,
+                context_limit: 32768,
+                context_limit_source: "config".into(),
+                resumed_from: None,
+            },
+            call("2026-01-01T00:00:02.000Z", 1, "c1"),
+            result("2026-01-01T00:00:03.000Z", 1, "c1"),
+            text("2026-01-01T00:00:05.000Z", 2, "thinking"),
+            call("2026-01-01T00:00:06.000Z", 2, "c2"),
+            result("2026-01-01T00:00:06.500Z", 2, "c2"),
+        ])
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers: KeyModifiers::empty(),
+            kind: KeyEventKind::Press,
+            state: KeyEventState::empty(),
+        }
+    }
+
+    #[test]
+    fn frames_reconstruct_counters() {
+        let frames = sample_frames();
+        assert_eq!(frames.len(), 6);
+
+        let mut state = AppState::new();
+        for frame in &frames {
+            for event in frame.events.iter().cloned() {
+                state.apply_event(event);
+            }
+        }
+        assert_eq!(state.session_number, 2);
+        assert_eq!(state.turn_count, 2);
+        assert_eq!(state.tool_call_count, 2);
+        assert_eq!(state.agent_state, AgentState::Idle);
+    }
+
+    #[test]
+    fn token_usage_recovers_context_limit() {
+        let frames = entries_to_frames(&[LogEntry::TokenUsage {
+            timestamp: "2026-01-01T00:00:00.000Z".into(),
+            turn: 1,
+            prompt_tokens: 16384,
+            completion_tokens: 100,
+            total_tokens: 16484,
+            context_used_pct: 0.5,
+        }]);
+        assert!(matches!(
+            frames[0].events[0],
+            AgentEvent::ContextPressure {
+                context_limit: 32768,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn unparseable_timestamp_has_no_delay() {
+        let frames = entries_to_frames(&[text("not a time", 1, "a"), text("also bad", 2, "b")]);
+        assert!(frames[0].at.is_none());
+
+        let mut player = ReplayPlayer::new(frames, 1.0, false);
+        let mut state = AppState::new();
+        player.advance(&mut state);
+        assert_eq!(player.next_delay(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn delay_follows_timestamps_and_speed() {
+        let mut state = AppState::new();
+        let mut player = ReplayPlayer::new(sample_frames(), 1.0, false);
+
+        // The first frame plays immediately.
+        assert_eq!(player.next_delay(), Some(Duration::ZERO));
+        player.advance(&mut state);
+        assert_eq!(player.next_delay(), Some(Duration::from_secs(2)));
+
+        player.faster(&mut state);
+        assert_eq!(player.next_delay(), Some(Duration::from_secs(1)));
+
+        player.toggle_pause(&mut state);
+        assert_eq!(player.next_delay(), None);
+    }
+
+    #[test]
+    fn step_back_rebuilds_state() {
+        let mut state = AppState::new();
+        let mut player = ReplayPlayer::new(sample_frames(), 1.0, true);
+        player.seek(3, &mut state);
+        let entries_at_3 = state.log_entries.len();
+        player.advance(&mut state);
+        assert!(state.log_entries.len() > entries_at_3);
+
+        player.step_back(&mut state);
+        assert_eq!(state.replay.unwrap().position, 3);
+        assert_eq!(state.log_entries.len(), entries_at_3);
+        assert_eq!(state.replay.unwrap().position, 3);
+    }
+
+    #[test]
+    fn turn_seek_moves_between_turns() {
+        let mut state = AppState::new();
+        let mut player = ReplayPlayer::new(sample_frames(), 1.0, true);
+
+        // From the start, the next turn begins at the first tool call.
+        player.next_turn(&mut state);
+        assert_eq!(state.replay.unwrap().position, 2);
+        assert_eq!(state.turn_count, 1);
+
+        player.next_turn(&mut state);
+        assert_eq!(state.replay.unwrap().position, 4);
+        assert_eq!(state.turn_count, 2);
+
+        player.prev_turn(&mut state);
+        assert_eq!(state.replay.unwrap().position, 2);
+
+        player.next_turn(&mut state);
+        player.next_turn(&mut state);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn replay_keys_control_player() {
+        let mut state = AppState::new();
+        let mut player = ReplayPlayer::new(sample_frames(), 1.0, false);
+
+        assert!(handle_replay_key(key(KeyCode::Char(' ')), &mut player, &mut state));
+        assert!(state.replay.unwrap().paused);
+
+        assert!(handle_replay_key(key(KeyCode::Right), &mut player, &mut state));
+        assert_eq!(state.replay.unwrap().position, 1);
+
+        assert!(handle_replay_key(key(KeyCode::Char('+')), &mut player, &mut state));
+        assert_eq!(state.replay.unwrap().speed, 2.0);
+
+        // Unbound keys fall through to the regular handler.
+        assert!(!handle_replay_key(key(KeyCode::Tab), &mut player, &mut state));
+
+        // While quit confirmation is pending, keys go to the regular handler.
+        state.quit_pending = true;
+        assert!(!handle_replay_key(key(KeyCode::Right), &mut player, &mut state));
+        assert_eq!(state.replay.unwrap().position, 1);
+    }
+}
@@ -13,6 +13,7 @@ use futures::StreamExt;
 use genai::chat::ChatMessage;
 
 use crate::agent::agent_loop::{run_agent_session, ShutdownReason};
+use crate::agent::discoveries::load_discoveries;
 use crate::config::AppConfig;
 use crate::safety::SafetyLayer;
 use crate::tui::app_state::AppState;
@@ -33,7 +34,15 @@ pub async fn run_tui(
     config: &AppConfig,
     _safety: &SafetyLayer,
     shutdown: Arc<AtomicBool>,
+    session_number: u32,
+    carryover_messages: Vec<ChatMessage>,
 ) -> anyhow::Result<()> {
+    // -- Load discoveries flagged in earlier sessions (a bad store is not fatal).
+    let prior_discoveries = load_discoveries(&config.workspace).unwrap_or_else(|e| {
+        tracing::warn!("Failed to load discoveries: {e}");
+        Vec::new()
+    });
+
     // -- Initialize terminal (raw mode + alternate screen + panic hook).
     let mut terminal = ratatui::init();
 
@@ -46,6 +55,11 @@ pub async fn run_tui(
 
     // -- Create application state.
     let mut app_state = AppState::new();
+    app_state.session_number = session_number;
+    app_state.discoveries = prior_discoveries
+        .iter()
+        .map(|d| (d.timestamp.clone(), d.summary()))
+        .collect();
 
     // -- Create async keyboard event stream.
     let mut key_stream = EventStream::new();
@@ -70,8 +84,8 @@ pub async fn run_tui(
             }
         };
 
-        let mut session_number: u32 = 1;
-        let mut carryover_messages: Vec<ChatMessage> = Vec::new();
+        let mut session_number = session_number;
+        let mut carryover_messages = carryover_messages;
 
         loop {
             let result = run_agent_session(
@@ -103,7 +117,8 @@ pub async fn run_tui(
                         carryover_messages = carry;
                     }
                     ShutdownReason::UserShutdown
-                    | ShutdownReason::MaxTurnsOrError(_) => break,
+                    | ShutdownReason::MaxTurnsOrError(_)
+                    | ShutdownReason::Completed { .. } => break,
                 },
                 Err(_) => break,
             }
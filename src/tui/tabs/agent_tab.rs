@@ -1,14 +1,16 @@
 //! Agent tab rendering (Tab 1).
 //!
 //! Displays the log stream as the primary content, with an optional
-//! sub-agent tree panel at the bottom (placeholder for Phase 5).
+//! sub-agent tree panel at the bottom.
 
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Rect};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
-use crate::tui::app_state::AppState;
+use crate::tui::app_state::{AppState, SubAgentView};
+use crate::tui::event::AgentState;
 use crate::tui::widgets::log_stream;
 
 /// Render the Agent tab into the given area.
@@ -36,8 +38,8 @@ pub fn render_agent_tab(state: &AppState, area: Rect, buf: &mut Buffer) {
             buf,
         );
 
-        // Sub-agent tree placeholder in bottom portion
-        render_sub_agent_placeholder(chunks[1], buf);
+        // Sub-agent tree in bottom portion
+        render_sub_agent_panel(&state.sub_agents, chunks[1], buf);
     } else {
         // Log stream takes full area
         log_stream::render_log_entries(
@@ -49,10 +51,12 @@ pub fn render_agent_tab(state: &AppState, area: Rect, buf: &mut Buffer) {
     }
 }
 
-/// Render a placeholder for the sub-agent tree panel.
+/// Render the sub-agent tree panel.
 ///
-/// This will be replaced with real sub-agent tree rendering in Phase 5.
-fn render_sub_agent_placeholder(area: Rect, buf: &mut Buffer) {
+/// One line per sub-agent, indented by nesting depth:
+/// `[status] id (turn N, M tools) task -- last activity`.
+/// When more sub-agents exist than fit, the most recent ones are shown.
+fn render_sub_agent_panel(agents: &[SubAgentView], area: Rect, buf: &mut Buffer) {
     let block = Block::default()
         .borders(Borders::ALL)
         .title(" Sub-Agents ");
@@ -64,9 +68,59 @@ fn render_sub_agent_placeholder(area: Rect, buf: &mut Buffer) {
         return;
     }
 
-    let placeholder = Paragraph::new("(No sub-agents -- Phase 5)")
-        .style(Style::default().fg(Color::DarkGray));
-    placeholder.render(inner, buf);
+    if agents.is_empty() {
+        let placeholder = Paragraph::new("(No sub-agents)")
+            .style(Style::default().fg(Color::DarkGray));
+        placeholder.render(inner, buf);
+        return;
+    }
+
+    let skip = agents.len().saturating_sub(inner.height as usize);
+    let lines: Vec<Line<'static>> = agents[skip..].iter().map(sub_agent_line).collect();
+    Paragraph::new(lines).render(inner, buf);
+}
+
+/// Status indicator and color for a sub-agent row.
+fn sub_agent_indicator(agent: &SubAgentView) -> (&'static str, Color) {
+    match agent.status.as_deref() {
+        None => match agent.state {
+            AgentState::Paused => ("[-]", Color::Red),
+            _ => ("[*]", Color::Yellow),
+        },
+        Some("completed") => ("[+]", Color::Green),
+        Some("shutdown") => ("[x]", Color::DarkGray),
+        Some(_) => ("[!]", Color::Red),
+    }
+}
+
+/// Build the display line for one sub-agent.
+fn sub_agent_line(agent: &SubAgentView) -> Line<'static> {
+    let (indicator, color) = sub_agent_indicator(agent);
+    let mut spans = vec![
+        Span::raw("  ".repeat(agent.depth)),
+        Span::styled(format!("{indicator} "), Style::default().fg(color)),
+        Span::styled(
+            agent.id.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(" (turn {}, {} tools) ", agent.turn, agent.tool_calls),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::raw(agent.task.lines().next().unwrap_or("").to_string()),
+    ];
+    if let Some(status) = &agent.status {
+        spans.push(Span::styled(
+            format!(" -- {status}"),
+            Style::default().fg(color),
+        ));
+    } else if !agent.last_activity.is_empty() {
+        spans.push(Span::styled(
+            format!(" -- {}", agent.last_activity),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
 }
 
 #[cfg(test)]
@@ -99,7 +153,7 @@ mod tests {
 
         let content: String = buf.content().iter().map(|c| c.symbol().to_string()).collect();
         assert!(content.contains("Sub-Agents"));
-        assert!(content.contains("Phase 5"));
+        assert!(content.contains("No sub-agents"));
     }
 
     #[test]
@@ -121,8 +175,37 @@ mod tests {
     fn sub_agent_placeholder_renders() {
         let area = Rect::new(0, 0, 40, 5);
         let mut buf = Buffer::empty(area);
-        render_sub_agent_placeholder(area, &mut buf);
+        render_sub_agent_panel(&[], area, &mut buf);
+        let content: String = buf.content().iter().map(|c| c.symbol().to_string()).collect();
+        assert!(content.contains("No sub-agents"));
+    }
+
+    #[test]
+    fn sub_agent_panel_renders_tree() {
+        let mut state = AppState::new();
+        state.apply_event(crate::tui::event::AgentEvent::SubAgentSpawned {
+            agent_id: "sub-1".into(),
+            parent_id: None,
+            task: "survey the data directory".into(),
+        });
+        state.apply_event(crate::tui::event::AgentEvent::SubAgentSpawned {
+            agent_id: "sub-2".into(),
+            parent_id: Some("sub-1".into()),
+            task: "count csv rows".into(),
+        });
+        state.apply_event(crate::tui::event::AgentEvent::SubAgentFinished {
+            agent_id: "sub-2".into(),
+            status: "completed".into(),
+            turns: 3,
+        });
+
+        let area = Rect::new(0, 0, 80, 6);
+        let mut buf = Buffer::empty(area);
+        render_sub_agent_panel(&state.sub_agents, area, &mut buf);
         let content: String = buf.content().iter().map(|c| c.symbol().to_string()).collect();
-        assert!(content.contains("Phase 5"));
+        assert!(content.contains("[*] sub-1"));
+        assert!(content.contains("survey the data directory"));
+        assert!(content.contains("  [+] sub-2"));
+        assert!(content.contains("completed"));
     }
 }
@@ -131,6 +131,15 @@ fn build_log_lines<'a>(entries: &'a [LogEntry], area_width: u16) -> Vec<Line<'a>
                     Style::default().fg(Color::DarkGray),
                 ),
             ]));
+            // A running tool call shows the tail of its streamed output
+            for content_line in live_tail(entry) {
+                for w in wrap_text(content_line, content_width) {
+                    lines.push(Line::from(vec![
+                        Span::raw("    "),
+                        Span::styled(w, Style::default().fg(Color::DarkGray)),
+                    ]));
+                }
+            }
         }
 
         // Blank line between entries for visual separation
@@ -140,6 +149,21 @@ fn build_log_lines<'a>(entries: &'a [LogEntry], area_width: u16) -> Vec<Line<'a>
     lines
 }
 
+/// Streamed output lines shown under a collapsed, still-running tool call.
+const LIVE_TAIL_LINES: usize = 10;
+
+/// The last [`LIVE_TAIL_LINES`] lines of a collapsed tool call's streamed
+/// output. Empty for every other entry.
+fn live_tail(entry: &LogEntry) -> impl Iterator<Item = &str> {
+    let content = if entry.kind == LogEntryKind::ToolCall {
+        entry.full_content.as_str()
+    } else {
+        ""
+    };
+    let skip = content.lines().count().saturating_sub(LIVE_TAIL_LINES);
+    content.lines().skip(skip)
+}
+
 /// Simple word-boundary-unaware text wrapping.
 fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
     if max_width == 0 {
@@ -239,6 +263,9 @@ fn entry_to_line_offset(entries: &[LogEntry], scroll_offset: usize, area_width:
             }
         } else {
             line_count += 1; // collapsed summary
+            for content_line in live_tail(entry) {
+                line_count += wrap_text(content_line, content_width).len();
+            }
         }
 
         // Blank separator
@@ -297,6 +324,28 @@ mod tests {
         assert_eq!(lines.len(), 3);
     }
 
+    #[test]
+    fn build_lines_running_tool_call_shows_output_tail() {
+        let output: Vec<String> = (1..=25).map(|i| format!("line{i}")).collect();
+        let entry = make_entry(LogEntryKind::ToolCall, "shell_exec(make)", &output.join("\n"), false);
+        let entries = [entry];
+        let lines = build_log_lines(&entries, 80);
+        // Header + summary + last 10 output lines + blank = 13
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[2].to_string().trim(), "line16");
+        assert_eq!(lines[11].to_string().trim(), "line25");
+    }
+
+    #[test]
+    fn entry_to_line_offset_counts_live_output() {
+        let entries = [
+            make_entry(LogEntryKind::ToolCall, "shell_exec(make)", "a\nb\nc", false),
+            make_entry(LogEntryKind::Thought, "next", "next", true),
+        ];
+        // Header + summary + 3 output lines + blank
+        assert_eq!(entry_to_line_offset(&entries, 1, 80), 6);
+    }
+
     #[test]
     fn build_lines_session_separator() {
         let entry = LogEntry {
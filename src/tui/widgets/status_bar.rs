@@ -1,7 +1,8 @@
 //! Two-line status bar widget.
 //!
 //! Renders persistent status information at the bottom of the TUI:
-//! - Line 1: Agent state (colored), context pressure gauge, session/turn/tool counters
+//! - Line 1: Agent state (colored), active model, context pressure gauge,
+//!   session/turn/tool counters
 //! - Line 2: Keybind hints for the current context
 
 use ratatui::buffer::Buffer;
@@ -21,13 +22,14 @@ fn agent_state_style(state: AgentState) -> Style {
         AgentState::Executing => Color::Cyan,
         AgentState::Idle => Color::DarkGray,
         AgentState::Paused => Color::Red,
+        AgentState::Retrying { .. } => Color::Magenta,
     };
     Style::default().fg(color).add_modifier(Modifier::BOLD)
 }
 
 /// Render the two-line status bar into the given area.
 ///
-/// Line 1: `[AgentState] | [context gauge] | Session N | Turn N | Tools: N`
+/// Line 1: `[AgentState] | [model] | [context gauge] | Session N | Turn N | Tools: N`
 /// Line 2: `Tab: switch tabs | arrows: scroll | p: pause/resume | e: expand | q: quit`
 pub fn render_status_bar(state: &AppState, area: Rect, buf: &mut Buffer) {
     if area.height == 0 || area.width == 0 {
@@ -47,6 +49,15 @@ pub fn render_status_bar(state: &AppState, area: Rect, buf: &mut Buffer) {
 
     line1_spans.push(sep.clone());
 
+    // Active model (changes when the session falls back to another model)
+    if !state.active_model.is_empty() {
+        line1_spans.push(Span::styled(
+            state.active_model.clone(),
+            Style::default().fg(Color::Blue),
+        ));
+        line1_spans.push(sep.clone());
+    }
+
     // Context gauge
     let gauge_spans = context_gauge::render_context_gauge(state.context_usage_pct);
     line1_spans.extend(gauge_spans);
@@ -66,13 +77,60 @@ pub fn render_status_bar(state: &AppState, area: Rect, buf: &mut Buffer) {
     // Tool call count
     line1_spans.push(Span::raw(format!("Tools: {}", state.tool_call_count)));
 
+    // Replay position (only when replaying a session log)
+    if let Some(replay) = state.replay {
+        line1_spans.push(sep.clone());
+        let mut label = format!(
+            "Replay {}/{} {}x",
+            replay.position, replay.total, replay.speed
+        );
+        if replay.paused {
+            label.push_str(" [paused]");
+        }
+        line1_spans.push(Span::styled(
+            label,
+            Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        ));
+    }
+
     let line1 = Line::from(line1_spans);
 
     // -- Line 2: Keybind hints --
     let hint_style = Style::default().fg(Color::DarkGray);
     let key_style = Style::default().fg(Color::White);
 
-    let line2 = Line::from(vec![
+    let line2 = if state.replay.is_some() {
+        Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Space", key_style),
+            Span::styled(": play/pause", hint_style),
+            Span::styled(" | ", hint_style),
+            Span::styled("\u{2190}\u{2192}", key_style), // "←→"
+            Span::styled(": step", hint_style),
+            Span::styled(" | ", hint_style),
+            Span::styled("[ ]", key_style),
+            Span::styled(": seek turn", hint_style),
+            Span::styled(" | ", hint_style),
+            Span::styled("+ -", key_style),
+            Span::styled(": speed", hint_style),
+            Span::styled(" | ", hint_style),
+            Span::styled("\u{2191}\u{2193}", key_style), // "↑↓"
+            Span::styled(": scroll", hint_style),
+            Span::styled(" | ", hint_style),
+            Span::styled("q", key_style),
+            Span::styled(": quit", hint_style),
+        ])
+    } else {
+        live_keybind_hints(key_style, hint_style)
+    };
+
+    let paragraph = Paragraph::new(vec![line1, line2]);
+    paragraph.render(area, buf);
+}
+
+/// Keybind hints for a live agent session.
+fn live_keybind_hints(key_style: Style, hint_style: Style) -> Line<'static> {
+    Line::from(vec![
         Span::raw(" "),
         Span::styled("Tab", key_style),
         Span::styled(": switch tabs", hint_style),
@@ -88,10 +146,7 @@ pub fn render_status_bar(state: &AppState, area: Rect, buf: &mut Buffer) {
         Span::styled(" | ", hint_style),
         Span::styled("q", key_style),
         Span::styled(": quit", hint_style),
-    ]);
-
-    let paragraph = Paragraph::new(vec![line1, line2]);
-    paragraph.render(area, buf);
+    ])
 }
 
 #[cfg(test)]
@@ -152,6 +207,37 @@ mod tests {
         assert!(content.contains("65%"));
     }
 
+    #[test]
+    fn render_status_bar_shows_retry_progress() {
+        let mut state = AppState::new();
+        state.agent_state = AgentState::Retrying {
+            attempt: 2,
+            max_attempts: 5,
+        };
+
+        let area = Rect::new(0, 0, 100, 2);
+        let mut buf = Buffer::empty(area);
+        render_status_bar(&state, area, &mut buf);
+
+        let content: String = buf.content().iter().map(|c| c.symbol().to_string()).collect();
+        assert!(content.contains("Retrying (2/5)"));
+    }
+
+    #[test]
+    fn render_status_bar_shows_active_model() {
+        let mut state = AppState::new();
+        state.apply_event(crate::tui::event::AgentEvent::ModelChanged {
+            model: "qwen2.5:7b".into(),
+        });
+
+        let area = Rect::new(0, 0, 100, 2);
+        let mut buf = Buffer::empty(area);
+        render_status_bar(&state, area, &mut buf);
+
+        let content: String = buf.content().iter().map(|c| c.symbol().to_string()).collect();
+        assert!(content.contains("qwen2.5:7b"));
+    }
+
     #[test]
     fn keybind_hints_present() {
         let state = AppState::new();
@@ -164,4 +250,22 @@ mod tests {
         assert!(content.contains("scroll"));
         assert!(content.contains("quit"));
     }
+
+    #[test]
+    fn replay_status_shown_when_replaying() {
+        let mut state = AppState::new();
+        state.replay = Some(crate::tui::app_state::ReplayStatus {
+            position: 12,
+            total: 300,
+            speed: 2.0,
+            paused: true,
+        });
+        let area = Rect::new(0, 0, 120, 2);
+        let mut buf = Buffer::empty(area);
+        render_status_bar(&state, area, &mut buf);
+
+        let content: String = buf.content().iter().map(|c| c.symbol().to_string()).collect();
+        assert!(content.contains("Replay 12/300 2x [paused]"));
+        assert!(content.contains("play/pause"));
+    }
 }
@@ -1,3 +1,4 @@
+use ouro::config::BlocklistEntry;
 use ouro::safety::command_filter::{BlockedCommand, CommandFilter};
 use ouro::safety::defaults::default_blocklist;
 
@@ -319,19 +320,21 @@ fn test_default_blocklist_is_nonempty() {
 #[test]
 fn test_default_blocklist_patterns_are_valid_regex() {
     let blocklist = default_blocklist();
-    for (pattern, reason) in &blocklist {
-        assert!(
-            regex::Regex::new(pattern).is_ok(),
-            "Pattern '{}' (reason: '{}') should be valid regex",
-            pattern, reason
-        );
+    for rule in &blocklist {
+        for pattern in [&rule.pattern, &rule.program, &rule.args, &rule.redirect].into_iter().flatten() {
+            assert!(
+                regex::Regex::new(pattern).is_ok(),
+                "Pattern '{}' (reason: '{}') should be valid regex",
+                pattern, rule.reason
+            );
+        }
     }
 }
 
 #[test]
 fn test_default_blocklist_covers_all_categories() {
     let blocklist = default_blocklist();
-    let reasons: Vec<&str> = blocklist.iter().map(|(_, r)| r.as_str()).collect();
+    let reasons: Vec<&str> = blocklist.iter().map(|r| r.reason.as_str()).collect();
 
     // Check that each major category is represented
     assert!(reasons.iter().any(|r| r.to_lowercase().contains("sudo") || r.to_lowercase().contains("privilege")),
@@ -353,3 +356,226 @@ fn test_default_blocklist_covers_all_categories() {
     assert!(reasons.iter().any(|r| r.to_lowercase().contains("reboot")),
         "blocklist should cover system reboot");
 }
+
+// ============================================================
+// Shell-aware rules
+// ============================================================
+
+#[test]
+fn test_blocks_programs_hidden_by_quoting_paths_and_wrappers() {
+    let filter = CommandFilter::from_defaults().unwrap();
+    for command in [
+        r"s\udo apt install foo",
+        "'sudo' ls",
+        "/usr/bin/sudo ls",
+        "echo hi && ./reboot_helper; /sbin/reboot",
+        "eval \"sudo ls\"",
+        "sh -c 'reboot'",
+        "bash -lc \"echo x | sudo tee f\"",
+        "env FOO=1 sudo ls",
+        "nice -n 10 timeout 5 shutdown now",
+        "ls | xargs -I{} sudo rm {}",
+        "find . -name x -exec rm -rf / \\;",
+        "cat $(sh -c reboot)",
+    ] {
+        assert!(filter.check(command).is_some(), "should block: {command}");
+    }
+}
+
+#[test]
+fn test_blocks_dynamic_program_names() {
+    let filter = CommandFilter::from_defaults().unwrap();
+    for command in [
+        "$CMD ls",
+        "$CC -o main main.c",
+        "$(echo rm) -rf x",
+        "`printf ls` -la",
+        "\"$(which python)\" run.py",
+        "eval \"$X\"",
+        "sh -c \"$X\"",
+    ] {
+        let blocked = filter.check(command);
+        assert!(blocked.is_some(), "should block: {command}");
+        assert!(blocked.unwrap().reason.contains("run time"), "{command}");
+    }
+}
+
+#[test]
+fn test_allows_dynamic_program_names_without_program_rules() {
+    let filter = CommandFilter::from_rules(&[BlocklistEntry::pattern(r"rm\s+-rf\s+/", "no")]).unwrap();
+    for command in ["$CC -o main main.c", "eval \"$X\""] {
+        assert!(filter.check(command).is_none(), "should allow: {command}");
+    }
+}
+
+#[test]
+fn test_allows_blocked_names_as_plain_text() {
+    let filter = CommandFilter::from_defaults().unwrap();
+    for command in [
+        "echo \"don't reboot the server\"",
+        "grep -r shutdown src/",
+        "git commit -m 'halt on error'",
+        "ls /etc",
+        "cat /proc/cpuinfo > cpuinfo.txt",
+        "rm -rf ./build /tmp/x",
+        "echo $HOME",
+    ] {
+        assert!(filter.check(command).is_none(), "should allow: {command}");
+    }
+}
+
+#[test]
+fn test_redirect_rules_only_match_write_targets() {
+    let filter = CommandFilter::from_defaults().unwrap();
+    assert!(filter.check("echo x >> /etc/hosts").is_some());
+    assert!(filter.check("echo x 2>/proc/sysrq-trigger").is_some());
+    assert!(filter.check("(echo x) > /usr/local/bin/foo").is_some());
+    assert!(filter.check("wc -l < /etc/hosts").is_none(), "reading is allowed");
+}
+
+#[test]
+fn test_rule_fields_must_match_the_same_command() {
+    let rules = vec![BlocklistEntry::program("git", "no force push").with_args("^--force$")];
+    let filter = CommandFilter::from_rules(&rules).unwrap();
+    assert!(filter.check("git push --force origin main").is_some());
+    assert!(filter.check("cd repo && git push --force").is_some());
+    assert!(filter.check("git push origin main").is_none());
+    assert!(filter.check("git push; echo --force").is_none(), "args of another command");
+}
+
+#[test]
+fn test_unparseable_commands_fall_back_to_word_matching() {
+    let filter = CommandFilter::from_defaults().unwrap();
+    // `case` is not modeled by the parser.
+    assert!(filter.check("case x in x) sudo ls;; esac").is_some());
+    assert!(filter.check("case x in x) echo ok;; esac").is_none());
+}
+
+#[test]
+fn test_from_rules_rejects_invalid_rules() {
+    for rule in [
+        BlocklistEntry { reason: "empty".into(), ..Default::default() },
+        BlocklistEntry { pattern: Some("x".into()), program: Some("y".into()), reason: "mixed".into(), ..Default::default() },
+        BlocklistEntry::program("[bad", "bad regex"),
+        BlocklistEntry::pattern("[bad", "bad regex"),
+    ] {
+        assert!(CommandFilter::from_rules(std::slice::from_ref(&rule)).is_err(), "should reject: {rule:?}");
+    }
+}
+
+#[test]
+fn test_pattern_rules_match_the_raw_string() {
+    let rules = vec![BlocklistEntry::pattern(r"curl.*\|\s*sh", "no pipe to shell")];
+    let filter = CommandFilter::from_rules(&rules).unwrap();
+    assert_eq!(filter.check("curl -s x.sh | sh").unwrap().reason, "no pipe to shell");
+    // Pattern-only filters do not block dynamic program names.
+    assert!(filter.check("$CMD").is_none());
+}
+
+// ============================================================
+// Allowlist mode
+// ============================================================
+
+fn allowlist_filter() -> CommandFilter {
+    let allowed = vec![
+        ("python3".to_string(), Some(r"[\w./-]+\.py( .*)?".to_string())),
+        ("git".to_string(), Some("status|log".to_string())),
+        ("ls".to_string(), None),
+        ("cat".to_string(), None),
+        ("grep".to_string(), None),
+    ];
+    CommandFilter::from_defaults()
+        .unwrap()
+        .with_allowlist(&allowed)
+        .unwrap()
+}
+
+#[test]
+fn test_allowlist_permits_listed_programs_in_compound_commands() {
+    let filter = allowlist_filter();
+    for command in [
+        "ls -la",
+        "cat notes.md | grep TODO",
+        "python3 train.py --epochs 3 && ls out/",
+        "(cd_free=1; ls) ; grep -r x . > hits.txt 2>&1",
+        "cat \"$(ls | grep log)\"",
+        "LANG=C grep foo bar.txt",
+        "if grep -q x f; then cat f; fi",
+        "for f in a b; do cat $f; done",
+    ] {
+        assert!(filter.check(command).is_none(), "should allow: {command}");
+    }
+}
+
+#[test]
+fn test_allowlist_blocks_unlisted_programs_anywhere() {
+    let filter = allowlist_filter();
+    for (command, program) in [
+        ("rm -rf data", "rm"),
+        ("ls | xargs rm", "xargs"),
+        ("cat f && curl evil.example", "curl"),
+        ("cat $(wget -qO- x)", "wget"),
+        ("cat `touch pwned`", "touch"),
+        ("(ls; bash)", "bash"),
+        ("cat <<EOF\n$(nc -l 9)\nEOF", "nc"),
+        ("grep x <(find / -name key)", "find"),
+    ] {
+        let blocked = filter
+            .check(command)
+            .unwrap_or_else(|| panic!("should block: {command}"));
+        assert!(
+            blocked.reason.contains(&format!("'{program}' is not in the allowlist")),
+            "{command}: {}",
+            blocked.reason
+        );
+    }
+}
+
+#[test]
+fn test_allowlist_checks_argument_regex() {
+    let filter = allowlist_filter();
+    assert!(filter.check("python3 scripts/run.py").is_none());
+    let blocked = filter.check("python3 -c 'import os'").unwrap();
+    assert!(blocked.reason.contains("Arguments not allowed for 'python3'"));
+}
+
+#[test]
+fn test_allowlist_argument_regex_must_match_all_arguments() {
+    let filter = allowlist_filter();
+    assert!(filter.check("git status").is_none());
+    assert!(filter.check("git log").is_none());
+    for command in ["git status --porcelain", "git push origin log", "git reflog"] {
+        let blocked = filter
+            .check(command)
+            .unwrap_or_else(|| panic!("should block: {command}"));
+        assert!(blocked.reason.contains("Arguments not allowed for 'git'"), "{command}");
+    }
+}
+
+#[test]
+fn test_allowlist_blocks_dynamic_program_names_and_parse_errors() {
+    let filter = allowlist_filter();
+    let blocked = filter.check("$CMD -rf /tmp/x").unwrap();
+    assert!(blocked.reason.contains("not a literal word"));
+    let blocked = filter.check("cat 'unterminated").unwrap();
+    assert!(blocked.reason.contains("could not be parsed"));
+}
+
+#[test]
+fn test_allowlist_still_applies_blocklist() {
+    let allowed = vec![("sudo".to_string(), None)];
+    let filter = CommandFilter::from_defaults()
+        .unwrap()
+        .with_allowlist(&allowed)
+        .unwrap();
+    assert!(filter.check("sudo ls").is_some());
+}
+
+#[test]
+fn test_allowlist_with_invalid_argument_regex_returns_error() {
+    let allowed = vec![("ls".to_string(), Some("[bad".to_string()))];
+    assert!(CommandFilter::from_defaults()
+        .unwrap()
+        .with_allowlist(&allowed)
+        .is_err());
+}
@@ -0,0 +1,20 @@
+use ouro::config::{AppConfig, PartialConfig};
+use std::path::Path;
+
+/// A config for tests: the given workspace, no protected paths, and a
+/// single model request attempt without backoff. Set anything else with
+/// struct update syntax: `AppConfig { shell_timeout_secs: 5, ..test_config(ws) }`.
+pub fn test_config(workspace: &Path) -> AppConfig {
+    AppConfig {
+        protected_paths: Vec::new(),
+        ..PartialConfig {
+            model: Some("test-model".to_string()),
+            workspace: Some(workspace.to_path_buf()),
+            retry_max_attempts: Some(1),
+            retry_base_delay_ms: Some(0),
+            retry_max_delay_ms: Some(0),
+            ..Default::default()
+        }
+        .finalize()
+    }
+}
@@ -0,0 +1,59 @@
+use ouro::safety::domain_filter::DomainFilter;
+use reqwest::Url;
+
+fn url(s: &str) -> Url {
+    Url::parse(s).unwrap()
+}
+
+fn list(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+// ============================================================
+// Scheme and host checks
+// ============================================================
+
+#[test]
+fn test_empty_lists_allow_any_http_url() {
+    let filter = DomainFilter::new(&[], &[]);
+    assert!(filter.check(&url("https://example.com/page")).is_none());
+    assert!(filter.check(&url("http://127.0.0.1:8080/")).is_none());
+}
+
+#[test]
+fn test_rejects_non_http_schemes() {
+    let filter = DomainFilter::new(&[], &[]);
+    assert!(filter.check(&url("file:///etc/passwd")).is_some());
+    assert!(filter.check(&url("ftp://example.com/")).is_some());
+}
+
+// ============================================================
+// Allow and deny lists
+// ============================================================
+
+#[test]
+fn test_blocked_domain_covers_subdomains_only() {
+    let filter = DomainFilter::new(&[], &list(&["Example.com"]));
+    assert!(filter.check(&url("https://example.com/")).is_some());
+    assert!(filter.check(&url("https://docs.EXAMPLE.com/")).is_some());
+    assert!(filter.check(&url("https://badexample.com/")).is_none());
+}
+
+#[test]
+fn test_allowlist_restricts_other_domains() {
+    let filter = DomainFilter::new(&list(&["*.rust-lang.org", "docs.rs"]), &[]);
+    assert!(filter.check(&url("https://doc.rust-lang.org/std")).is_none());
+    assert!(filter.check(&url("https://docs.rs/serde")).is_none());
+
+    let reason = filter.check(&url("https://crates.io/")).unwrap();
+    assert!(reason.contains("allowed_domains"));
+}
+
+#[test]
+fn test_denylist_wins_over_allowlist() {
+    let filter = DomainFilter::new(&list(&["rust-lang.org"]), &list(&["blog.rust-lang.org"]));
+    assert!(filter.check(&url("https://www.rust-lang.org/")).is_none());
+
+    let reason = filter.check(&url("https://blog.rust-lang.org/")).unwrap();
+    assert!(reason.contains("blocked_domains"));
+}
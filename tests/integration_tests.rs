@@ -1,4 +1,8 @@
+mod common;
+
 use ouro::config::AppConfig;
+use ouro::exec::{JobRegistry, JobStatus};
+use ouro::safety::protected::RESTORE_MAX_BYTES;
 use ouro::safety::SafetyLayer;
 use std::path::PathBuf;
 use tempfile::TempDir;
@@ -9,19 +13,23 @@ fn setup_workspace() -> TempDir {
     tempfile::tempdir().expect("failed to create temp dir")
 }
 
+/// Poll until the job is no longer running.
+async fn wait_for_job(jobs: &JobRegistry, job_id: &str) -> JobStatus {
+    for _ in 0..100 {
+        let (status, _) = jobs.status(job_id, 0).unwrap();
+        if status.state != "running" {
+            return status;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("job {job_id} did not finish");
+}
+
 fn test_config(workspace: &std::path::Path, security_log: PathBuf, timeout: u64) -> AppConfig {
     AppConfig {
-        model: "test-model".to_string(),
-        workspace: workspace.to_path_buf(),
         shell_timeout_secs: timeout,
-        context_limit: 8000,
-        blocked_patterns: ouro::safety::defaults::default_blocklist(),
         security_log_path: security_log,
-        soft_threshold_pct: 0.70,
-        hard_threshold_pct: 0.90,
-        carryover_turns: 5,
-        max_restarts: None,
-        auto_restart: true,
+        ..common::test_config(workspace)
     }
 }
 
@@ -36,7 +44,7 @@ async fn test_safety_layer_blocks_sudo() {
     let config = test_config(ws.path(), security_log, 5);
     let layer = SafetyLayer::new(&config).unwrap();
 
-    let result = layer.execute("sudo ls").await.unwrap();
+    let result = layer.execute("sudo ls", None).await.unwrap();
 
     // Should be blocked, not executed.
     assert_eq!(result.exit_code, Some(126));
@@ -58,7 +66,7 @@ async fn test_safety_layer_blocks_rm_rf_root() {
     let config = test_config(ws.path(), security_log, 5);
     let layer = SafetyLayer::new(&config).unwrap();
 
-    let result = layer.execute("rm -rf /").await.unwrap();
+    let result = layer.execute("rm -rf /", None).await.unwrap();
 
     assert_eq!(result.exit_code, Some(126));
     let parsed: serde_json::Value = serde_json::from_str(&result.stderr).unwrap();
@@ -76,7 +84,7 @@ async fn test_safety_layer_executes_echo() {
     let config = test_config(ws.path(), security_log, 5);
     let layer = SafetyLayer::new(&config).unwrap();
 
-    let result = layer.execute("echo hello").await.unwrap();
+    let result = layer.execute("echo hello", None).await.unwrap();
 
     assert_eq!(result.stdout, "hello\n");
     assert_eq!(result.exit_code, Some(0));
@@ -91,7 +99,7 @@ async fn test_safety_layer_executes_in_workspace_dir() {
     let config = test_config(ws.path(), security_log, 5);
     let layer = SafetyLayer::new(&config).unwrap();
 
-    let result = layer.execute("pwd").await.unwrap();
+    let result = layer.execute("pwd", None).await.unwrap();
     assert_eq!(result.stdout.trim(), canonical.to_str().unwrap());
 }
 
@@ -107,7 +115,7 @@ async fn test_safety_layer_timeout() {
     let layer = SafetyLayer::new(&config).unwrap();
 
     let start = std::time::Instant::now();
-    let result = layer.execute("sleep 60").await.unwrap();
+    let result = layer.execute("sleep 60", None).await.unwrap();
     let elapsed = start.elapsed();
 
     assert!(result.timed_out, "should report timed_out");
@@ -131,7 +139,7 @@ async fn test_security_log_created_on_blocked_command() {
     let layer = SafetyLayer::new(&config).unwrap();
 
     // Execute a blocked command.
-    let _ = layer.execute("sudo ls").await.unwrap();
+    let _ = layer.execute("sudo ls", None).await.unwrap();
 
     // Security log should exist.
     assert!(security_log.exists(), "security log should be created");
@@ -157,9 +165,9 @@ async fn test_security_log_appends_multiple_entries() {
     let layer = SafetyLayer::new(&config).unwrap();
 
     // Execute multiple blocked commands.
-    let _ = layer.execute("sudo ls").await.unwrap();
-    let _ = layer.execute("sudo rm foo").await.unwrap();
-    let _ = layer.execute("reboot").await.unwrap();
+    let _ = layer.execute("sudo ls", None).await.unwrap();
+    let _ = layer.execute("sudo rm foo", None).await.unwrap();
+    let _ = layer.execute("reboot", None).await.unwrap();
 
     let contents = std::fs::read_to_string(&security_log).unwrap();
     let lines: Vec<&str> = contents.lines().collect();
@@ -180,7 +188,7 @@ async fn test_no_security_log_for_allowed_commands() {
     let layer = SafetyLayer::new(&config).unwrap();
 
     // Execute an allowed command.
-    let _ = layer.execute("echo hello").await.unwrap();
+    let _ = layer.execute("echo hello", None).await.unwrap();
 
     // Security log should NOT exist (no blocked commands).
     assert!(
@@ -203,3 +211,230 @@ async fn test_workspace_root_matches_config() {
 
     assert_eq!(layer.workspace_root(), canonical.as_path());
 }
+
+// ============================================================
+// SafetyLayer restores protected files after shell commands
+// ============================================================
+
+#[tokio::test]
+async fn test_safety_layer_restores_protected_files() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("logs").join("security.log");
+    std::fs::create_dir_all(ws.path().join("logs")).unwrap();
+    let config = AppConfig {
+        protected_paths: vec!["ouro.toml".into(), "archive/**".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    std::fs::write(ws.path().join("ouro.toml"), "[safety]\n").unwrap();
+    std::fs::create_dir_all(ws.path().join("archive")).unwrap();
+    std::fs::write(ws.path().join("archive").join("old.md"), "keep").unwrap();
+    let layer = SafetyLayer::new(&config).unwrap();
+
+    let result = layer
+        .execute("echo 'blocked_patterns = []' > ouro.toml; rm archive/old.md; echo x > archive/new.md; echo done", None)
+        .await
+        .unwrap();
+
+    // The command itself ran...
+    assert_eq!(result.stdout, "done\n");
+    assert_eq!(result.exit_code, Some(0));
+    // ...but every protected change was undone and reported.
+    assert_eq!(std::fs::read_to_string(ws.path().join("ouro.toml")).unwrap(), "[safety]\n");
+    assert_eq!(std::fs::read_to_string(ws.path().join("archive/old.md")).unwrap(), "keep");
+    assert!(!ws.path().join("archive/new.md").exists());
+    assert!(result.stderr.contains("ouro.toml (modified)"), "stderr: {}", result.stderr);
+    assert!(result.stderr.contains("archive/old.md (deleted)"));
+    assert!(result.stderr.contains("archive/new.md (created)"));
+
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert!(log.contains("Changed protected files"), "security log: {log}");
+}
+
+#[tokio::test]
+async fn test_sub_agent_safety_layer_restores_parent_protected_files() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: vec!["ouro.toml".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    std::fs::write(ws.path().join("ouro.toml"), "[safety]\n").unwrap();
+    let parent = SafetyLayer::new(&config).unwrap();
+    let child_config = AppConfig {
+        workspace: ws.path().join("sub"),
+        ..config.clone()
+    };
+    let child = parent.for_subdirectory(&child_config).unwrap();
+
+    let result = child
+        .execute("echo 'blocked_patterns = []' > ../ouro.toml; echo x > ouro.toml", None)
+        .await
+        .unwrap();
+
+    // The top-level config is put back; the sub-agent's own ouro.toml is
+    // not the protected one.
+    assert_eq!(std::fs::read_to_string(ws.path().join("ouro.toml")).unwrap(), "[safety]\n");
+    assert_eq!(std::fs::read_to_string(ws.path().join("sub/ouro.toml")).unwrap(), "x\n");
+    assert!(result.stderr.contains("ouro.toml (modified)"), "stderr: {}", result.stderr);
+    assert!(!result.stderr.contains("sub/ouro.toml"), "stderr: {}", result.stderr);
+    assert_eq!(child.protected_pattern(&ws.path().join("ouro.toml")), Some("ouro.toml"));
+    assert_eq!(child.protected_pattern(&ws.path().join("sub/ouro.toml")), None);
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert!(log.contains("Changed protected files"), "security log: {log}");
+}
+
+#[tokio::test]
+async fn test_safety_layer_reports_protected_files_too_large_to_restore() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: vec!["data/**".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    std::fs::create_dir_all(ws.path().join("data")).unwrap();
+    let big = vec![b'x'; RESTORE_MAX_BYTES as usize + 1];
+    std::fs::write(ws.path().join("data/big.bin"), &big).unwrap();
+    std::fs::write(ws.path().join("data/small.txt"), "keep").unwrap();
+    let layer = SafetyLayer::new(&config).unwrap();
+
+    let result = layer
+        .execute("echo y > data/big.bin; echo y > data/small.txt", None)
+        .await
+        .unwrap();
+
+    // The small file is put back; the large one was never copied.
+    assert_eq!(std::fs::read_to_string(ws.path().join("data/small.txt")).unwrap(), "keep");
+    assert_eq!(std::fs::read_to_string(ws.path().join("data/big.bin")).unwrap(), "y\n");
+    assert!(result.stderr.contains("data/small.txt (modified)"), "stderr: {}", result.stderr);
+    assert!(result.stderr.contains("data/big.bin (modified; too large to restore)"));
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert!(log.contains("data/big.bin (modified; too large to restore)"), "security log: {log}");
+}
+
+#[tokio::test]
+async fn test_safety_layer_ignores_protected_files_touched_without_changes() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: vec!["ouro.toml".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    std::fs::write(ws.path().join("ouro.toml"), "[safety]\n").unwrap();
+    let layer = SafetyLayer::new(&config).unwrap();
+
+    let result = layer.execute("touch ouro.toml", None).await.unwrap();
+
+    assert!(result.stderr.is_empty(), "stderr: {}", result.stderr);
+    assert!(!security_log.exists());
+}
+
+#[tokio::test]
+async fn test_safety_layer_leaves_unprotected_changes_alone() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: vec!["ouro.toml".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    let layer = SafetyLayer::new(&config).unwrap();
+
+    let result = layer.execute("echo notes > notes.md", None).await.unwrap();
+
+    assert!(result.stderr.is_empty());
+    assert_eq!(std::fs::read_to_string(ws.path().join("notes.md")).unwrap(), "notes\n");
+    assert!(!security_log.exists());
+}
+
+// ============================================================
+// SafetyLayer background jobs
+// ============================================================
+
+#[tokio::test]
+async fn test_safety_layer_blocks_jobs_like_commands() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = test_config(ws.path(), security_log.clone(), 5);
+    let layer = SafetyLayer::new(&config).unwrap();
+    let jobs = JobRegistry::new(4);
+
+    let err = layer.start_job(&jobs, "sudo sleep 100").await.unwrap_err();
+
+    assert!(err.to_string().starts_with("blocked:"), "error: {err}");
+    assert!(jobs.list().is_empty());
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert!(log.contains("sudo sleep 100"));
+}
+
+#[tokio::test]
+async fn test_safety_layer_restores_protected_files_when_job_exits() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: vec!["ouro.toml".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    std::fs::write(ws.path().join("ouro.toml"), "[safety]\n").unwrap();
+    let layer = SafetyLayer::new(&config).unwrap();
+    let jobs = JobRegistry::new(4);
+
+    let job = layer.start_job(&jobs, "echo 'blocked_patterns = []' > ouro.toml").await.unwrap();
+    let status = wait_for_job(&jobs, &job.job_id).await;
+
+    assert_eq!(status.exit_code, Some(0));
+    assert_eq!(std::fs::read_to_string(ws.path().join("ouro.toml")).unwrap(), "[safety]\n");
+    let (_, output) = jobs.status(&job.job_id, 10).unwrap();
+    assert!(output.contains("ouro.toml (modified)"), "output: {output}");
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert!(log.contains("Changed protected files"), "security log: {log}");
+}
+
+#[tokio::test]
+async fn test_job_exit_keeps_security_log_entries_written_while_it_ran() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: ouro::safety::defaults::default_protected_paths(ws.path(), &security_log),
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    let layer = SafetyLayer::new(&config).unwrap();
+    let jobs = JobRegistry::new(4);
+
+    let job = layer.start_job(&jobs, "sleep 0.5").await.unwrap();
+    layer.execute("sudo ls", None).await.unwrap();
+    let result = layer.execute("echo ok", None).await.unwrap();
+    wait_for_job(&jobs, &job.job_id).await;
+
+    assert!(result.stderr.is_empty(), "stderr: {}", result.stderr);
+    let (_, output) = jobs.status(&job.job_id, 10).unwrap();
+    assert!(!output.contains("[harness]"), "output: {output}");
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert!(log.contains("sudo ls"), "security log: {log}");
+    assert!(!log.contains("Changed protected files"), "security log: {log}");
+}
+
+#[tokio::test]
+async fn test_commands_undo_protected_changes_made_by_running_jobs() {
+    let ws = setup_workspace();
+    let security_log = ws.path().join("security.log");
+    let config = AppConfig {
+        protected_paths: vec!["ouro.toml".into()],
+        ..test_config(ws.path(), security_log.clone(), 5)
+    };
+    std::fs::write(ws.path().join("ouro.toml"), "[safety]\n").unwrap();
+    let layer = SafetyLayer::new(&config).unwrap();
+    let jobs = JobRegistry::new(4);
+
+    let job = layer.start_job(&jobs, "echo 'blocked_patterns = []' > ouro.toml; sleep 30").await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let result = layer.execute("true", None).await.unwrap();
+
+    // The command's check catches the job's change...
+    assert_eq!(std::fs::read_to_string(ws.path().join("ouro.toml")).unwrap(), "[safety]\n");
+    assert!(result.stderr.contains("A background job changed protected files"), "stderr: {}", result.stderr);
+    // ...so the job is not blamed again when it ends.
+    jobs.kill(&job.job_id).await.unwrap();
+    let (_, output) = jobs.status(&job.job_id, 10).unwrap();
+    assert!(!output.contains("[harness]"), "output: {output}");
+    let log = std::fs::read_to_string(&security_log).unwrap();
+    assert_eq!(log.matches("Changed protected files").count(), 1, "security log: {log}");
+}
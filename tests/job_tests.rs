@@ -0,0 +1,199 @@
+use ouro::exec::{JobRegistry, JobStatus, ResourceLimits};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn setup_workspace() -> TempDir {
+    tempfile::tempdir().expect("failed to create temp dir")
+}
+
+fn start(jobs: &JobRegistry, command: &str, dir: &Path) -> JobStatus {
+    jobs.start(command, dir, &ResourceLimits::default(), None, None)
+        .unwrap()
+}
+
+async fn wait_for_exit(jobs: &JobRegistry, job_id: &str) -> JobStatus {
+    for _ in 0..100 {
+        let (status, _) = jobs.status(job_id, 0).unwrap();
+        if status.state != "running" {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("job {job_id} did not finish");
+}
+
+/// Whether `pid` is running (zombies awaiting their parent do not count).
+fn process_alive(pid: &str) -> bool {
+    let stat = std::fs::read_to_string(Path::new("/proc").join(pid.trim()).join("stat"));
+    // The state follows the parenthesized command name.
+    stat.is_ok_and(|stat| !stat.rsplit(')').next().unwrap_or("").trim_start().starts_with('Z'))
+}
+
+// ============================================================
+// Lifecycle
+// ============================================================
+
+#[tokio::test]
+async fn test_job_runs_in_background_and_reports_exit() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    let job = start(&jobs, "sleep 0.3; echo out; echo err >&2; exit 3", ws.path());
+    assert_eq!(job.job_id, "job-1");
+    assert_eq!(job.state, "running");
+    assert_eq!(job.exit_code, None);
+
+    let status = wait_for_exit(&jobs, &job.job_id).await;
+    assert_eq!(status.state, "exited");
+    assert_eq!(status.exit_code, Some(3));
+    assert_eq!(status.killed_by, None);
+    let (_, output) = jobs.status(&job.job_id, 10).unwrap();
+    assert!(output.contains("out\n") && output.contains("err\n"), "output: {output}");
+}
+
+#[tokio::test]
+async fn test_job_status_returns_output_tail() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    let job = start(&jobs, "seq 100", ws.path());
+    wait_for_exit(&jobs, &job.job_id).await;
+
+    let (_, output) = jobs.status(&job.job_id, 3).unwrap();
+    assert_eq!(output, "98\n99\n100\n");
+    let (_, output) = jobs.status(&job.job_id, 0).unwrap();
+    assert_eq!(output, "");
+    assert!(jobs.status("job-2", 3).is_none());
+}
+
+#[tokio::test]
+async fn test_job_list_includes_finished_jobs() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    let first = start(&jobs, "true", ws.path());
+    wait_for_exit(&jobs, &first.job_id).await;
+    start(&jobs, "sleep 30", ws.path());
+
+    let list = jobs.list();
+    let states: Vec<(&str, &str)> = list.iter().map(|j| (j.job_id.as_str(), j.state)).collect();
+    assert_eq!(states, [("job-1", "exited"), ("job-2", "running")]);
+}
+
+// ============================================================
+// Killing
+// ============================================================
+
+#[tokio::test]
+async fn test_job_kill_stops_whole_process_group() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    // The shell waits on a background child; both must die.
+    let job = start(&jobs, "sleep 60 & echo $! > child.pid; wait", ws.path());
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let child_pid = std::fs::read_to_string(ws.path().join("child.pid")).unwrap();
+    assert!(process_alive(&child_pid));
+
+    let status = jobs.kill(&job.job_id).await.unwrap();
+    assert_eq!(status.state, "killed");
+    assert_eq!(status.killed_by.as_deref(), Some("job_kill"));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!process_alive(&child_pid));
+
+    let err = jobs.kill(&job.job_id).await.unwrap_err();
+    assert!(err.to_string().contains("already ended"));
+    assert!(jobs.kill("job-7").await.is_err());
+}
+
+#[tokio::test]
+async fn test_dropping_registry_kills_jobs() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    start(&jobs, "echo $$ > shell.pid; sleep 60", ws.path());
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let shell_pid = std::fs::read_to_string(ws.path().join("shell.pid")).unwrap();
+    assert!(process_alive(&shell_pid));
+
+    drop(jobs);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!process_alive(&shell_pid));
+}
+
+#[tokio::test]
+async fn test_dropping_registry_kills_processes_left_by_exited_jobs() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    let job = start(&jobs, "sleep 60 > /dev/null 2>&1 & echo $! > child.pid", ws.path());
+    wait_for_exit(&jobs, &job.job_id).await;
+    let child_pid = std::fs::read_to_string(ws.path().join("child.pid")).unwrap();
+    assert!(process_alive(&child_pid));
+
+    drop(jobs);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!process_alive(&child_pid));
+}
+
+// ============================================================
+// Limits
+// ============================================================
+
+#[tokio::test]
+async fn test_concurrent_job_limit() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(1);
+
+    let first = start(&jobs, "sleep 30", ws.path());
+    let err = jobs
+        .start("sleep 30", ws.path(), &ResourceLimits::default(), None, None)
+        .unwrap_err();
+    assert!(err.to_string().contains("limit is 1"), "error: {err}");
+
+    // A finished job frees its slot.
+    jobs.kill(&first.job_id).await.unwrap();
+    start(&jobs, "sleep 30", ws.path());
+}
+
+#[tokio::test]
+async fn test_zero_limit_disables_jobs() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(0);
+    assert!(jobs
+        .start("true", ws.path(), &ResourceLimits::default(), None, None)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_job_reports_resource_limit() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+    let limits = ResourceLimits {
+        cpu_secs: Some(1),
+        ..Default::default()
+    };
+
+    let job = jobs
+        .start("while :; do :; done", ws.path(), &limits, None, None)
+        .unwrap();
+    let status = wait_for_exit(&jobs, &job.job_id).await;
+    assert_eq!(status.state, "killed");
+    assert_eq!(status.killed_by.as_deref(), Some("cpu"));
+}
+
+#[tokio::test]
+async fn test_exit_hook_note_is_appended_to_output() {
+    let ws = setup_workspace();
+    let jobs = JobRegistry::new(4);
+
+    let hook = Box::new(|| Some("[harness] cleaned up".to_string()));
+    let job = jobs
+        .start("echo hi", ws.path(), &ResourceLimits::default(), None, Some(hook))
+        .unwrap();
+    wait_for_exit(&jobs, &job.job_id).await;
+
+    let (_, output) = jobs.status(&job.job_id, 10).unwrap();
+    assert_eq!(output, "hi\n[harness] cleaned up\n");
+}
@@ -0,0 +1,93 @@
+use ouro::exec::{execute_shell, ExecResult, ResourceLimits, Sandbox};
+use std::path::Path;
+use tempfile::TempDir;
+
+// ─── Helpers ─────────────────────────────────────────────────────────
+
+/// A canonical workspace under /tmp and a sandbox for it, or `None` when this
+/// host cannot create the namespaces (the tests then have nothing to check).
+fn setup(network: bool) -> Option<(TempDir, Sandbox)> {
+    let ws = tempfile::tempdir().expect("failed to create temp dir");
+    let root = std::fs::canonicalize(ws.path()).unwrap();
+    let sandbox = Sandbox::new(&root, network);
+    match sandbox.probe() {
+        Ok(()) => Some((ws, sandbox)),
+        Err(e) => {
+            eprintln!("skipping: sandbox unavailable: {e}");
+            None
+        }
+    }
+}
+
+async fn run(command: &str, dir: &Path, sandbox: &Sandbox) -> ExecResult {
+    execute_shell(command, dir, 10, &ResourceLimits::default(), Some(sandbox), None)
+        .await
+        .unwrap()
+}
+
+fn interfaces(proc_net_dev: &str) -> Vec<String> {
+    proc_net_dev
+        .lines()
+        .skip(2)
+        .filter_map(|line| line.split(':').next())
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+// ─── Filesystem ──────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn workspace_is_writable_and_shared_with_host() {
+    let Some((ws, sandbox)) = setup(false) else { return };
+    let result = run("mkdir -p sub && echo hi > sub/f.txt && pwd", ws.path(), &sandbox).await;
+    assert_eq!(result.exit_code, Some(0), "stderr: {}", result.stderr);
+    assert_eq!(std::fs::read_to_string(ws.path().join("sub/f.txt")).unwrap(), "hi\n");
+    let canonical = std::fs::canonicalize(ws.path()).unwrap();
+    assert_eq!(result.stdout.trim(), canonical.to_str().unwrap());
+}
+
+#[tokio::test]
+async fn rest_of_filesystem_is_read_only() {
+    let Some((ws, sandbox)) = setup(false) else { return };
+    // Must be outside /tmp, or the private tmpfs hides it and the write
+    // fails for a different reason.
+    let outside = tempfile::tempdir_in(env!("CARGO_TARGET_TMPDIR")).unwrap();
+    if outside.path().starts_with("/tmp") {
+        return;
+    }
+    let target = outside.path().join("escaped.txt");
+    let result = run(&format!("echo x > '{}'", target.display()), ws.path(), &sandbox).await;
+    assert_ne!(result.exit_code, Some(0));
+    assert!(result.stderr.contains("Read-only"), "stderr: {}", result.stderr);
+    assert!(!target.exists());
+}
+
+#[tokio::test]
+async fn tmp_is_private() {
+    let Some((ws, sandbox)) = setup(false) else { return };
+    let host_file = tempfile::NamedTempFile::new_in("/tmp").unwrap();
+    let result = run(
+        &format!("test ! -e '{}' && echo scratch > /tmp/scratch && cat /tmp/scratch", host_file.path().display()),
+        ws.path(),
+        &sandbox,
+    )
+    .await;
+    assert_eq!(result.stdout, "scratch\n", "stderr: {}", result.stderr);
+}
+
+// ─── Network ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn network_is_disabled_by_default() {
+    let Some((ws, sandbox)) = setup(false) else { return };
+    let result = run("cat /proc/net/dev", ws.path(), &sandbox).await;
+    assert_eq!(interfaces(&result.stdout), vec!["lo"]);
+}
+
+#[tokio::test]
+async fn network_can_be_allowed() {
+    let Some((ws, sandbox)) = setup(true) else { return };
+    let result = run("cat /proc/net/dev", ws.path(), &sandbox).await;
+    let host = std::fs::read_to_string("/proc/net/dev").unwrap();
+    assert_eq!(interfaces(&result.stdout), interfaces(&host));
+}
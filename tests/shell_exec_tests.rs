@@ -1,4 +1,5 @@
-use ouro::exec::{execute_shell, ExecResult};
+use ouro::exec::shell::OutputStream;
+use ouro::exec::{execute_shell, ExecResult, ResourceLimits};
 use std::time::Instant;
 use tempfile::TempDir;
 
@@ -13,7 +14,7 @@ fn setup_workspace() -> TempDir {
 #[tokio::test]
 async fn test_normal_execution_stdout() {
     let ws = setup_workspace();
-    let result = execute_shell("echo hello", ws.path(), 5).await.unwrap();
+    let result = execute_shell("echo hello", ws.path(), 5, &ResourceLimits::default(), None, None).await.unwrap();
     assert_eq!(result.stdout, "hello\n");
     assert_eq!(result.exit_code, Some(0));
     assert!(!result.timed_out);
@@ -22,7 +23,7 @@ async fn test_normal_execution_stdout() {
 #[tokio::test]
 async fn test_stderr_capture() {
     let ws = setup_workspace();
-    let result = execute_shell("echo err >&2", ws.path(), 5).await.unwrap();
+    let result = execute_shell("echo err >&2", ws.path(), 5, &ResourceLimits::default(), None, None).await.unwrap();
     assert_eq!(result.stderr, "err\n");
     assert_eq!(result.stdout, "");
     assert_eq!(result.exit_code, Some(0));
@@ -32,7 +33,7 @@ async fn test_stderr_capture() {
 #[tokio::test]
 async fn test_exit_code() {
     let ws = setup_workspace();
-    let result = execute_shell("exit 42", ws.path(), 5).await.unwrap();
+    let result = execute_shell("exit 42", ws.path(), 5, &ResourceLimits::default(), None, None).await.unwrap();
     assert_eq!(result.exit_code, Some(42));
     assert!(!result.timed_out);
 }
@@ -41,7 +42,7 @@ async fn test_exit_code() {
 async fn test_working_directory() {
     let ws = setup_workspace();
     let canonical = std::fs::canonicalize(ws.path()).unwrap();
-    let result = execute_shell("pwd", ws.path(), 5).await.unwrap();
+    let result = execute_shell("pwd", ws.path(), 5, &ResourceLimits::default(), None, None).await.unwrap();
     assert_eq!(result.stdout.trim(), canonical.to_str().unwrap());
     assert_eq!(result.exit_code, Some(0));
 }
@@ -54,11 +55,12 @@ async fn test_working_directory() {
 async fn test_timeout_kills_process() {
     let ws = setup_workspace();
     let start = Instant::now();
-    let result = execute_shell("sleep 60", ws.path(), 1).await.unwrap();
+    let result = execute_shell("sleep 60", ws.path(), 1, &ResourceLimits::default(), None, None).await.unwrap();
     let elapsed = start.elapsed();
 
     assert!(result.timed_out, "should report timed_out");
     assert_eq!(result.exit_code, None, "timed-out process should have no exit code");
+    assert_eq!(result.killed_by.as_deref(), Some("timeout"));
     assert!(
         elapsed.as_secs() < 5,
         "timeout should fire within ~2 seconds, took {:?}",
@@ -70,7 +72,7 @@ async fn test_timeout_kills_process() {
 async fn test_timeout_no_zombies() {
     let ws = setup_workspace();
     // Run a process that spawns children, then timeout.
-    let _result = execute_shell("sleep 60 & sleep 60 & wait", ws.path(), 1)
+    let _result = execute_shell("sleep 60 & sleep 60 & wait", ws.path(), 1, &ResourceLimits::default(), None, None)
         .await
         .unwrap();
 
@@ -83,6 +85,9 @@ async fn test_timeout_no_zombies() {
         &format!("ps -o pid,stat,comm -p $(pgrep -P {} 2>/dev/null || echo 0) 2>/dev/null | grep -c Z || true", std::process::id()),
         ws.path(),
         5,
+        &ResourceLimits::default(),
+        None,
+        None,
     )
     .await
     .unwrap();
@@ -91,6 +96,77 @@ async fn test_timeout_no_zombies() {
     assert_eq!(zombie_count, 0, "no zombie processes should remain");
 }
 
+// ============================================================
+// Resource limits
+// ============================================================
+
+#[tokio::test]
+async fn test_cpu_limit_kills_busy_loop() {
+    let ws = setup_workspace();
+    let limits = ResourceLimits { cpu_secs: Some(1), ..Default::default() };
+    let result = execute_shell("while :; do :; done", ws.path(), 10, &limits, None, None).await.unwrap();
+    assert!(!result.timed_out);
+    assert_eq!(result.killed_by.as_deref(), Some("cpu"));
+}
+
+#[tokio::test]
+async fn test_file_size_limit_stops_large_writes() {
+    let ws = setup_workspace();
+    let limits = ResourceLimits { file_size_bytes: Some(64 * 1024), ..Default::default() };
+    let result = execute_shell("head -c 1000000 /dev/zero > big.bin", ws.path(), 10, &limits, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.killed_by.as_deref(), Some("file_size"));
+    let written = std::fs::metadata(ws.path().join("big.bin")).unwrap().len();
+    assert!(written <= 64 * 1024, "wrote {written} bytes");
+}
+
+#[tokio::test]
+async fn test_open_files_limit_applies_to_children() {
+    let ws = setup_workspace();
+    let limits = ResourceLimits { open_files: Some(42), ..Default::default() };
+    let result = execute_shell("ulimit -n", ws.path(), 5, &limits, None, None).await.unwrap();
+    assert_eq!(result.stdout.trim(), "42");
+    assert_eq!(result.killed_by, None);
+}
+
+#[tokio::test]
+async fn test_crash_without_oom_message_is_not_blamed_on_memory_limit() {
+    let ws = setup_workspace();
+    let limits = ResourceLimits { memory_bytes: Some(512 * 1024 * 1024), ..Default::default() };
+    let result = execute_shell("kill -SEGV $$", ws.path(), 5, &limits, None, None).await.unwrap();
+    assert_eq!(result.exit_code, None);
+    assert_eq!(result.killed_by, None);
+}
+
+#[tokio::test]
+async fn test_crash_with_oom_message_is_blamed_on_memory_limit() {
+    let ws = setup_workspace();
+    let limits = ResourceLimits { memory_bytes: Some(512 * 1024 * 1024), ..Default::default() };
+    let result = execute_shell("echo 'std::bad_alloc' >&2; kill -ABRT $$", ws.path(), 5, &limits, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.killed_by.as_deref(), Some("memory"));
+}
+
+#[tokio::test]
+async fn test_limits_do_not_affect_normal_commands() {
+    let ws = setup_workspace();
+    let limits = ResourceLimits {
+        memory_bytes: Some(512 * 1024 * 1024),
+        cpu_secs: Some(5),
+        file_size_bytes: Some(1024 * 1024),
+        processes: None,
+        open_files: Some(256),
+    };
+    let result = execute_shell("echo hello > f.txt && cat f.txt", ws.path(), 5, &limits, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.stdout, "hello\n");
+    assert_eq!(result.exit_code, Some(0));
+    assert_eq!(result.killed_by, None);
+}
+
 // ============================================================
 // Mixed stdout and stderr
 // ============================================================
@@ -98,7 +174,7 @@ async fn test_timeout_no_zombies() {
 #[tokio::test]
 async fn test_mixed_stdout_stderr() {
     let ws = setup_workspace();
-    let result = execute_shell("echo out && echo err >&2", ws.path(), 5)
+    let result = execute_shell("echo out && echo err >&2", ws.path(), 5, &ResourceLimits::default(), None, None)
         .await
         .unwrap();
     assert_eq!(result.stdout, "out\n");
@@ -106,6 +182,41 @@ async fn test_mixed_stdout_stderr() {
     assert_eq!(result.exit_code, Some(0));
 }
 
+// ============================================================
+// Streaming output
+// ============================================================
+
+#[tokio::test]
+async fn test_output_streams_line_by_line() {
+    let ws = setup_workspace();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let result = execute_shell(
+        "echo one && echo two >&2 && printf 'three'",
+        ws.path(),
+        5,
+        &ResourceLimits::default(),
+        None,
+        Some(tx),
+    )
+    .await
+    .unwrap();
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    while let Ok((stream, line)) = rx.try_recv() {
+        match stream {
+            OutputStream::Stdout => stdout.push(line),
+            OutputStream::Stderr => stderr.push(line),
+        }
+    }
+    assert_eq!(stdout, ["one", "three"]);
+    assert_eq!(stderr, ["two"]);
+    // The collected result is the same as without a sink.
+    assert_eq!(result.stdout, "one\nthree");
+    assert_eq!(result.stderr, "two\n");
+    assert_eq!(result.exit_code, Some(0));
+}
+
 // ============================================================
 // Serialization
 // ============================================================
@@ -117,10 +228,12 @@ async fn test_exec_result_serializes() {
         stderr: "".into(),
         exit_code: Some(0),
         timed_out: false,
+        killed_by: None,
     };
     let json = serde_json::to_string(&result).unwrap();
     let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
     assert_eq!(parsed["stdout"], "output");
     assert_eq!(parsed["exit_code"], 0);
     assert_eq!(parsed["timed_out"], false);
+    assert!(parsed["killed_by"].is_null());
 }
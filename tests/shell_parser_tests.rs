@@ -0,0 +1,184 @@
+use ouro::safety::shell_parser::{invoked_commands, parse_commands, SimpleCommand};
+
+// ─── Helper ───────────────────────────────────────────────────────────
+
+/// Program names of every simple command in `input`, in parse order.
+fn programs(input: &str) -> Vec<String> {
+    parse_commands(input)
+        .unwrap()
+        .iter()
+        .filter_map(|c| c.program.as_ref().map(|p| p.text.clone()))
+        .collect()
+}
+
+fn single(input: &str) -> SimpleCommand {
+    let mut commands = parse_commands(input).unwrap();
+    assert_eq!(commands.len(), 1, "expected one command in {input:?}");
+    commands.remove(0)
+}
+
+// ─── Lists and pipelines ─────────────────────────────────────────────
+
+#[test]
+fn splits_pipelines_and_lists() {
+    assert_eq!(
+        programs("a | b && c || d; e & f\ng |& h"),
+        vec!["a", "b", "c", "d", "e", "f", "g", "h"]
+    );
+}
+
+#[test]
+fn descends_into_subshells_and_groups() {
+    assert_eq!(programs("(a; b) | { c; }"), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn skips_reserved_words_and_for_clauses() {
+    assert_eq!(
+        programs("if a; then b; elif c; then d; else e; fi"),
+        vec!["a", "b", "c", "d", "e"]
+    );
+    assert_eq!(programs("for x in one two; do a \"$x\"; done"), vec!["a"]);
+    assert_eq!(programs("while ! a; do b; done"), vec!["a", "b"]);
+}
+
+#[test]
+fn ignores_comments() {
+    assert_eq!(programs("a # b; c\nd"), vec!["a", "d"]);
+    assert_eq!(single("echo a#b").args[0].text, "a#b");
+}
+
+// ─── Words ───────────────────────────────────────────────────────────
+
+#[test]
+fn removes_quotes_and_escapes() {
+    let command = single(r#"grep "don't reboot" 'a b' c\ d"#);
+    let args: Vec<&str> = command.args.iter().map(|w| w.text.as_str()).collect();
+    assert_eq!(args, vec!["don't reboot", "a b", "c d"]);
+    assert!(command.args.iter().all(|w| w.quoted && !w.has_expansion));
+}
+
+#[test]
+fn escaped_program_name_is_unquoted() {
+    let command = single(r"s\udo ls");
+    assert_eq!(command.program.unwrap().text, "sudo");
+}
+
+#[test]
+fn separates_assignments_from_program() {
+    let command = single("A=1 B=\"x y\" run --flag");
+    assert_eq!(command.assignments.len(), 2);
+    assert_eq!(command.program.unwrap().text, "run");
+    assert_eq!(command.args[0].text, "--flag");
+
+    let bare = single("A=1");
+    assert!(bare.program.is_none());
+}
+
+#[test]
+fn flags_expansions() {
+    let command = single("$CMD ${HOME}/x \"$1\" '$literal' $((1 + 2))");
+    assert!(command.program.unwrap().has_expansion);
+    let flags: Vec<bool> = command.args.iter().map(|w| w.has_expansion).collect();
+    assert_eq!(flags, vec![true, true, false, true]);
+}
+
+// ─── Substitutions ───────────────────────────────────────────────────
+
+#[test]
+fn finds_commands_in_substitutions() {
+    assert_eq!(programs("echo $(a | b)"), vec!["a", "b", "echo"]);
+    assert_eq!(programs("echo \"`a`\""), vec!["a", "echo"]);
+    assert_eq!(programs("echo ${x:-$(a)} $(( $(b) + 1 ))"), vec!["a", "b", "echo"]);
+    assert_eq!(programs("diff <(a) >(b)"), vec!["a", "b", "diff"]);
+    assert_eq!(programs("echo $(echo $(a))"), vec!["a", "echo", "echo"]);
+}
+
+#[test]
+fn here_documents_expand_only_with_unquoted_delimiter() {
+    assert_eq!(programs("cat <<EOF\n$(a)\nEOF\nb"), vec!["cat", "a", "b"]);
+    assert_eq!(programs("cat <<'EOF'\n$(a)\nEOF\nb"), vec!["cat", "b"]);
+    assert_eq!(programs("cat <<-EOF\n\t`a`\n\tEOF"), vec!["cat", "a"]);
+}
+
+// ─── Redirections ────────────────────────────────────────────────────
+
+#[test]
+fn collects_redirections() {
+    let command = single("run < in.txt > out.txt 2>&1 >> log");
+    assert_eq!(command.program.unwrap().text, "run");
+    assert!(command.args.is_empty());
+    let redirects: Vec<(Option<u32>, &str, &str)> = command
+        .redirects
+        .iter()
+        .map(|r| (r.fd, r.op.as_str(), r.target.text.as_str()))
+        .collect();
+    assert_eq!(
+        redirects,
+        vec![
+            (None, "<", "in.txt"),
+            (None, ">", "out.txt"),
+            (Some(2), ">&", "1"),
+            (None, ">>", "log"),
+        ]
+    );
+}
+
+// ─── Invoked commands ────────────────────────────────────────────────
+
+/// Program names of every command `input` runs, wrappers unwrapped.
+fn invoked(input: &str) -> Vec<String> {
+    invoked_commands(parse_commands(input).unwrap())
+        .unwrap()
+        .iter()
+        .filter_map(|c| c.program.as_ref().map(|p| p.text.clone()))
+        .collect()
+}
+
+#[test]
+fn unwraps_wrappers_eval_and_shells() {
+    assert_eq!(invoked("env -u X A=1 nice -n 5 rm f"), vec!["env", "nice", "rm"]);
+    assert_eq!(invoked("timeout -s KILL 10 make"), vec!["timeout", "make"]);
+    assert_eq!(invoked("ls | xargs -n 1 -- rm"), vec!["ls", "xargs", "rm"]);
+    assert_eq!(invoked("eval 'a; b'"), vec!["eval", "a", "b"]);
+    assert_eq!(invoked("bash -ec 'a | b'"), vec!["bash", "a", "b"]);
+    assert_eq!(invoked("sh script.sh"), vec!["sh"]);
+    assert_eq!(
+        invoked("find . -exec rm {} \\; -execdir chmod +x {} +"),
+        vec!["find", "rm", "chmod"]
+    );
+    assert_eq!(invoked("/usr/bin/env sh -c \"sh -c 'c'\""), vec!["/usr/bin/env", "sh", "sh", "c"]);
+}
+
+#[test]
+fn dynamic_scripts_become_dynamic_programs() {
+    let commands = invoked_commands(parse_commands("eval \"$CMD\"").unwrap()).unwrap();
+    assert_eq!(commands.len(), 2);
+    assert!(commands[1].program.as_ref().unwrap().has_expansion);
+}
+
+#[test]
+fn limits_nesting_depth() {
+    let deep = "nice ".repeat(20) + "ls";
+    assert!(invoked_commands(parse_commands(&deep).unwrap()).is_err());
+}
+
+// ─── Errors ──────────────────────────────────────────────────────────
+
+#[test]
+fn rejects_malformed_or_unsupported_input() {
+    for input in [
+        "echo 'open",
+        "echo \"open",
+        "echo $(open",
+        "echo `open",
+        "(a",
+        "a )",
+        "cat <<EOF\nbody",
+        "case x in a) b;; esac",
+        "f() { a; }",
+        "echo >",
+    ] {
+        assert!(parse_commands(input).is_err(), "should reject: {input:?}");
+    }
+}
@@ -0,0 +1,241 @@
+mod common;
+
+use ouro::config::AppConfig;
+use ouro::exec::web::html_to_text;
+use ouro::exec::{fetch_url, WebLimits};
+use ouro::safety::domain_filter::DomainFilter;
+use ouro::safety::SafetyLayer;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// ─── Helper ───────────────────────────────────────────────────────────
+
+/// Canned response for a request path: (status line, headers, body).
+fn route(path: &str) -> (&'static str, Vec<(&'static str, String)>, String) {
+    match path {
+        "/page" => (
+            "200 OK",
+            vec![("Content-Type", "text/html; charset=utf-8".to_string())],
+            "<html><head><title>Test</title><script>var x = 1;</script></head>\
+             <body><h1>Hello</h1><p>Some <a href=\"/next\">linked</a> text.</p></body></html>"
+                .to_string(),
+        ),
+        "/plain" => (
+            "200 OK",
+            vec![("Content-Type", "text/plain".to_string())],
+            "just text".to_string(),
+        ),
+        "/big" => (
+            "200 OK",
+            vec![("Content-Type", "text/plain".to_string())],
+            "x".repeat(10_000),
+        ),
+        "/binary" => (
+            "200 OK",
+            vec![("Content-Type", "application/octet-stream".to_string())],
+            "\u{0}\u{1}".to_string(),
+        ),
+        "/redirect" => ("302 Found", vec![("Location", "/page".to_string())], String::new()),
+        "/loop" => ("302 Found", vec![("Location", "/loop".to_string())], String::new()),
+        "/escape" => (
+            "302 Found",
+            vec![("Location", "http://blocked.test/".to_string())],
+            String::new(),
+        ),
+        _ => ("404 Not Found", vec![("Content-Type", "text/plain".to_string())], "nope".to_string()),
+    }
+}
+
+/// Start a local HTTP/1.1 stand-in server and return its base URL.
+async fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else { return };
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut chunk).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&buf);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+                let (status, headers, body) = route(&path);
+                let mut response = format!("HTTP/1.1 {status}\r\n");
+                for (name, value) in &headers {
+                    response.push_str(&format!("{name}: {value}\r\n"));
+                }
+                response.push_str(&format!(
+                    "Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                ));
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    format!("http://{addr}")
+}
+
+fn limits(max_bytes: usize) -> WebLimits {
+    WebLimits {
+        max_bytes,
+        max_redirects: 3,
+        timeout_secs: 5,
+    }
+}
+
+fn open_filter() -> DomainFilter {
+    DomainFilter::new(&[], &[])
+}
+
+fn test_config(ws: &TempDir, blocked_domains: Vec<String>) -> AppConfig {
+    AppConfig {
+        web_timeout_secs: 5,
+        web_blocked_domains: blocked_domains,
+        ..common::test_config(ws.path())
+    }
+}
+
+// ============================================================
+// Fetching
+// ============================================================
+
+#[tokio::test]
+async fn test_fetch_converts_html() {
+    let base = start_server().await;
+    let result = fetch_url(&format!("{base}/page"), &open_filter(), &limits(10_000))
+        .await
+        .unwrap();
+
+    assert_eq!(result.status, 200);
+    assert!(result.content.contains("# Hello"));
+    assert!(result.content.contains("Some [linked](/next) text."));
+    assert!(!result.content.contains("var x"), "scripts should be dropped");
+    assert!(!result.truncated);
+}
+
+#[tokio::test]
+async fn test_fetch_returns_plain_text_and_error_statuses() {
+    let base = start_server().await;
+    let result = fetch_url(&format!("{base}/plain"), &open_filter(), &limits(10_000))
+        .await
+        .unwrap();
+    assert_eq!(result.content, "just text");
+
+    let missing = fetch_url(&format!("{base}/missing"), &open_filter(), &limits(10_000))
+        .await
+        .unwrap();
+    assert_eq!(missing.status, 404);
+}
+
+#[tokio::test]
+async fn test_fetch_truncates_to_byte_budget() {
+    let base = start_server().await;
+    let result = fetch_url(&format!("{base}/big"), &open_filter(), &limits(100))
+        .await
+        .unwrap();
+    assert_eq!(result.content.len(), 100);
+    assert!(result.truncated);
+}
+
+#[tokio::test]
+async fn test_fetch_rejects_binary_content() {
+    let base = start_server().await;
+    let err = fetch_url(&format!("{base}/binary"), &open_filter(), &limits(10_000))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("unsupported content type"));
+}
+
+// ============================================================
+// Redirects
+// ============================================================
+
+#[tokio::test]
+async fn test_fetch_follows_redirects() {
+    let base = start_server().await;
+    let result = fetch_url(&format!("{base}/redirect"), &open_filter(), &limits(10_000))
+        .await
+        .unwrap();
+    assert_eq!(result.url, format!("{base}/page"));
+    assert!(result.content.contains("# Hello"));
+}
+
+#[tokio::test]
+async fn test_fetch_stops_redirect_loops() {
+    let base = start_server().await;
+    let err = fetch_url(&format!("{base}/loop"), &open_filter(), &limits(10_000))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("too many redirects"));
+}
+
+#[tokio::test]
+async fn test_redirect_to_blocked_domain_is_refused() {
+    let base = start_server().await;
+    let filter = DomainFilter::new(&[], &["blocked.test".to_string()]);
+    let err = fetch_url(&format!("{base}/escape"), &filter, &limits(10_000))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("redirect blocked"));
+}
+
+// ============================================================
+// SafetyLayer integration
+// ============================================================
+
+#[tokio::test]
+async fn test_safety_layer_blocks_and_logs_denied_domain() {
+    let ws = tempfile::tempdir().unwrap();
+    let config = test_config(&ws, vec!["127.0.0.1".to_string()]);
+    let layer = SafetyLayer::new(&config).unwrap();
+
+    let base = start_server().await;
+    let err = layer.fetch(&format!("{base}/page")).await.unwrap_err();
+    assert!(err.to_string().contains("blocked"));
+
+    let log = std::fs::read_to_string(ws.path().join("security.log")).unwrap();
+    assert!(log.contains("web_fetch http://127.0.0.1"));
+}
+
+#[tokio::test]
+async fn test_safety_layer_fetches_allowed_url() {
+    let ws = tempfile::tempdir().unwrap();
+    let config = test_config(&ws, vec!["blocked.test".to_string()]);
+    let layer = SafetyLayer::new(&config).unwrap();
+
+    let base = start_server().await;
+    let result = layer.fetch(&format!("{base}/plain")).await.unwrap();
+    assert_eq!(result.content, "just text");
+}
+
+// ============================================================
+// HTML conversion
+// ============================================================
+
+#[test]
+fn test_html_to_text_lists_and_entities() {
+    let text = html_to_text(
+        "<ul><li>One &amp; two</li><li>Three&nbsp;&lt;3&#33;</li></ul><p>a < b</p>",
+    );
+    assert_eq!(text, "- One & two\n- Three <3!\n\na < b");
+}
+
+#[test]
+fn test_html_to_text_preserves_pre_and_drops_comments() {
+    let text = html_to_text("<!-- hidden --><pre>fn main() {\n    x\n}</pre><style>p{}</style>done");
+    assert_eq!(text, "```\nfn main() {\n    x\n}\n```\n\ndone");
+}
+
+#[test]
+fn test_html_to_text_collapses_whitespace() {
+    let text = html_to_text("<div>\n  Lots   of\n\n space  </div>\n\n\n<div>next</div>");
+    assert_eq!(text, "Lots of space\n\nnext");
+}
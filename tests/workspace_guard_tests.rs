@@ -1,3 +1,4 @@
+use ouro::safety::protected::ProtectedPaths;
 use ouro::safety::workspace::WorkspaceGuard;
 use std::fs;
 use std::path::Path;
@@ -195,6 +196,71 @@ fn canonical_root_returns_resolved_path() {
     assert_eq!(guard.canonical_root(), expected.as_path());
 }
 
+// ─── Protected paths ────────────────────────────────────────────────
+
+fn protected(patterns: &[&str]) -> ProtectedPaths {
+    let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+    ProtectedPaths::new(&patterns).unwrap()
+}
+
+#[test]
+fn protected_globs_match_workspace_relative_paths() {
+    let paths = protected(&["ouro.toml", "archive/**", "**/*.lock", "notes/?.md"]);
+
+    assert_eq!(paths.matching_pattern("ouro.toml"), Some("ouro.toml"));
+    assert_eq!(paths.matching_pattern("sub/ouro.toml"), None);
+    assert_eq!(paths.matching_pattern("archive"), Some("archive/**"));
+    assert_eq!(paths.matching_pattern("archive/a/b.txt"), Some("archive/**"));
+    assert_eq!(paths.matching_pattern("archive.md"), None);
+    assert_eq!(paths.matching_pattern("Cargo.lock"), Some("**/*.lock"));
+    assert_eq!(paths.matching_pattern("deep/dir/x.lock"), Some("**/*.lock"));
+    assert_eq!(paths.matching_pattern("notes/a.md"), Some("notes/?.md"));
+    assert_eq!(paths.matching_pattern("notes/ab.md"), None);
+}
+
+#[test]
+fn rejects_absolute_or_escaping_protected_patterns() {
+    for bad in ["/etc/passwd", "../ouro.toml", ""] {
+        assert!(ProtectedPaths::new(&[bad.to_string()]).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn blocks_write_to_protected_path() {
+    let tmp = setup_workspace();
+    let guard = WorkspaceGuard::new(tmp.path())
+        .unwrap()
+        .with_protected(protected(&["ouro.toml", "archive/**"]));
+
+    let config = tmp.path().join("ouro.toml");
+    fs::write(&config, "[safety]").unwrap();
+    assert_eq!(guard.is_write_allowed(&config).unwrap(), false);
+
+    fs::create_dir_all(tmp.path().join("archive")).unwrap();
+    let archived = tmp.path().join("archive").join("new.md");
+    assert_eq!(guard.is_write_allowed(&archived).unwrap(), false);
+
+    let other = tmp.path().join("notes.md");
+    assert_eq!(guard.is_write_allowed(&other).unwrap(), true);
+}
+
+#[cfg(unix)]
+#[test]
+fn blocks_write_through_symlink_to_protected_path() {
+    let tmp = setup_workspace();
+    let guard = WorkspaceGuard::new(tmp.path())
+        .unwrap()
+        .with_protected(protected(&["ouro.toml"]));
+
+    let config = tmp.path().join("ouro.toml");
+    fs::write(&config, "[safety]").unwrap();
+    let link = tmp.path().join("innocent.txt");
+    std::os::unix::fs::symlink(&config, &link).unwrap();
+
+    assert_eq!(guard.protected_pattern(&link), Some("ouro.toml"));
+    assert_eq!(guard.is_write_allowed(&link).unwrap(), false);
+}
+
 // ─── Utility ────────────────────────────────────────────────────────
 
 fn dirs_home() -> Option<std::path::PathBuf> {